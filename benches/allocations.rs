@@ -0,0 +1,134 @@
+//! Compares the parsing done by the GUI and the borrowed parsing of the saved pages
+//! in `tests/fixtures`. Yle pages of the GUI keep the fetched html and borrow it,
+//! the owned parsing that copies the html and the texts is measured as the baseline.
+//! DR pages are still copied into owned ones by the GUI.
+//!
+//! Run with `cargo bench --bench allocations`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use yle_tekstitv_gui::parser::{DrText, HtmlLoader, HtmlParser, TeleText, YleText};
+
+const ROUNDS: u32 = 1000;
+
+/// Counts the allocations so the parsers can be compared without external tools
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+struct Measurement {
+    allocations: usize,
+    bytes: usize,
+    time: Duration,
+}
+
+/// Run the parser `ROUNDS` times and report the allocations of a single round
+fn measure<F: FnMut()>(mut parse: F) -> Measurement {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        parse();
+    }
+
+    Measurement {
+        allocations: (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ROUNDS as usize,
+        bytes: (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) / ROUNDS as usize,
+        time: start.elapsed() / ROUNDS,
+    }
+}
+
+fn report(name: &str, measurements: &[(&str, Measurement)]) {
+    println!("{name}");
+    for (label, measurement) in measurements {
+        println!(
+            "    {label:<10}{:>6} allocations {:>8} bytes {:>10.2?}",
+            measurement.allocations, measurement.bytes, measurement.time
+        );
+    }
+}
+
+fn fixtures(dir: &str, is_page: fn(&str) -> bool) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = fs::read_dir(format!("tests/fixtures/{dir}"))
+        .unwrap_or_else(|_| panic!("Can't find fixtures in \"{}\"", dir))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|ext| ext == "htm").unwrap_or(false))
+        .map(|path| {
+            let data = fs::read_to_string(&path).unwrap();
+            (path.display().to_string(), data)
+        })
+        .filter(|(name, html)| {
+            // Error pages like 404.htm are fixtures for the parse errors
+            let is_page = is_page(html);
+            if !is_page {
+                println!("{name}\n    skipped, not a page");
+            }
+            is_page
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn main() {
+    for (name, html) in fixtures("yle_text", |html| TeleText::parse_str(html).is_ok()) {
+        // Earlier GUI copied the response into the loader and the page into an owned one
+        let owned = measure(|| {
+            let response = html.clone();
+            TeleText::parse_str(&response).unwrap().into_owned();
+        });
+        // The response is shared by the store and the page
+        let shared: Arc<str> = html.as_str().into();
+        let gui = measure(|| {
+            let loader = HtmlLoader {
+                page_data: shared.clone(),
+            };
+            <YleText as HtmlParser>::new().parse(loader).unwrap();
+        });
+        let borrowed = measure(|| {
+            TeleText::parse_str(&html).unwrap();
+        });
+        let measurements = [("owned", owned), ("gui", gui), ("borrowed", borrowed)];
+        report(&name, &measurements);
+    }
+
+    for (name, html) in fixtures("dr_text", |html| DrText::parse_str(html).is_ok()) {
+        let shared: Arc<str> = html.as_str().into();
+        let gui = measure(|| {
+            let loader = HtmlLoader {
+                page_data: shared.clone(),
+            };
+            DrText::new().parse(loader).unwrap();
+        });
+        let borrowed = measure(|| {
+            DrText::parse_str(&html).unwrap();
+        });
+        report(&name, &[("gui", gui), ("borrowed", borrowed)]);
+    }
+}
@@ -0,0 +1,14 @@
+//! Run with `cargo +nightly fuzz run page_str`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use yle_tekstitv_gui::parser::{DrText, SvtText, TeleText, YleImage};
+use yle_tekstitv_gui::TelePager;
+
+fuzz_target!(|page: &str| {
+    // Pages come from links of the fetched pages so they can be anything
+    let _ = TeleText::from_page_str(page);
+    let _ = YleImage::from_page_str(page);
+    let _ = SvtText::from_page_str(page);
+    let _ = DrText::from_page_str(page);
+});
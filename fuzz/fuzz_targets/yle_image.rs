@@ -0,0 +1,10 @@
+//! Run with `cargo +nightly fuzz run yle_image fuzz/corpus/yle_image tests/fixtures/yle_image`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use yle_tekstitv_gui::parser::{HtmlLoader, HtmlParser, YleImage};
+
+fuzz_target!(|data: &[u8]| {
+    let json = String::from_utf8_lossy(data).into_owned();
+    let _ = YleImage::new().parse(HtmlLoader { page_data: json });
+});
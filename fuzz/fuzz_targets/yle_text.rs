@@ -0,0 +1,14 @@
+//! Run with `cargo +nightly fuzz run yle_text fuzz/corpus/yle_text tests/fixtures/yle_text`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use yle_tekstitv_gui::parser::{HtmlLoader, HtmlParser, TeleText};
+
+fuzz_target!(|data: &[u8]| {
+    // Responses are decoded lossily, like `reqwest::Response::text` does
+    let html = String::from_utf8_lossy(data);
+    let _ = TeleText::parse_str(&html);
+    let _ = TeleText::new().parse(HtmlLoader {
+        page_data: html.into_owned(),
+    });
+});
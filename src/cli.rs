@@ -0,0 +1,120 @@
+use crate::gui::{client, FetchError};
+use crate::parser::{
+    DrText, ExportFormat, HtmlColors, HtmlLoader, HtmlParser, SvtText, TextPage, YleText,
+};
+use crate::{TelePage, TelePager};
+
+const USAGE: &str = "Usage: tekstitv-gui [--export FORMAT PAGE [--reader READER] [--output FILE]]
+
+Without arguments the reader window is opened.
+
+    --export FORMAT PAGE  Print the page as text, markdown or html, e.g. --export md 190/2
+    --reader READER       Load the page from yle (default), svt or dr
+    --output FILE         Write the page to the file instead";
+
+struct ExportArgs {
+    format: ExportFormat,
+    page: TelePage,
+    reader: String,
+    output: Option<String>,
+}
+
+/// Page from "190", "190/2" or "190_0002"
+fn parse_page(page: &str) -> Option<TelePage> {
+    let (page, sub_page) = match page.split_once(['/', '_']) {
+        Some((page, sub_page)) => (page, sub_page.parse::<i32>().ok()?),
+        None => (page, 1),
+    };
+
+    Some(TelePage::new(page.parse::<i32>().ok()?, sub_page))
+}
+
+impl ExportArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let (format, page) = match args {
+            [format, page, ..] => (format, page),
+            _ => return Err(USAGE.to_string()),
+        };
+
+        let mut export = Self {
+            format: ExportFormat::from_name(format)
+                .ok_or_else(|| format!("Unknown format: {format}"))?,
+            page: parse_page(page).ok_or_else(|| format!("Invalid page: {page}"))?,
+            reader: "yle".to_string(),
+            output: None,
+        };
+
+        let mut options = args[2..].iter();
+        while let Some(option) = options.next() {
+            let value = options
+                .next()
+                .ok_or_else(|| format!("{option} needs a value"))?;
+            match option.as_str() {
+                "--reader" => export.reader = value.clone(),
+                "--output" => export.output = Some(value.clone()),
+                _ => return Err(format!("Unknown option: {option}\n\n{USAGE}")),
+            }
+        }
+
+        Ok(export)
+    }
+}
+
+fn export_page<T: HtmlParser + TelePager + TextPage>(args: &ExportArgs) -> Result<String, String> {
+    let page = &args.page;
+    let response = client()
+        .get(&T::to_full_page(page), None)
+        .map_err(|failure| failure.error.message(page))?;
+    let parsed = T::new()
+        .parse(HtmlLoader {
+            page_data: response.body.into(),
+        })
+        .map_err(|err| FetchError::from(err).message(page))?;
+
+    let colors = HtmlColors::default();
+    Ok(parsed
+        .teletext()
+        .export(args.format, page.page, page.sub_page, &colors))
+}
+
+fn export(args: &[String]) -> Result<(), String> {
+    let args = ExportArgs::parse(args)?;
+    let text = match args.reader.as_str() {
+        "yle" => export_page::<YleText>(&args)?,
+        "svt" => export_page::<SvtText>(&args)?,
+        "dr" => export_page::<DrText>(&args)?,
+        reader => return Err(format!("Unknown reader: {reader}")),
+    };
+
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, text).map_err(|err| format!("Cannot write {path}: {err}"))
+        }
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+/// Run the command given in the arguments. Returns the exit code,
+/// or None if the reader window should be opened
+pub fn run(args: &[String]) -> Option<i32> {
+    let result = match args.first().map(String::as_str) {
+        None => return None,
+        Some("--export") => export(&args[1..]),
+        Some("--help") | Some("-h") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(arg) => Err(format!("Unknown argument: {arg}\n\n{USAGE}")),
+    };
+
+    match result {
+        Ok(_) => Some(0),
+        Err(err) => {
+            eprintln!("{err}");
+            Some(1)
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Oldest versions are removed when a page has more than this many
+pub(super) const MAX_VERSIONS: usize = 200;
+
+/// Response of the page as it was at the fetch time
+pub struct ArchivedPage {
+    pub body: Arc<str>,
+    pub time: chrono::DateTime<chrono::Local>,
+    /// Hash of the content the version was compared with
+    hash: u64,
+}
+
+/// Every different version of the fetched pages by their url, oldest first
+#[derive(Default)]
+pub struct PageArchive {
+    pages: HashMap<String, Vec<ArchivedPage>>,
+}
+
+impl PageArchive {
+    /// Add the version unless its content is the same as in the latest version.
+    /// `content` is what the user sees, since the response can change without the page changing
+    pub fn add(
+        &mut self,
+        url: &str,
+        body: Arc<str>,
+        content: &str,
+        time: chrono::DateTime<chrono::Local>,
+    ) -> bool {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let versions = self.pages.entry(url.to_string()).or_default();
+        if versions.last().is_some_and(|latest| latest.hash == hash) {
+            return false;
+        }
+
+        if versions.len() == MAX_VERSIONS {
+            versions.remove(0);
+        }
+        versions.push(ArchivedPage { body, time, hash });
+        true
+    }
+
+    pub fn versions(&self, url: &str) -> &[ArchivedPage] {
+        self.pages.get(url).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{Duration, Local};
+
+    const URL: &str = "https://yle.fi/tekstitv/txt/190_0001.htm";
+
+    #[test]
+    fn archive_skips_unchanged_versions() {
+        let mut archive = PageArchive::default();
+        let time = Local::now();
+
+        assert!(archive.add(URL, "<html>1-3</html>".into(), "1-3", time));
+        // Response changed but the content the user sees did not
+        assert!(!archive.add(
+            URL,
+            "<html> 1-3</html>".into(),
+            "1-3",
+            time + Duration::minutes(1)
+        ));
+        assert!(archive.add(
+            URL,
+            "<html>2-3</html>".into(),
+            "2-3",
+            time + Duration::minutes(2)
+        ));
+        // Returning to an earlier score is a new version
+        assert!(archive.add(
+            URL,
+            "<html>1-3</html>".into(),
+            "1-3",
+            time + Duration::minutes(3)
+        ));
+
+        let versions = archive.versions(URL);
+        assert_eq!(versions.len(), 3);
+        assert_eq!(&*versions[0].body, "<html>1-3</html>");
+        assert_eq!(versions[1].time, time + Duration::minutes(2));
+        assert!(archive
+            .versions("https://yle.fi/tekstitv/txt/100_0001.htm")
+            .is_empty());
+    }
+}
@@ -1,21 +1,57 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     rc::Rc,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
 
 #[cfg(not(target_arch = "wasm32"))]
 use std::{thread, time::Duration};
 
-use egui::{self, InputState, Key::*, PointerState};
+use egui::{self, Color32, InputState, Key::*, PointerState};
+use image::ImageResult;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::JsCast;
 
-use crate::parser::{HtmlItem, HtmlLink, HtmlLoader, HtmlParser};
+use super::archive::PageArchive;
+use super::crawl::{CrawlProgress, SourceCrawl};
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+use super::database::{self, PageDatabase, StoredPage};
+use super::print::{PrintJob, PrintRange};
+use super::search::{SearchIndex, SearchResult};
+use super::selection::TextSelection;
+use super::watch::PageWatch;
+use crate::parser::{
+    common::{ParseErr, ParserResult},
+    print, ExportFormat, Fastext, FastextKey, HtmlColors, HtmlItem, HtmlLink, HtmlLoader,
+    HtmlParser, ImageFormat, PrintPage, ProviderConfig, ProviderPage,
+};
 
+/// Network and server errors are retried automatically after 2, 4, 8... seconds
+pub const MAX_RETRIES: u32 = 6;
+/// Cached pages younger than this are shown without loading them again
+const CACHE_MAX_AGE_SECS: i64 = 60;
+/// Linked pages are prefetched one at a time, at most this many per page
+const MAX_PREFETCH: usize = 8;
+#[cfg(not(target_arch = "wasm32"))]
+const PREFETCH_DELAY: Duration = Duration::from_millis(250);
+/// Crawler loads at most one page per second so it doesn't burden the site
+const CRAWL_DELAY_MS: u64 = 1000;
+/// Rows changed in a refresh are highlighted this long
+const HIGHLIGHT_SECS: i64 = 60;
 const NUM_KEYS: [egui::Key; 10] = [Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9];
+/// Fastext keys can be used with either function keys or the first letter of the colour
+const FASTEXT_KEYS: [(egui::Key, egui::Key, FastextKey); 4] = [
+    (F1, R, FastextKey::Red),
+    (F2, G, FastextKey::Green),
+    (F3, Y, FastextKey::Yellow),
+    (F4, B, FastextKey::Cyan),
+];
 
 /// Return None if number is not pressed
 pub fn input_to_num(input: &InputState) -> Option<i32> {
@@ -28,18 +64,79 @@ pub fn input_to_num(input: &InputState) -> Option<i32> {
     None
 }
 
+/// Return None if fastext key is not pressed
+pub fn input_to_fastext(input: &InputState) -> Option<FastextKey> {
+    for (fkey, letter, key) in FASTEXT_KEYS.iter() {
+        if input.key_released(*fkey) || input.key_released(*letter) {
+            return Some(*key);
+        }
+    }
+
+    None
+}
+
 pub trait PageDraw<'a, T: HtmlParser + TelePager + Send + 'static> {
     fn draw(&mut self);
     fn new(ui: &'a mut egui::Ui, ctx: &'a mut GuiContext<T>) -> Self;
 }
 
 pub trait TelePager {
+    /// Key of the reader in the store, it's saved in the database so it must not change
+    const SOURCE: &'static str;
+
     fn to_full_page(page: &TelePage) -> String;
     fn to_page_str(page: &TelePage) -> String;
-    fn from_page_str(page: &str) -> TelePage;
+    /// Page from a link in the "100_0001" format. None if the link is not a teletext page
+    fn from_page_str(page: &str) -> Option<TelePage>;
+    fn fastext(&self) -> &Fastext<'_>;
+
+    /// Pages linked from the navigation, prefetched after the page is shown
+    fn prefetch_pages(&self) -> Vec<TelePage> {
+        Vec::new()
+    }
+
+    /// Rows that are different in the `previous` version of the page
+    fn changed_rows(&self, _previous: &Self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// Text of the page, None if the page is not text
+    fn plain_text(&self) -> Option<String> {
+        None
+    }
+
+    /// Parsed page as json, stored in the database with the response
+    fn to_json(&self) -> Option<String> {
+        None
+    }
+
+    /// Title and lines of the page for the search index, None if the page has no text
+    fn search_text(&self) -> Option<(String, Vec<String>)> {
+        None
+    }
+
+    /// Number of sub-pages if the page lists them
+    fn sub_page_count(&self) -> Option<i32> {
+        None
+    }
+
+    /// Sub-page shown after `current` in the carousel, wraps back to the first sub-page
+    fn next_sub_page(&self, current: &TelePage) -> Option<TelePage> {
+        let count = self.sub_page_count()?;
+        if count < 2 {
+            return None;
+        }
+
+        let sub_page = if current.sub_page >= count {
+            1
+        } else {
+            current.sub_page + 1
+        };
+        Some(TelePage::new(current.page, sub_page))
+    }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct TelePage {
     pub page: i32,
     pub sub_page: i32,
@@ -111,6 +208,9 @@ pub struct GuiWorker {
     should_refresh: Arc<Mutex<bool>>,
     #[cfg(target_arch = "wasm32")]
     interval_handle: Option<i32>,
+    /// Called by the interval, it's dropped after the interval is cleared
+    #[cfg(target_arch = "wasm32")]
+    interval_fn: Option<Closure<dyn FnMut()>>,
 }
 
 impl GuiWorker {
@@ -124,6 +224,8 @@ impl GuiWorker {
             interval: Arc::new(Mutex::new(interval)),
             #[cfg(target_arch = "wasm32")]
             interval_handle: None,
+            #[cfg(target_arch = "wasm32")]
+            interval_fn: None,
         }
     }
 
@@ -159,24 +261,22 @@ impl GuiWorker {
         let interval = self.interval.clone();
         let should_refresh = self.should_refresh.clone();
 
-        let interval_fn = Some(Closure::wrap(Box::new(move || {
+        let interval_fn = Closure::wrap(Box::new(move || {
             let mut refresh = should_refresh.lock().unwrap();
             *refresh = true;
-        }) as Box<dyn FnMut()>));
+        }) as Box<dyn FnMut()>);
 
         let handle = web_sys::window()
             .expect("no global `window` exists")
             .set_interval_with_callback_and_timeout_and_arguments_0(
-                interval_fn.as_ref().unwrap().as_ref().unchecked_ref(),
+                interval_fn.as_ref().unchecked_ref(),
                 (*interval.lock().unwrap() as i32) * 1000,
             )
             .unwrap();
-        // clone, forget avoids Uncaught Error: closure invoked recursively or after being dropped
-        // https://stackoverflow.com/a/53219594
-        // https://rustwasm.github.io/wasm-bindgen/examples/closures.html
-        // FIXME: fix the memory leak
-        interval_fn.unwrap().forget();
+        // Dropping the closure while the interval can call it fails with
+        // "closure invoked recursively or after being dropped", so it's kept until `stop`
         self.interval_handle = Some(handle);
+        self.interval_fn = Some(interval_fn);
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -202,6 +302,7 @@ impl GuiWorker {
                 .clear_interval_with_handle(interval);
         }
         self.interval_handle = None;
+        self.interval_fn = None;
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -217,6 +318,18 @@ impl GuiWorker {
         self.start();
     }
 
+    /// Start counting the interval from the beginning
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restart(&mut self) {
+        *self.timer.lock().unwrap() = 0;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn restart(&mut self) {
+        self.stop();
+        self.start();
+    }
+
     pub fn should_refresh(&self) -> bool {
         *self.should_refresh.lock().unwrap()
     }
@@ -239,24 +352,475 @@ impl Default for GuiWorker {
     }
 }
 
+/// Reason why the page couldn't be shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchError {
+    /// Page doesn't exist
+    NotFound,
+    /// Provider is under maintenance or otherwise unavailable
+    Maintenance,
+    /// Request failed, e.g. there's no network connection
+    Network,
+    /// Server didn't respond in time
+    Timeout,
+    /// Server responded with an unexpected status code
+    Http(u16),
+    /// Page couldn't be parsed, the provider has probably changed the format
+    InvalidPage,
+}
+
+impl From<ParseErr> for FetchError {
+    fn from(err: ParseErr) -> Self {
+        match err {
+            ParseErr::NotFound => Self::NotFound,
+            ParseErr::Maintenance => Self::Maintenance,
+            ParseErr::InvalidPage => Self::InvalidPage,
+        }
+    }
+}
+
+impl FetchError {
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::NotFound => "Page not found",
+            Self::Maintenance => "Service unavailable",
+            Self::Network => "Network error",
+            Self::Timeout => "Timeout",
+            Self::Http(_) => "HTTP error",
+            Self::InvalidPage => "Invalid page",
+        }
+    }
+
+    /// Errors that can go away by loading the page again
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Network | Self::Timeout | Self::Http(500..=599))
+    }
+
+    /// Message shown to the user instead of the page
+    pub fn message(&self, page: &TelePage) -> String {
+        match self {
+            Self::NotFound => format!("Page {} does not exist", page.page),
+            Self::Maintenance => "Service is under maintenance, try again later".to_string(),
+            Self::Network => "Network unreachable".to_string(),
+            Self::Timeout => "Loading the page timed out".to_string(),
+            Self::Http(status) => format!("Server responded with status {}", status),
+            Self::InvalidPage => format!("Page format changed, page {} can't be shown", page.page),
+        }
+    }
+}
+
+/// Details of a failed load shown in the error page
+#[derive(Debug, Clone)]
+pub struct FetchFailure {
+    pub error: FetchError,
+    pub url: String,
+    /// Http status code if the server responded
+    pub status: Option<u16>,
+    pub time: chrono::DateTime<chrono::Local>,
+    /// How many times the load has been retried automatically
+    pub attempt: u32,
+    /// Whether an older copy of the page can be shown instead, checked when the
+    /// load fails so the error page doesn't query the store every frame
+    pub has_cached: bool,
+}
+
+impl FetchFailure {
+    pub fn new(error: FetchError, url: &str, status: Option<u16>) -> Self {
+        Self {
+            error,
+            url: url.to_string(),
+            status,
+            time: chrono::Local::now(),
+            attempt: 0,
+            has_cached: false,
+        }
+    }
+
+    /// Time of the next automatic retry. Only transient errors are retried since
+    /// loading the page again won't help with the other errors
+    pub fn retry_at(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        if !self.error.is_transient() || self.attempt >= MAX_RETRIES {
+            return None;
+        }
+
+        Some(self.time + chrono::Duration::seconds(2_i64.pow(self.attempt + 1)))
+    }
+}
+
+/// Actions of the error page. They change the fetch state so they are
+/// handled after the page is drawn
+pub enum ErrorAction {
+    /// Load the page again, `attempt` is 0 when the user asked for it
+    Retry {
+        attempt: u32,
+    },
+    Back,
+    Home,
+    OpenCached,
+}
+
 pub enum FetchState<T: HtmlParser> {
     /// No fetch has been done, so the state is uninitialised
     Init,
-    InitFailed,
+    InitFailed(FetchFailure),
     Fetching,
-    // TODO: error codes
-    Error,
+    Error(FetchFailure),
     Complete(T),
 }
 
+/// Successful response of a page request
+#[derive(Debug)]
+pub struct PageResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Missing pages can also be told from the status code. Other responses that aren't
+/// successful are `FetchError::Http` errors, maintenance breaks are found by the parsers
+/// from the maintenance page
+pub fn check_status(site: &str, status: reqwest::StatusCode) -> Result<(), FetchFailure> {
+    let error = if status.is_success() {
+        return Ok(());
+    } else if status == reqwest::StatusCode::NOT_FOUND {
+        FetchError::NotFound
+    } else {
+        FetchError::Http(status.as_u16())
+    };
+
+    Err(FetchFailure::new(error, site, Some(status.as_u16())))
+}
+
+/// How the page is loaded
+#[derive(Clone, Copy)]
+enum LoadKind {
+    /// User opened the page, a recently loaded copy is shown if there's one
+    Navigate,
+    /// Automatic or user asked retry of a failed load, with the number of the automatic retry
+    Retry(u32),
+    /// Reload by the refresh interval. The old version is shown until the new one
+    /// is loaded so the changes can be found
+    Refresh,
+}
+
+/// When the shown page was loaded and which of its rows changed in the last refresh
+#[derive(Default)]
+pub struct PageUpdate {
+    pub loaded: Option<chrono::DateTime<chrono::Local>>,
+    pub changed_rows: Vec<usize>,
+    pub changed_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Fetch times of the archived versions of the page, oldest first. Read when the
+    /// page is loaded so the timeline doesn't query the store every frame
+    pub versions: Vec<chrono::DateTime<chrono::Local>>,
+}
+
+impl PageUpdate {
+    /// Text shown in the header, e.g. "updated 12:34"
+    pub fn loaded_label(&self) -> Option<String> {
+        let loaded = self.loaded?;
+        Some(format!("updated {}", loaded.format("%H:%M")))
+    }
+
+    /// Rows that changed recently enough to be highlighted
+    pub fn highlighted_rows(&self) -> &[usize] {
+        match self.changed_at {
+            Some(time) if Self::is_recent(time) => &self.changed_rows,
+            _ => &[],
+        }
+    }
+
+    fn is_recent(time: chrono::DateTime<chrono::Local>) -> bool {
+        chrono::Local::now() - time < chrono::Duration::seconds(HIGHLIGHT_SECS)
+    }
+
+    fn page_loaded(&mut self, time: chrono::DateTime<chrono::Local>, changed_rows: Vec<usize>) {
+        self.loaded = Some(time);
+        // Highlights of the previous refresh are kept if nothing changed since
+        if !changed_rows.is_empty() {
+            self.changed_rows = changed_rows;
+            self.changed_at = Some(time);
+        }
+    }
+
+    /// Archived versions are kept since they're read again only when the page is loaded
+    fn clear(&mut self, loaded: chrono::DateTime<chrono::Local>) {
+        *self = Self {
+            loaded: Some(loaded),
+            versions: std::mem::take(&mut self.versions),
+            ..Default::default()
+        };
+    }
+}
+
+/// Response of a loaded or prefetched page
+#[derive(Clone)]
+pub struct CachedPage {
+    pub body: Arc<str>,
+    pub time: chrono::DateTime<chrono::Local>,
+}
+
+impl CachedPage {
+    fn is_fresh(&self) -> bool {
+        chrono::Local::now() - self.time < chrono::Duration::seconds(CACHE_MAX_AGE_SECS)
+    }
+}
+
+/// Pages loaded by the readers of every tab. The app owns them and the store of
+/// each reader shares them, so the search and the archive have the pages of every tab.
+/// With the `sqlite` feature, which is on by default, the archive and the search index
+/// are in the database instead, and pages loaded in the earlier sessions are used as
+/// cached copies
+#[derive(Default)]
+pub struct SharedPages {
+    /// Responses of the loaded pages by their url. Recent copies are shown instead of
+    /// loading the page again, and the last copy when loading the page fails
+    cache: Mutex<HashMap<String, CachedPage>>,
+    index: Mutex<SearchIndex>,
+    archive: Mutex<PageArchive>,
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    database: Option<PageDatabase>,
+}
+
+impl SharedPages {
+    /// Pages kept in the database if it can be opened, otherwise in memory
+    pub fn new() -> Self {
+        Self {
+            #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+            database: database::open(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Every valid page that is loaded by the reader, in the foreground or background,
+/// is kept in the pages shared with the other readers
+#[derive(Clone)]
+pub struct PageStore {
+    pages: Arc<SharedPages>,
+    /// Identifies the reader in the database and the search index, `TelePager::SOURCE`
+    /// or the name of the provider
+    pub source: String,
+}
+
+impl PageStore {
+    pub fn new(source: &str, pages: Arc<SharedPages>) -> Self {
+        Self {
+            pages,
+            source: source.to_string(),
+        }
+    }
+
+    fn add<T: TelePager>(&self, site: &str, page: &TelePage, parsed: &T, body: Arc<str>) {
+        let time = chrono::Local::now();
+        // Image pages are compared by the response since they have no plain text
+        let content = parsed.plain_text();
+        let content = content.as_deref().unwrap_or(&body);
+        if !self.add_to_database(site, page, parsed, &body, content, time) {
+            self.pages
+                .archive
+                .lock()
+                .unwrap()
+                .add(site, body.clone(), content, time);
+            if let Some((title, lines)) = parsed.search_text() {
+                self.pages
+                    .index
+                    .lock()
+                    .unwrap()
+                    .add(&self.source, page, title, lines);
+            }
+        }
+
+        let cached = CachedPage { body, time };
+        self.pages
+            .cache
+            .lock()
+            .unwrap()
+            .insert(site.to_string(), cached);
+    }
+
+    /// Returns false if there's no database
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    fn add_to_database<T: TelePager>(
+        &self,
+        site: &str,
+        page: &TelePage,
+        parsed: &T,
+        body: &str,
+        content: &str,
+        time: chrono::DateTime<chrono::Local>,
+    ) -> bool {
+        let database = match &self.pages.database {
+            Some(database) => database,
+            None => return false,
+        };
+
+        let stored = StoredPage {
+            source: &self.source,
+            url: site,
+            page,
+            body,
+            content,
+            model: parsed.to_json(),
+            text: parsed.search_text(),
+            time,
+        };
+        if let Err(err) = database.add(&stored) {
+            log::warn!("Cannot store page {}: {}", site, err);
+        }
+        true
+    }
+
+    #[cfg(not(all(feature = "sqlite", not(target_arch = "wasm32"))))]
+    fn add_to_database<T: TelePager>(
+        &self,
+        _site: &str,
+        _page: &TelePage,
+        _parsed: &T,
+        _body: &str,
+        _content: &str,
+        _time: chrono::DateTime<chrono::Local>,
+    ) -> bool {
+        false
+    }
+
+    /// Latest copy of the page
+    fn cached(&self, url: &str) -> Option<CachedPage> {
+        if let Some(cached) = self.pages.cache.lock().unwrap().get(url) {
+            return Some(cached.clone());
+        }
+
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(database) = &self.pages.database {
+            let latest = database.latest(url).unwrap_or_else(|err| {
+                log::warn!("Cannot read page {}: {}", url, err);
+                None
+            });
+            return latest.map(|(body, time)| CachedPage {
+                body: body.into(),
+                time,
+            });
+        }
+
+        None
+    }
+
+    fn is_fresh(&self, url: &str) -> bool {
+        self.cached(url).is_some_and(|cached| cached.is_fresh())
+    }
+
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(database) = &self.pages.database {
+            return database.search(query).unwrap_or_else(|err| {
+                log::warn!("Cannot search pages: {}", err);
+                Vec::new()
+            });
+        }
+
+        self.pages.index.lock().unwrap().search(query)
+    }
+
+    /// Fetch times of the archived versions of the page, oldest first
+    fn archive_times(&self, url: &str) -> Vec<chrono::DateTime<chrono::Local>> {
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(database) = &self.pages.database {
+            return database.version_times(url).unwrap_or_else(|err| {
+                log::warn!("Cannot read archive of {}: {}", url, err);
+                Vec::new()
+            });
+        }
+
+        let archive = self.pages.archive.lock().unwrap();
+        archive.versions(url).iter().map(|page| page.time).collect()
+    }
+
+    fn archived_version(&self, url: &str, idx: usize) -> Option<CachedPage> {
+        #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+        if let Some(database) = &self.pages.database {
+            let version = database.version(url, idx).unwrap_or_else(|err| {
+                log::warn!("Cannot read archive of {}: {}", url, err);
+                None
+            });
+            return version.map(|(body, time)| CachedPage {
+                body: body.into(),
+                time,
+            });
+        }
+
+        let archive = self.pages.archive.lock().unwrap();
+        archive.versions(url).get(idx).map(|version| CachedPage {
+            body: version.body.clone(),
+            time: version.time,
+        })
+    }
+}
+
 pub trait IGuiCtx {
     fn handle_input(&mut self, input: InputState);
+    /// Run the timers of the page, called every frame whether the page is shown or not
+    fn update(&mut self);
     fn draw(&mut self, ui: &mut egui::Ui);
     fn set_refresh_interval(&mut self, interval: u64);
     fn stop_refresh_interval(&mut self);
+    fn set_carousel_interval(&mut self, interval: u64);
+    fn stop_carousel(&mut self);
+    fn set_prefetch(&mut self, enabled: bool);
+    /// Watch that polls the current page in the background and notifies when it changes
+    fn watch_current_page(&self, interval: u64, keyword: Option<String>) -> PageWatch;
+    /// Lines of the loaded pages containing the query
+    fn search(&self, query: &str) -> Vec<SearchResult>;
+    /// Load the page of the result with the matching line highlighted
+    fn open_search_result(&mut self, result: &SearchResult);
+    fn crawl_progress(&self) -> CrawlProgress;
+    /// Continue the crawl saved when the app was closed
+    fn resume_crawl(&mut self, progress: CrawlProgress);
+    fn start_crawl(&mut self);
+    fn stop_crawl(&mut self);
+    /// Forget the progress and crawl all pages again
+    fn restart_crawl(&mut self);
+    /// Load the pages of the range and save them as pdf, on wasm the print dialog is opened
+    fn start_print(&mut self, range: PrintRange, path: String);
+    fn stop_print(&mut self);
+    /// Whether the print job is finished and its description, None before printing
+    fn print_progress(&self) -> Option<(bool, String)>;
+    /// Page of the link that was middle-clicked since the last call
+    fn take_new_tab(&mut self) -> Option<TelePage>;
+    fn current_page(&self) -> TelePage;
+    /// Load the page with the history starting from it
+    fn open_page(&mut self, page: TelePage);
     fn return_from_error_page(&mut self);
     fn load_current_page(&mut self);
     fn load_page(&mut self, page: &str, add_to_history: bool);
+    /// Show a page from `.tti` file, only text readers support this
+    fn import_tti(&mut self, _data: &[u8]) -> Result<(), ParseErr> {
+        Err(ParseErr::InvalidPage)
+    }
+    /// Current page in `.tti` format if the reader supports it
+    fn export_tti(&self) -> Option<Vec<u8>> {
+        None
+    }
+    /// Current page as text, markdown or html, only text readers support this
+    fn export_page(&self, _format: ExportFormat, _colors: &HtmlColors) -> Option<String> {
+        None
+    }
+    /// Current page as png or svg. Text pages are drawn at the `scale` and always have
+    /// the header, image pages keep their size and get the header if `header` is set
+    fn export_image(
+        &self,
+        _format: ImageFormat,
+        _scale: f32,
+        _header: bool,
+        _colors: &HtmlColors,
+    ) -> Option<ImageResult<Vec<u8>>> {
+        None
+    }
+    /// Text selected on the page
+    fn selected_text(&self) -> Option<String> {
+        None
+    }
+    /// Text of the page for the clipboard, with the page and the title if `header` is set
+    fn page_text(&self, _header: bool) -> Option<String> {
+        None
+    }
 }
 
 pub struct GuiContext<T: HtmlParser + TelePager + Send + 'static> {
@@ -266,7 +830,32 @@ pub struct GuiContext<T: HtmlParser + TelePager + Send + 'static> {
     pub history: TeleHistory,
     pub page_buffer: Vec<i32>,
     pub worker: Option<GuiWorker>,
+    /// Cycles through the sub-pages of the current page when set
+    pub carousel: Option<GuiWorker>,
     pub pointer: PointerState,
+    /// Pages are fetched from the provider instead of `T::to_full_page` when set
+    pub provider: Option<Arc<ProviderConfig>>,
+    pub store: PageStore,
+    /// Load the pages linked from the navigation in the background
+    pub prefetch: bool,
+    /// Incremented on every load so prefetching the links of the previous page stops
+    load_id: Arc<AtomicUsize>,
+    pub update: Arc<Mutex<PageUpdate>>,
+    /// Search result that was opened, its line is highlighted while the page is shown
+    pub search_match: Option<SearchResult>,
+    /// Crawl of the source, shared with the other readers of it
+    pub crawl: SourceCrawl,
+    /// Archived version of the current page that is shown, None when the latest version is shown
+    pub timeline: Option<usize>,
+    /// Selected text of the current page
+    pub selection: Option<TextSelection>,
+    pub print: Option<Arc<Mutex<PrintJob>>>,
+    /// Incremented when a print job is started or stopped so the previous job stops
+    print_id: Arc<AtomicUsize>,
+    /// Link that was middle-clicked, the app opens it in a new tab
+    pub new_tab: Option<TelePage>,
+    /// Link that was clicked while the page was drawn, it's loaded after drawing
+    pub clicked_link: Option<String>,
 }
 
 impl<T: HtmlParser + TelePager + Send + 'static> GuiContext<T> {
@@ -280,34 +869,41 @@ impl<T: HtmlParser + TelePager + Send + 'static> GuiContext<T> {
             page_buffer: Vec::with_capacity(3),
             history: TeleHistory::new(current_page),
             worker: None,
+            carousel: None,
             pointer: Default::default(),
+            provider: None,
+            store: PageStore::new(T::SOURCE, Default::default()),
+            prefetch: true,
+            load_id: Default::default(),
+            update: Default::default(),
+            search_match: None,
+            crawl: Default::default(),
+            timeline: None,
+            selection: None,
+            print: None,
+            print_id: Default::default(),
+            new_tab: None,
+            clicked_link: None,
         }
     }
 
     /// Used for testing/dev only
     #[allow(dead_code)]
     pub fn from_file(egui: egui::Context, file: &str) -> Self {
-        let current_page = TelePage::new(100, 1);
         let pobj = HtmlLoader::new(file);
         let parser = T::new();
         let completed = parser.parse(pobj).unwrap();
 
-        Self {
-            egui,
-            current_page,
-            state: Arc::new(Mutex::new(FetchState::Complete(completed))),
-            page_buffer: Vec::with_capacity(3),
-            history: TeleHistory::new(current_page),
-            worker: None,
-            pointer: Default::default(),
-        }
+        let ctx = Self::new(egui);
+        *ctx.state.lock().unwrap() = FetchState::Complete(completed);
+        ctx
     }
 
     /// Helper function to load GuiContext without having to relyi on http request
     /// Example:
-    /// ```rust
+    /// ```ignore
     /// let page_string = String::from_utf8(include_bytes!("../../100.htm").to_vec()).unwrap();
-    /// let mut page = Box::new(GuiYleTextContext::new(GuiContext::from_string(
+    /// let mut page = Box::new(GuiTeleTextContext::<YleText>::new(GuiContext::from_string(
     ///     ctx.egui_ctx.clone(),
     ///     &page_string,
     /// ))) as Box<dyn IGuiCtx>;
@@ -315,40 +911,81 @@ impl<T: HtmlParser + TelePager + Send + 'static> GuiContext<T> {
     /// ```
     #[allow(dead_code)]
     pub fn from_string(egui: egui::Context, src: &str) -> Self {
-        let current_page = TelePage::new(100, 1);
         let pobj = HtmlLoader {
             page_data: src.into(),
         };
         let parser = T::new();
         let completed = parser.parse(pobj).unwrap();
 
-        Self {
-            egui,
-            current_page,
-            state: Arc::new(Mutex::new(FetchState::Complete(completed))),
-            page_buffer: Vec::with_capacity(3),
-            history: TeleHistory::new(current_page),
-            worker: None,
-            pointer: Default::default(),
-        }
+        let ctx = Self::new(egui);
+        *ctx.state.lock().unwrap() = FetchState::Complete(completed);
+        ctx
+    }
+
+    /// Share the loaded pages with the other readers of the app
+    pub fn with_pages(mut self, pages: Arc<SharedPages>) -> Self {
+        self.store = PageStore::new(&self.store.source, pages);
+        self
+    }
+
+    /// Share the crawl with the other readers of the source
+    pub fn with_crawl(mut self, crawl: SourceCrawl) -> Self {
+        self.crawl = crawl;
+        self
+    }
+
+    /// Fetch the pages from a provider defined in the providers file
+    pub fn with_provider(mut self, provider: Arc<ProviderConfig>) -> Self {
+        self.store.source = provider.name.clone();
+        self.provider = Some(provider);
+        self
     }
 
     pub fn handle_input(&mut self, input: InputState) {
+        let page = self.current_page;
+        self.handle_page_input(input);
+
+        // Carousel shows the next sub-page a whole interval after the user changed the page
+        if self.current_page != page {
+            if let Some(carousel) = &mut self.carousel {
+                carousel.restart();
+            }
+        }
+    }
+
+    fn handle_page_input(&mut self, input: InputState) {
         // Ignore input while fetching
         match *self.state.lock().unwrap() {
             FetchState::Complete(_) => {}
             _ => return,
         };
 
-        if let Some(num) = input_to_num(&input) {
-            if self.page_buffer.len() < 3 {
-                self.page_buffer.push(num);
+        // Keys are used in text fields, like the search box, so they only
+        // control the page when no field is focused
+        if !self.egui.wants_keyboard_input() {
+            if let Some(num) = input_to_num(&input) {
+                if self.page_buffer.len() < 3 {
+                    self.page_buffer.push(num);
+                }
+
+                if self.page_buffer.len() == 3 {
+                    let page_num = self.page_buffer.iter().fold(0, |acum, val| acum * 10 + val);
+                    self.page_buffer.clear();
+                    self.load_page(&T::to_page_str(&TelePage::new(page_num, 1)), true);
+                }
             }
 
-            if self.page_buffer.len() == 3 {
-                let page_num = self.page_buffer.iter().fold(0, |acum, val| acum * 10 + val);
-                self.page_buffer.clear();
-                self.load_page(&T::to_page_str(&TelePage::new(page_num, 1)), true);
+            if let Some(key) = input_to_fastext(&input) {
+                let url = match &*self.state.lock().unwrap() {
+                    FetchState::Complete(page) => {
+                        page.fastext().get(key).map(|l| l.url.to_string())
+                    }
+                    _ => None,
+                };
+
+                if let Some(url) = url {
+                    self.load_page(&url, true);
+                }
             }
         }
 
@@ -372,139 +1009,1049 @@ impl<T: HtmlParser + TelePager + Send + 'static> GuiContext<T> {
         }
     }
 
-    pub fn draw(&mut self, _ui: &mut egui::Ui) {
+    /// Refresh, carousel and automatic retries, run for every tab even if it's not shown
+    pub fn update(&mut self) {
+        let retry = match &*self.state.lock().unwrap() {
+            FetchState::Error(failure) | FetchState::InitFailed(failure) => failure
+                .retry_at()
+                .filter(|retry_at| *retry_at <= chrono::Local::now())
+                .map(|_| failure.attempt + 1),
+            _ => None,
+        };
+        if let Some(attempt) = retry {
+            self.error_action(ErrorAction::Retry { attempt });
+        }
+
+        // Older version of the page stays on the screen until the user leaves it
+        let is_latest = self.timeline.is_none();
         if let Some(worker) = &mut self.worker {
             if worker.should_refresh() {
                 worker.use_refresh();
-                self.load_current_page();
+                if is_latest {
+                    self.reload_current_page();
+                }
             }
         }
-    }
 
-    pub fn set_refresh_interval(&mut self, interval: u64) {
-        if let Some(worker) = &mut self.worker {
-            worker.set_interval(interval);
-        } else {
-            let mut worker = GuiWorker::new(interval);
-            worker.start();
-            self.worker = Some(worker);
+        if let Some(carousel) = &mut self.carousel {
+            if carousel.should_refresh() {
+                carousel.use_refresh();
+                if is_latest {
+                    self.show_next_sub_page();
+                }
+            }
         }
     }
 
-    pub fn stop_refresh_interval(&mut self) {
-        self.worker = None;
+    /// Watch of the page, it's polled by the `PageWatcher` of the app
+    pub fn watch_page(&self, page: TelePage, interval: u64, keyword: Option<String>) -> PageWatch {
+        let site = Self::page_url(self.provider.as_deref(), &page);
+        let provider = self.provider.clone();
+        PageWatch::new(page, site, provider, interval, keyword, Self::watch_content)
     }
 
-    pub fn return_from_error_page(&mut self) {
-        if let Some(page) = self.history.prev_trunc() {
-            self.current_page = page;
-            self.load_current_page();
+    /// Text of the page, or the whole response if the page is not text
+    fn watch_content(
+        response: PageResponse,
+        provider: Option<&ProviderConfig>,
+        page: &TelePage,
+    ) -> Option<String> {
+        let body: Arc<str> = response.body.into();
+        let loader = HtmlLoader {
+            page_data: body.clone(),
+        };
+        let parsed = Self::parse_page(loader, provider, page).ok()?;
+        Some(parsed.plain_text().unwrap_or_else(|| body.to_string()))
+    }
+
+    fn show_next_sub_page(&mut self) {
+        let next = match &*self.state.lock().unwrap() {
+            FetchState::Complete(page) => page.next_sub_page(&self.current_page),
+            _ => None,
+        };
+
+        // Sub-pages of the carousel are not added to the history
+        if let Some(next) = next {
+            self.load_page(&T::to_page_str(&next), false);
         }
     }
 
-    pub fn load_current_page(&mut self) {
-        let page = T::to_page_str(&self.current_page);
-        self.load_page(&page, false);
+    /// "updated HH:MM" label of the header and the rows changed in the last refresh
+    pub fn update_status(&self) -> (Option<String>, Vec<usize>) {
+        let update = self.update.lock().unwrap();
+        let highlighted = update.highlighted_rows().to_vec();
+        if !highlighted.is_empty() {
+            // Highlights are removed without other repaints
+            self.egui
+                .request_repaint_after(std::time::Duration::from_secs(1));
+        }
+
+        (update.loaded_label(), highlighted)
     }
 
-    pub fn load_page(&mut self, page: &str, add_to_history: bool) {
-        let ctx = self.egui.clone();
-        let state = self.state.clone();
-        let page = T::from_page_str(page);
+    pub fn open_search_result(&mut self, result: &SearchResult) {
+        self.search_match = Some(result.clone());
+        self.load_page(&T::to_page_str(&result.page), true);
+    }
 
-        self.current_page = page;
-        if add_to_history {
-            self.history.add(self.current_page)
+    pub fn resume_crawl(&mut self, mut progress: CrawlProgress) {
+        // Without the database the crawled pages were lost when the app was closed
+        progress.requeue_missing(|page| {
+            let site = Self::page_url(self.provider.as_deref(), page);
+            self.store.cached(&site).is_some()
+        });
+        let running = progress.running;
+        *self.crawl.progress.lock().unwrap() = progress;
+        if running {
+            self.start_crawl();
         }
+    }
+
+    pub fn start_crawl(&mut self) {
+        self.crawl.progress.lock().unwrap().running = true;
+        let crawl_id = self.crawl.id.fetch_add(1, Ordering::Relaxed) + 1;
+        let crawl = self.crawl.progress.clone();
+        let crawl_ids = self.crawl.id.clone();
+        let provider = self.provider.clone();
+        let store = self.store.clone();
+        let egui = self.egui.clone();
 
         #[cfg(not(target_arch = "wasm32"))]
-        thread::spawn(move || {
-            let is_init = matches!(
-                *state.lock().unwrap(),
-                FetchState::Init | FetchState::InitFailed
-            );
+        thread::spawn(move || Self::crawl(crawl, crawl_ids, crawl_id, provider, store, egui));
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(Self::crawl(
+            crawl, crawl_ids, crawl_id, provider, store, egui,
+        ));
+    }
 
-            *state.lock().unwrap() = FetchState::Fetching;
-            let site = &T::to_full_page(&page);
-            log::info!("Load page: {}", site);
-            let new_state = match Self::fetch_page(site) {
-                Ok(parser) => FetchState::Complete(parser),
-                Err(_) => {
-                    if is_init {
-                        FetchState::InitFailed
-                    } else {
-                        FetchState::Error
-                    }
-                }
-            };
+    pub fn stop_crawl(&mut self) {
+        self.crawl.id.fetch_add(1, Ordering::Relaxed);
+        self.crawl.progress.lock().unwrap().running = false;
+    }
 
-            *state.lock().unwrap() = new_state;
-            ctx.request_repaint();
-        });
+    pub fn restart_crawl(&mut self) {
+        self.stop_crawl();
+        *self.crawl.progress.lock().unwrap() = CrawlProgress::default();
+        self.start_crawl();
+    }
 
+    pub fn start_print(&mut self, range: PrintRange, path: String) {
+        let job = Arc::new(Mutex::new(PrintJob::new(range, self.current_page, path)));
+        self.print = Some(job.clone());
+        let print_id = self.print_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let print_ids = self.print_id.clone();
+        let provider = self.provider.clone();
+        let store = self.store.clone();
+        let egui = self.egui.clone();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        thread::spawn(move || Self::print(job, print_ids, print_id, provider, store, egui));
         #[cfg(target_arch = "wasm32")]
-        wasm_bindgen_futures::spawn_local(async move {
-            let is_init = matches!(
-                *state.lock().unwrap(),
-                FetchState::Init | FetchState::InitFailed
-            );
+        wasm_bindgen_futures::spawn_local(Self::print(
+            job, print_ids, print_id, provider, store, egui,
+        ));
+    }
 
-            *state.lock().unwrap() = FetchState::Fetching;
-            let site = &T::to_full_page(&page);
+    pub fn stop_print(&mut self) {
+        self.print_id.fetch_add(1, Ordering::Relaxed);
+        if let Some(job) = &self.print {
+            job.lock().unwrap().status = Some("Stopped".to_string());
+        }
+    }
+
+    pub fn print_progress(&self) -> Option<(bool, String)> {
+        let job = self.print.as_ref()?.lock().unwrap();
+        Some((job.status.is_some(), job.description()))
+    }
+
+    /// The drawn page keeps the state locked so the link is loaded after drawing
+    pub fn follow_link(&mut self, page: &str) {
+        self.clicked_link = Some(page.to_string());
+    }
+
+    /// Links outside of teletext are ignored like when they're clicked
+    pub fn open_in_new_tab(&mut self, page: &str) {
+        if let Some(page) = T::from_page_str(page) {
+            self.new_tab = Some(page);
+        }
+    }
+
+    /// Load the page with the history starting from it, used by new tabs
+    pub fn open_page(&mut self, page: TelePage) {
+        self.history = TeleHistory::new(page);
+        self.load_page(&T::to_page_str(&page), false);
+    }
+
+    /// Fetch times of the archived versions of the current page, oldest first
+    pub fn archive_times(&self) -> Vec<chrono::DateTime<chrono::Local>> {
+        self.update.lock().unwrap().versions.clone()
+    }
+
+    /// Show the archived version of the current page. The latest version
+    /// is updated by the refresh interval again
+    pub fn show_version(&mut self, idx: usize) {
+        let provider = self.provider.as_deref();
+        let url = Self::page_url(provider, &self.current_page);
+        let is_latest = idx + 1 == self.update.lock().unwrap().versions.len();
+        let (body, time) = match self.store.archived_version(&url, idx) {
+            Some(version) => (version.body, version.time),
+            None => return,
+        };
+
+        let loader = HtmlLoader { page_data: body };
+        if let Ok(parsed) = Self::parse_page(loader, provider, &self.current_page) {
+            self.timeline = (!is_latest).then_some(idx);
+            self.update.lock().unwrap().clear(time);
+            *self.state.lock().unwrap() = FetchState::Complete(parsed);
+            self.egui.request_repaint();
+        }
+    }
+
+    /// Slider over the archived versions of the current page.
+    /// Returns the version the user selected
+    pub fn timeline_ui(&self, ui: &mut egui::Ui) -> Option<usize> {
+        let times = self.archive_times();
+        if times.len() < 2 {
+            return None;
+        }
+
+        let latest = times.len() - 1;
+        let mut selected = self.timeline.unwrap_or(latest).min(latest);
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("History");
+            let slider = egui::Slider::new(&mut selected, 0..=latest).show_value(false);
+            changed = ui.add(slider).changed();
+            ui.label(times[selected].format("%d.%m. %H:%M:%S").to_string());
+            if selected != latest && ui.button("Latest").clicked() {
+                selected = latest;
+                changed = true;
+            }
+        });
+
+        changed.then_some(selected)
+    }
+
+    /// Row of the opened search result if it's on the current page
+    pub fn search_row(&self) -> Option<usize> {
+        self.search_match
+            .as_ref()
+            .filter(|result| result.page == self.current_page)
+            .map(|result| result.row)
+    }
+
+    /// Sub-page indicator of the header, e.g. "2/5"
+    pub fn sub_page_indicator(&self, page: &T) -> Option<String> {
+        let sub_page = self.current_page.sub_page;
+        match page.sub_page_count() {
+            Some(count) if count > 1 => Some(format!("{}/{}", sub_page, count)),
+            Some(_) => None,
+            // Number of sub-pages isn't known, only that there are more than one
+            None if sub_page > 1 || page.next_sub_page(&self.current_page).is_some() => {
+                Some(format!("{}/?", sub_page))
+            }
+            None => None,
+        }
+    }
+
+    pub fn set_carousel_interval(&mut self, interval: u64) {
+        if let Some(carousel) = &mut self.carousel {
+            carousel.set_interval(interval);
+        } else {
+            let mut carousel = GuiWorker::new(interval);
+            carousel.start();
+            self.carousel = Some(carousel);
+        }
+    }
+
+    pub fn stop_carousel(&mut self) {
+        self.carousel = None;
+    }
+
+    pub fn set_refresh_interval(&mut self, interval: u64) {
+        if let Some(worker) = &mut self.worker {
+            worker.set_interval(interval);
+        } else {
+            let mut worker = GuiWorker::new(interval);
+            worker.start();
+            self.worker = Some(worker);
+        }
+    }
+
+    pub fn stop_refresh_interval(&mut self) {
+        self.worker = None;
+    }
+
+    pub fn return_from_error_page(&mut self) {
+        if let Some(page) = self.history.prev_trunc() {
+            self.current_page = page;
+            self.load_current_page();
+        }
+    }
+
+    /// Show already parsed page without fetching it
+    pub fn show_page(&mut self, page: TelePage, parsed: T) {
+        self.current_page = page;
+        self.selection = None;
+        self.history.add(page);
+        *self.state.lock().unwrap() = FetchState::Complete(parsed);
+        self.egui.request_repaint();
+    }
+
+    pub fn load_current_page(&mut self) {
+        let page = T::to_page_str(&self.current_page);
+        self.load_page(&page, false);
+    }
+
+    /// Index page of the provider
+    fn home_page(&self) -> TelePage {
+        let page = self.provider.as_ref().map(|p| p.index_page).unwrap_or(100);
+        TelePage::new(page, 1)
+    }
+
+    pub fn error_action(&mut self, action: ErrorAction) {
+        match action {
+            ErrorAction::Retry { attempt } => {
+                let page = T::to_page_str(&self.current_page);
+                self.fetch(&page, false, LoadKind::Retry(attempt));
+            }
+            ErrorAction::Back => self.return_from_error_page(),
+            ErrorAction::Home => self.load_page(&T::to_page_str(&self.home_page()), true),
+            ErrorAction::OpenCached => self.open_cached_page(),
+        }
+    }
+
+    /// Show the last loaded copy of the current page
+    pub fn open_cached_page(&mut self) {
+        if let Some((parsed, loaded)) = self.cached_page(&self.current_page, false) {
+            let url = Self::page_url(self.provider.as_deref(), &self.current_page);
+            let mut update = self.update.lock().unwrap();
+            update.clear(loaded);
+            update.versions = self.store.archive_times(&url);
+            drop(update);
+            *self.state.lock().unwrap() = FetchState::Complete(parsed);
+            self.egui.request_repaint();
+        }
+    }
+
+    /// Parse the cached copy of the page with the time it was loaded.
+    /// Only recent copies are used if `fresh` is set
+    fn cached_page(
+        &self,
+        page: &TelePage,
+        fresh: bool,
+    ) -> Option<(T, chrono::DateTime<chrono::Local>)> {
+        let provider = self.provider.as_deref();
+        let url = Self::page_url(provider, page);
+        let (body, time) = match self.store.cached(&url) {
+            Some(cached) if !fresh || cached.is_fresh() => (cached.body, cached.time),
+            _ => return None,
+        };
+
+        let parsed = Self::parse_page(HtmlLoader { page_data: body }, provider, page).ok()?;
+        Some((parsed, time))
+    }
+
+    pub fn load_page(&mut self, page: &str, add_to_history: bool) {
+        self.fetch(page, add_to_history, LoadKind::Navigate);
+    }
+
+    /// Load the current page even if it was loaded recently, used by the refresh interval
+    pub fn reload_current_page(&mut self) {
+        let page = T::to_page_str(&self.current_page);
+        self.fetch(&page, false, LoadKind::Refresh);
+    }
+
+    fn fetch(&mut self, page: &str, add_to_history: bool, kind: LoadKind) {
+        let ctx = self.egui.clone();
+        let state = self.state.clone();
+        let update = self.update.clone();
+        let provider = self.provider.clone();
+        let store = self.store.clone();
+        let page = match T::from_page_str(page) {
+            Some(page) => page,
+            // Links outside of teletext can't be loaded
+            None => return,
+        };
+
+        if self.current_page != page {
+            self.selection = None;
+        }
+        self.current_page = page;
+        if self
+            .search_match
+            .as_ref()
+            .is_some_and(|result| result.page != page)
+        {
+            self.search_match = None;
+        }
+        self.timeline = None;
+        if add_to_history {
+            self.history.add(self.current_page)
+        }
+
+        let load_ids = self.load_id.clone();
+        let load_id = load_ids.fetch_add(1, Ordering::Relaxed) + 1;
+        let prefetch = self.prefetch;
+        let cached = match kind {
+            LoadKind::Navigate => self.cached_page(&page, true),
+            _ => None,
+        };
+        if let Some((parsed, loaded)) = cached {
+            let pages = if prefetch {
+                parsed.prefetch_pages()
+            } else {
+                Vec::new()
+            };
+            let site = Self::page_url(provider.as_deref(), &page);
+            let mut page_update = update.lock().unwrap();
+            page_update.clear(loaded);
+            page_update.versions = store.archive_times(&site);
+            drop(page_update);
+            *state.lock().unwrap() = FetchState::Complete(parsed);
+            ctx.request_repaint();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            thread::spawn(move || Self::prefetch(pages, provider, store, load_ids, load_id));
+            #[cfg(target_arch = "wasm32")]
+            wasm_bindgen_futures::spawn_local(Self::prefetch(
+                pages, provider, store, load_ids, load_id,
+            ));
+            return;
+        }
+
+        // State is changed before the load starts so the error page can't start another retry
+        let is_init = Self::start_load(&state, kind);
+        #[cfg(not(target_arch = "wasm32"))]
+        thread::spawn(move || {
+            let site = &Self::page_url(provider.as_deref(), &page);
+            log::info!("Load page: {}", site);
+            let mut fetched = Self::fetch_page(site, Some(&store)).and_then(|response| {
+                Self::parse_response(response, site, provider.as_deref(), &page, &store)
+            });
+            if let Err(failure) = &fetched {
+                log::warn!("Failed to load page {}: {:?}", site, failure.error);
+            }
+            let versions = Self::read_store(site, &store, &mut fetched);
+
+            // Page shown from the cache while this one was loading replaces it
+            if load_ids.load(Ordering::Relaxed) != load_id {
+                return;
+            }
+
+            let pages =
+                Self::finish_load(&state, &update, fetched, versions, kind, is_init, prefetch);
+            ctx.request_repaint();
+            Self::prefetch(pages, provider, store, load_ids, load_id);
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            let site = &Self::page_url(provider.as_deref(), &page);
             tracing::info!("Load page: {}", site);
-            let fetched = Self::fetch_page(site).await;
-            let new_state = match fetched {
-                Ok(parser) => FetchState::Complete(parser),
-                Err(_) => {
-                    if is_init {
-                        FetchState::InitFailed
-                    } else {
-                        FetchState::Error
-                    }
+            let mut fetched = match Self::fetch_page(site, Some(&store)).await {
+                Ok(response) => {
+                    Self::parse_response(response, site, provider.as_deref(), &page, &store)
                 }
+                Err(failure) => Err(failure),
             };
+            if let Err(failure) = &fetched {
+                tracing::warn!("Failed to load page {}: {:?}", site, failure.error);
+            }
+            let versions = Self::read_store(site, &store, &mut fetched);
+
+            // Page shown from the cache while this one was loading replaces it
+            if load_ids.load(Ordering::Relaxed) != load_id {
+                return;
+            }
 
-            *state.lock().unwrap() = new_state;
+            let pages =
+                Self::finish_load(&state, &update, fetched, versions, kind, is_init, prefetch);
             ctx.request_repaint();
+            Self::prefetch(pages, provider, store, load_ids, load_id).await;
         });
     }
 
+    /// Returns true if this is the first load of the reader
+    fn start_load(state: &Mutex<FetchState<T>>, kind: LoadKind) -> bool {
+        let mut state = state.lock().unwrap();
+        let is_init = matches!(*state, FetchState::Init | FetchState::InitFailed(_));
+        if !matches!(kind, LoadKind::Refresh) {
+            *state = FetchState::Fetching;
+        }
+
+        is_init
+    }
+
+    /// Read what the page needs from the store after loading it, the archived versions
+    /// of a loaded page or if a failed page has a cached copy
+    fn read_store(
+        site: &str,
+        store: &PageStore,
+        fetched: &mut Result<T, FetchFailure>,
+    ) -> Vec<chrono::DateTime<chrono::Local>> {
+        match fetched {
+            Ok(_) => store.archive_times(site),
+            Err(failure) => {
+                failure.has_cached = store.cached(site).is_some();
+                Vec::new()
+            }
+        }
+    }
+
+    /// Show the loaded page or the failure. Refreshed pages are compared to the
+    /// previous version. Returns the pages that should be prefetched
+    fn finish_load(
+        state: &Mutex<FetchState<T>>,
+        update: &Mutex<PageUpdate>,
+        fetched: Result<T, FetchFailure>,
+        versions: Vec<chrono::DateTime<chrono::Local>>,
+        kind: LoadKind,
+        is_init: bool,
+        prefetch: bool,
+    ) -> Vec<TelePage> {
+        let mut state = state.lock().unwrap();
+        match fetched {
+            Ok(parsed) => {
+                let now = chrono::Local::now();
+                let mut update = update.lock().unwrap();
+                match (&*state, kind) {
+                    (FetchState::Complete(previous), LoadKind::Refresh) => {
+                        update.page_loaded(now, parsed.changed_rows(previous))
+                    }
+                    _ => update.clear(now),
+                }
+                update.versions = versions;
+
+                let pages = if prefetch {
+                    parsed.prefetch_pages()
+                } else {
+                    Vec::new()
+                };
+                *state = FetchState::Complete(parsed);
+                pages
+            }
+            Err(mut failure) => {
+                if let LoadKind::Retry(attempt) = kind {
+                    failure.attempt = attempt;
+                }
+
+                *state = if is_init {
+                    FetchState::InitFailed(failure)
+                } else {
+                    FetchState::Error(failure)
+                };
+                Vec::new()
+            }
+        }
+    }
+
+    /// Load the pages into the cache one at a time.
+    /// Stops when another page is loaded since these pages are not needed anymore
     #[cfg(not(target_arch = "wasm32"))]
-    fn fetch_page(site: &str) -> Result<T, ()> {
-        use reqwest::header::{HeaderMap, HeaderValue};
-
-        // let body = reqwest::blocking::get(site).unwrap();
-        let mut headers = HeaderMap::new();
-        headers.insert("user-agent", HeaderValue::from_static("curl/7.81.0"));
-        let body = reqwest::blocking::Client::builder()
-            .default_headers(headers)
-            .build()
-            .unwrap()
-            .get(site)
-            .send()
-            .unwrap();
-        let body = body.text().unwrap();
-        let teletext = T::new().parse(HtmlLoader { page_data: body }).unwrap();
-        Ok(teletext)
+    fn prefetch(
+        pages: Vec<TelePage>,
+        provider: Option<Arc<ProviderConfig>>,
+        store: PageStore,
+        load_ids: Arc<AtomicUsize>,
+        load_id: usize,
+    ) {
+        for page in pages.iter().take(MAX_PREFETCH) {
+            let site = &Self::page_url(provider.as_deref(), page);
+            if store.is_fresh(site) {
+                continue;
+            }
+
+            thread::sleep(PREFETCH_DELAY);
+            if load_ids.load(Ordering::Relaxed) != load_id {
+                return;
+            }
+
+            let fetched = Self::fetch_page(site, Some(&store)).and_then(|response| {
+                Self::parse_response(response, site, provider.as_deref(), page, &store)
+            });
+            if let Err(failure) = fetched {
+                log::info!("Failed to prefetch page {}: {:?}", site, failure.error);
+            }
+        }
     }
 
     #[cfg(target_arch = "wasm32")]
-    async fn fetch_page(site: &str) -> Result<T, ()> {
-        let res = reqwest::Client::new()
-            .get(site)
-            .send()
-            .await
-            .map_err(|_| ())?;
+    async fn prefetch(
+        pages: Vec<TelePage>,
+        provider: Option<Arc<ProviderConfig>>,
+        store: PageStore,
+        load_ids: Arc<AtomicUsize>,
+        load_id: usize,
+    ) {
+        for page in pages.iter().take(MAX_PREFETCH) {
+            let site = &Self::page_url(provider.as_deref(), page);
+            if store.is_fresh(site) {
+                continue;
+            }
 
-        let text = res.text().await.map_err(|_| ())?;
-        let teletext = T::new()
-            .parse(HtmlLoader { page_data: text })
-            .map_err(|_| ())?;
-        Ok(teletext)
+            if load_ids.load(Ordering::Relaxed) != load_id {
+                return;
+            }
+
+            let fetched = match Self::fetch_page(site, Some(&store)).await {
+                Ok(response) => {
+                    Self::parse_response(response, site, provider.as_deref(), page, &store)
+                }
+                Err(failure) => Err(failure),
+            };
+            if let Err(failure) = fetched {
+                tracing::info!("Failed to prefetch page {}: {:?}", site, failure.error);
+            }
+        }
+    }
+
+    /// Load the queued pages one at a time until the queue is empty or the crawl is stopped.
+    /// Loaded pages are kept in the store like the pages the user opens
+    #[cfg(not(target_arch = "wasm32"))]
+    fn crawl(
+        crawl: Arc<Mutex<CrawlProgress>>,
+        crawl_ids: Arc<AtomicUsize>,
+        crawl_id: usize,
+        provider: Option<Arc<ProviderConfig>>,
+        store: PageStore,
+        egui: egui::Context,
+    ) {
+        loop {
+            thread::sleep(Duration::from_millis(CRAWL_DELAY_MS));
+            let page = match Self::next_crawl_page(&crawl, &crawl_ids, crawl_id) {
+                Some(page) => page,
+                None => break,
+            };
+
+            let site = &Self::page_url(provider.as_deref(), &page);
+            let fetched = Self::fetch_page(site, Some(&store)).and_then(|response| {
+                Self::parse_response(response, site, provider.as_deref(), &page, &store)
+            });
+            if let Err(failure) = &fetched {
+                log::info!("Failed to crawl page {}: {:?}", site, failure.error);
+            }
+
+            Self::crawl_loaded(&crawl, &crawl_ids, crawl_id, fetched);
+            egui.request_repaint();
+        }
+
+        egui.request_repaint();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn crawl(
+        crawl: Arc<Mutex<CrawlProgress>>,
+        crawl_ids: Arc<AtomicUsize>,
+        crawl_id: usize,
+        provider: Option<Arc<ProviderConfig>>,
+        store: PageStore,
+        egui: egui::Context,
+    ) {
+        loop {
+            sleep(CRAWL_DELAY_MS).await;
+            let page = match Self::next_crawl_page(&crawl, &crawl_ids, crawl_id) {
+                Some(page) => page,
+                None => break,
+            };
+
+            let site = &Self::page_url(provider.as_deref(), &page);
+            let fetched = match Self::fetch_page(site, Some(&store)).await {
+                Ok(response) => {
+                    Self::parse_response(response, site, provider.as_deref(), &page, &store)
+                }
+                Err(failure) => Err(failure),
+            };
+            if let Err(failure) = &fetched {
+                tracing::info!("Failed to crawl page {}: {:?}", site, failure.error);
+            }
+
+            Self::crawl_loaded(&crawl, &crawl_ids, crawl_id, fetched);
+            egui.request_repaint();
+        }
+
+        egui.request_repaint();
+    }
+
+    /// Load the pages of the print job one at a time. Fresh copies in the store are used
+    /// so pages that were just read are not loaded again
+    #[cfg(not(target_arch = "wasm32"))]
+    fn print(
+        job: Arc<Mutex<PrintJob>>,
+        print_ids: Arc<AtomicUsize>,
+        print_id: usize,
+        provider: Option<Arc<ProviderConfig>>,
+        store: PageStore,
+        egui: egui::Context,
+    ) {
+        loop {
+            if print_ids.load(Ordering::Relaxed) != print_id {
+                return;
+            }
+
+            let page = match job.lock().unwrap().next_page() {
+                Some(page) => page,
+                None => break,
+            };
+
+            let site = &Self::page_url(provider.as_deref(), &page);
+            let fetched = match Self::stored_page(site, provider.as_deref(), &page, &store) {
+                Some(parsed) => Ok(parsed),
+                None => {
+                    thread::sleep(PREFETCH_DELAY);
+                    Self::fetch_page(site, Some(&store)).and_then(|response| {
+                        Self::parse_response(response, site, provider.as_deref(), &page, &store)
+                    })
+                }
+            };
+            if let Err(failure) = &fetched {
+                log::info!(
+                    "Failed to load page {} for printing: {:?}",
+                    site,
+                    failure.error
+                );
+            }
+
+            Self::print_loaded(&job, &page, fetched);
+            egui.request_repaint();
+        }
+
+        let mut job = job.lock().unwrap();
+        let status = Self::finish_print(&job);
+        job.status = Some(status);
+        egui.request_repaint();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn print(
+        job: Arc<Mutex<PrintJob>>,
+        print_ids: Arc<AtomicUsize>,
+        print_id: usize,
+        provider: Option<Arc<ProviderConfig>>,
+        store: PageStore,
+        egui: egui::Context,
+    ) {
+        loop {
+            if print_ids.load(Ordering::Relaxed) != print_id {
+                return;
+            }
+
+            let page = match job.lock().unwrap().next_page() {
+                Some(page) => page,
+                None => break,
+            };
+
+            let site = &Self::page_url(provider.as_deref(), &page);
+            let fetched = match Self::stored_page(site, provider.as_deref(), &page, &store) {
+                Some(parsed) => Ok(parsed),
+                None => match Self::fetch_page(site, Some(&store)).await {
+                    Ok(response) => {
+                        Self::parse_response(response, site, provider.as_deref(), &page, &store)
+                    }
+                    Err(failure) => Err(failure),
+                },
+            };
+            if let Err(failure) = &fetched {
+                tracing::info!(
+                    "Failed to load page {} for printing: {:?}",
+                    site,
+                    failure.error
+                );
+            }
+
+            Self::print_loaded(&job, &page, fetched);
+            egui.request_repaint();
+        }
+
+        let mut job = job.lock().unwrap();
+        let status = Self::finish_print(&job);
+        job.status = Some(status);
+        egui.request_repaint();
+    }
+
+    /// Fresh copy of the page from the store
+    fn stored_page(
+        site: &str,
+        provider: Option<&ProviderConfig>,
+        page: &TelePage,
+        store: &PageStore,
+    ) -> Option<T> {
+        let cached = store.cached(site).filter(CachedPage::is_fresh)?;
+        Self::parse_page(
+            HtmlLoader {
+                page_data: cached.body,
+            },
+            provider,
+            page,
+        )
+        .ok()
+    }
+
+    fn print_loaded(job: &Mutex<PrintJob>, page: &TelePage, fetched: Result<T, FetchFailure>) {
+        let mut job = job.lock().unwrap();
+        match fetched {
+            Ok(parsed) => {
+                let print_page = parsed
+                    .search_text()
+                    .map(|(title, lines)| PrintPage::new(page.page, page.sub_page, &title, lines));
+                job.page_loaded(print_page, parsed.next_sub_page(page));
+            }
+            Err(_) => job.page_failed(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn finish_print(job: &PrintJob) -> String {
+        if job.pages.is_empty() {
+            return "No pages to print".to_string();
+        }
+
+        match std::fs::write(&job.path, print::to_pdf(&job.pages)) {
+            Ok(_) => format!("Saved {} pages to {}", job.pages.len(), job.path),
+            Err(err) => format!("Cannot write {}: {err}", job.path),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn finish_print(job: &PrintJob) -> String {
+        if job.pages.is_empty() {
+            return "No pages to print".to_string();
+        }
+
+        match open_print_dialog(&print::to_print_html(&job.pages)) {
+            Ok(_) => format!("Printing {} pages", job.pages.len()),
+            Err(err) => format!("Cannot open the print dialog: {err:?}"),
+        }
+    }
+
+    /// None when the crawl was stopped or there are no pages left
+    fn next_crawl_page(
+        crawl: &Mutex<CrawlProgress>,
+        crawl_ids: &AtomicUsize,
+        crawl_id: usize,
+    ) -> Option<TelePage> {
+        if crawl_ids.load(Ordering::Relaxed) != crawl_id {
+            return None;
+        }
+
+        let mut crawl = crawl.lock().unwrap();
+        let next = crawl.next_page();
+        if next.is_none() {
+            crawl.running = false;
+        }
+        next
+    }
+
+    /// Results of a stopped crawl are ignored so the page is loaded again when the crawl continues
+    fn crawl_loaded(
+        crawl: &Mutex<CrawlProgress>,
+        crawl_ids: &AtomicUsize,
+        crawl_id: usize,
+        fetched: Result<T, FetchFailure>,
+    ) {
+        if crawl_ids.load(Ordering::Relaxed) != crawl_id {
+            return;
+        }
+
+        let mut crawl = crawl.lock().unwrap();
+        match fetched {
+            Ok(parsed) => crawl.page_loaded(parsed.prefetch_pages()),
+            Err(failure) => crawl.page_failed(failure.error.is_transient()),
+        }
+    }
+
+    fn page_url(provider: Option<&ProviderConfig>, page: &TelePage) -> String {
+        match provider {
+            Some(provider) => provider.page_url(page.page, page.sub_page),
+            None => T::to_full_page(page),
+        }
+    }
+
+    fn parse_page(
+        loader: HtmlLoader,
+        provider: Option<&ProviderConfig>,
+        page: &TelePage,
+    ) -> ParserResult<T> {
+        match provider {
+            Some(provider) => T::new().parse_with_provider(
+                loader,
+                &ProviderPage {
+                    provider,
+                    page: page.page,
+                    sub_page: page.sub_page,
+                },
+            ),
+            None => T::new().parse(loader),
+        }
+    }
+
+    /// Parse the response and keep it in the store if it's valid
+    fn parse_response(
+        response: PageResponse,
+        site: &str,
+        provider: Option<&ProviderConfig>,
+        page: &TelePage,
+        store: &PageStore,
+    ) -> Result<T, FetchFailure> {
+        // Store and the parsed page share the body
+        let (status, body): (u16, Arc<str>) = (response.status, response.body.into());
+        let loader = HtmlLoader {
+            page_data: body.clone(),
+        };
+        let parsed = Self::parse_page(loader, provider, page)
+            .map_err(|err| FetchFailure::new(err.into(), site, Some(status)))?;
+        store.add(site, page, &parsed, body);
+        Ok(parsed)
+    }
+
+    /// The request is conditional if the store has a copy of the page
+    #[cfg(not(target_arch = "wasm32"))]
+    fn fetch_page(site: &str, store: Option<&PageStore>) -> Result<PageResponse, FetchFailure> {
+        let cached = store.and_then(|store| store.cached(site));
+        let cached = cached.as_ref().map(|cached| cached.body.as_ref());
+        super::http::client().get(site, cached)
+    }
+
+    /// Browser does the conditional requests with its own cache
+    #[cfg(target_arch = "wasm32")]
+    async fn fetch_page(
+        site: &str,
+        _store: Option<&PageStore>,
+    ) -> Result<PageResponse, FetchFailure> {
+        fetch_url(site).await
+    }
+}
+
+/// Request of the wasm builds, the pages are fetched with the browser
+#[cfg(target_arch = "wasm32")]
+pub async fn fetch_url(site: &str) -> Result<PageResponse, FetchFailure> {
+    let network_error = |status: Option<u16>| FetchFailure::new(FetchError::Network, site, status);
+    let res = reqwest::Client::new()
+        .get(site)
+        .send()
+        .await
+        .map_err(|_| network_error(None))?;
+    let status = res.status();
+    check_status(site, status)?;
+
+    let body = res
+        .text()
+        .await
+        .map_err(|_| network_error(Some(status.as_u16())))?;
+    Ok(PageResponse {
+        status: status.as_u16(),
+        body,
+    })
+}
+
+impl<T: HtmlParser + TelePager + Send + 'static> Drop for GuiContext<T> {
+    /// Printing runs in the background so it's stopped when the reader is closed.
+    /// The crawl belongs to the app and keeps running
+    fn drop(&mut self) {
+        self.print_id.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Print the html in a hidden frame, the document opens the print dialog when it's loaded
+#[cfg(target_arch = "wasm32")]
+fn open_print_dialog(html: &str) -> Result<(), JsValue> {
+    const PRINT_FRAME_ID: &str = "print-frame";
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or(JsValue::NULL)?;
+    if let Some(previous) = document.get_element_by_id(PRINT_FRAME_ID) {
+        previous.remove();
+    }
+
+    let frame = document.create_element("iframe")?;
+    frame.set_id(PRINT_FRAME_ID);
+    frame.set_attribute("style", "position: fixed; width: 0; height: 0; border: 0")?;
+    frame.set_attribute("srcdoc", html)?;
+    document.body().ok_or(JsValue::NULL)?.append_child(&frame)?;
+    Ok(())
+}
+
+/// Wait without blocking the browser
+#[cfg(target_arch = "wasm32")]
+async fn sleep(millis: u64) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .expect("no global `window` exists")
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis as i32)
+            .unwrap();
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+impl FetchFailure {
+    /// Draw the error page. The action is returned instead of run since the
+    /// fetch state is locked while the page is drawn
+    pub fn add_to_ui<T: HtmlParser + TelePager + Send + 'static>(
+        &self,
+        ui: &mut egui::Ui,
+        ctx: Rc<RefCell<&mut GuiContext<T>>>,
+    ) -> Option<ErrorAction> {
+        let page = ctx.borrow().current_page;
+        let has_cached = self.has_cached;
+
+        let mut action = None;
+        ui.heading(self.error.message(&page));
+        egui::Grid::new("error_details")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Address");
+                ui.label(&self.url);
+                ui.end_row();
+                ui.label("Status");
+                ui.label(match self.status {
+                    Some(status) => status.to_string(),
+                    None => "No response".to_string(),
+                });
+                ui.end_row();
+                ui.label("Error");
+                ui.label(self.error.category());
+                ui.end_row();
+                ui.label("Time");
+                ui.label(self.time.format("%d.%m. %H:%M:%S").to_string());
+                ui.end_row();
+            });
+
+        // The retry itself is started by `GuiContext::update`
+        if let Some(retry_at) = self.retry_at() {
+            let wait = retry_at - chrono::Local::now();
+            if wait > chrono::Duration::zero() {
+                ui.label(format!(
+                    "Retrying in {} s ({}/{})",
+                    wait.num_seconds() + 1,
+                    self.attempt + 1,
+                    MAX_RETRIES
+                ));
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_secs(1));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Retry").clicked() {
+                action = Some(ErrorAction::Retry { attempt: 0 });
+            }
+            if ui.button("Back").clicked() {
+                action = Some(ErrorAction::Back);
+            }
+            if ui.button("Go to home").clicked() {
+                action = Some(ErrorAction::Home);
+            }
+            let cached = egui::Button::new("Open cached copy");
+            if ui.add_enabled(has_cached, cached).clicked() {
+                action = Some(ErrorAction::OpenCached);
+            }
+        });
+
+        action
     }
 }
 
-impl HtmlItem {
+impl HtmlItem<'_> {
     pub fn add_to_ui<T: HtmlParser + TelePager + Send + 'static>(
         &self,
         ui: &mut egui::Ui,
@@ -515,20 +2062,115 @@ impl HtmlItem {
                 link.add_to_ui(ui, ctx);
             }
             HtmlItem::Text(text) => {
-                ui.label(text);
+                ui.label(text.as_ref());
             }
         }
     }
 }
 
-impl HtmlLink {
+impl HtmlLink<'_> {
     pub fn add_to_ui<T: HtmlParser + TelePager + Send + 'static>(
         &self,
         ui: &mut egui::Ui,
         ctx: Rc<RefCell<&mut GuiContext<T>>>,
     ) {
-        if ui.link(&self.inner_text).clicked() {
-            ctx.borrow_mut().load_page(&self.url, true);
+        let resp = ui.link(self.inner_text.as_ref());
+        if resp.clicked() {
+            ctx.borrow_mut().follow_link(&self.url);
+        } else if resp.middle_clicked() {
+            ctx.borrow_mut().open_in_new_tab(&self.url);
         }
     }
 }
+
+impl Fastext<'_> {
+    pub fn add_to_ui<T: HtmlParser + TelePager + Send + 'static>(
+        &self,
+        ui: &mut egui::Ui,
+        ctx: Rc<RefCell<&mut GuiContext<T>>>,
+    ) {
+        for (key, link) in self.links() {
+            let text = egui::RichText::new(link.inner_text.as_ref());
+            let button = match key.rgb() {
+                Some([r, g, b]) => {
+                    egui::Button::new(text.color(Color32::BLACK)).fill(Color32::from_rgb(r, g, b))
+                }
+                None => egui::Button::new(text),
+            };
+
+            let resp = ui.add(button);
+            if resp.clicked() {
+                ctx.borrow_mut().follow_link(&link.url);
+            } else if resp.middle_clicked() {
+                ctx.borrow_mut().open_in_new_tab(&link.url);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::YleText;
+
+    const URL: &str = "https://yle.fi/tekstitv/txt/100_0001.htm";
+
+    #[test]
+    fn retry_backoff() {
+        let mut failure = FetchFailure::new(FetchError::Network, URL, None);
+        for (attempt, delay) in [(0, 2), (1, 4), (2, 8), (5, 64)] {
+            failure.attempt = attempt;
+            let retry_at = failure.retry_at().unwrap();
+            assert_eq!((retry_at - failure.time).num_seconds(), delay);
+        }
+    }
+
+    #[test]
+    fn retry_stops_after_max_retries() {
+        let mut failure = FetchFailure::new(FetchError::Timeout, URL, None);
+        failure.attempt = MAX_RETRIES - 1;
+        assert!(failure.retry_at().is_some());
+        failure.attempt = MAX_RETRIES;
+        assert!(failure.retry_at().is_none());
+    }
+
+    #[test]
+    fn retried_errors() {
+        for error in [
+            FetchError::Network,
+            FetchError::Timeout,
+            FetchError::Http(503),
+        ] {
+            assert!(FetchFailure::new(error, URL, None).retry_at().is_some());
+        }
+
+        // Loading these again gives the same result
+        for error in [
+            FetchError::NotFound,
+            FetchError::Maintenance,
+            FetchError::Http(403),
+            FetchError::InvalidPage,
+        ] {
+            assert!(FetchFailure::new(error, URL, None).retry_at().is_none());
+        }
+    }
+
+    #[test]
+    fn stores_share_the_pages_of_the_app() {
+        let loader = HtmlLoader::new("tests/fixtures/yle_text/100.htm");
+        let body = loader.page_data.clone();
+        let parsed = <YleText as HtmlParser>::new().parse(loader).unwrap();
+
+        let pages = Arc::new(SharedPages::default());
+        let store = PageStore::new("yle", pages.clone());
+        store.add(URL, &TelePage::new(100, 1), &parsed, body);
+
+        let other = PageStore::new("other", pages);
+        assert!(other.cached(URL).is_some());
+        assert!(other.search("myrsky")[0].page == TelePage::new(100, 1));
+        // Pages of another app aren't shared
+        assert!(PageStore::new("yle", Default::default())
+            .cached(URL)
+            .is_none());
+    }
+}
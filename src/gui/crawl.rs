@@ -0,0 +1,254 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::{atomic::AtomicUsize, Arc, Mutex},
+};
+
+use super::common::TelePage;
+
+/// Pages outside of this range are not crawled
+pub const FIRST_PAGE: i32 = 100;
+pub const LAST_PAGE: i32 = 899;
+
+/// Pages waiting to be crawled and the pages already found.
+/// Saved when the app is closed so the crawl can continue where it was left
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct CrawlProgress {
+    queue: VecDeque<TelePage>,
+    /// Pages that have been added to the queue, by page and sub-page
+    found: BTreeSet<(i32, i32)>,
+    /// Pages that failed with a network error, they are retried once
+    retried: BTreeSet<(i32, i32)>,
+    pub loaded: usize,
+    pub failed: usize,
+    /// Crawl continues after restarting the app when set
+    pub running: bool,
+}
+
+impl Default for CrawlProgress {
+    fn default() -> Self {
+        let first = TelePage::new(FIRST_PAGE, 1);
+        Self {
+            queue: VecDeque::from([first]),
+            found: BTreeSet::from([(first.page, first.sub_page)]),
+            retried: BTreeSet::new(),
+            loaded: 0,
+            failed: 0,
+            running: false,
+        }
+    }
+}
+
+impl CrawlProgress {
+    /// Page that is crawled next. It stays in the queue until it's loaded
+    pub fn next_page(&self) -> Option<TelePage> {
+        self.queue.front().copied()
+    }
+
+    /// Remove the next page from the queue and add the pages it links to
+    pub fn page_loaded(&mut self, links: Vec<TelePage>) {
+        self.queue.pop_front();
+        self.loaded += 1;
+        for link in links {
+            let in_range = (FIRST_PAGE..=LAST_PAGE).contains(&link.page);
+            if in_range && self.found.insert((link.page, link.sub_page)) {
+                self.queue.push_back(link);
+            }
+        }
+    }
+
+    /// Remove the next page from the queue. Pages that failed with transient
+    /// errors are tried again after the rest of the queue
+    pub fn page_failed(&mut self, transient: bool) {
+        let page = match self.queue.pop_front() {
+            Some(page) => page,
+            None => return,
+        };
+
+        if transient && self.retried.insert((page.page, page.sub_page)) {
+            self.queue.push_back(page);
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    /// Queue the found pages that are not in the queue and not stored anymore, e.g.
+    /// the pages kept in memory before the app was restarted. Failed pages are tried again
+    pub fn requeue_missing(&mut self, is_stored: impl Fn(&TelePage) -> bool) {
+        let queued: BTreeSet<(i32, i32)> = self
+            .queue
+            .iter()
+            .map(|page| (page.page, page.sub_page))
+            .collect();
+        for &(page, sub_page) in self.found.difference(&queued) {
+            let page = TelePage::new(page, sub_page);
+            if !is_stored(&page) {
+                self.queue.push_back(page);
+            }
+        }
+
+        // Pages that are not queued are stored
+        self.loaded = self.found.len() - self.queue.len();
+        self.failed = 0;
+    }
+
+    /// Whether any page has been crawled
+    pub fn is_started(&self) -> bool {
+        self.running || self.loaded + self.failed > 0
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Share of the found pages that have been crawled
+    pub fn fraction(&self) -> f32 {
+        let done = self.loaded + self.failed;
+        match done + self.queue.len() {
+            0 => 1.0,
+            total => done as f32 / total as f32,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let status = format!(
+            "{} pages loaded, {} failed, {} queued",
+            self.loaded,
+            self.failed,
+            self.queue.len()
+        );
+        match self.next_page() {
+            Some(next) if self.running => format!("Loading P{}, {}", next.page, status),
+            _ => status,
+        }
+    }
+}
+
+/// Crawl of a source. The readers of the source share it so the pages are crawled once
+/// whichever of them starts the crawl
+#[derive(Clone, Default)]
+pub struct SourceCrawl {
+    pub progress: Arc<Mutex<CrawlProgress>>,
+    /// Incremented when the crawl is started or stopped so the previous crawl stops
+    pub id: Arc<AtomicUsize>,
+}
+
+/// Crawls of the app by the source of the reader, see `PageStore::source`.
+/// A crawl keeps running when the tab that started it is closed
+#[derive(Default)]
+pub struct PageCrawler {
+    crawls: Mutex<HashMap<String, SourceCrawl>>,
+}
+
+impl PageCrawler {
+    pub fn crawl(&self, source: &str) -> SourceCrawl {
+        let mut crawls = self.crawls.lock().unwrap();
+        crawls.entry(source.to_string()).or_default().clone()
+    }
+
+    /// Crawls that have been started by the source, saved when the app is closed
+    pub fn saved(&self) -> BTreeMap<String, CrawlProgress> {
+        let crawls = self.crawls.lock().unwrap();
+        crawls
+            .iter()
+            .map(|(source, crawl)| (source.clone(), crawl.progress.lock().unwrap().clone()))
+            .filter(|(_, progress)| progress.is_started())
+            .collect()
+    }
+}
+
+pub const CRAWLS_KEY: &str = "crawls";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crawl_follows_links() {
+        let mut crawl = CrawlProgress::default();
+        let first = crawl.next_page().unwrap();
+        assert_eq!((first.page, first.sub_page), (100, 1));
+
+        // Links outside the range and pages that were already found are not queued
+        crawl.page_loaded(vec![
+            TelePage::new(100, 1),
+            TelePage::new(100, 2),
+            TelePage::new(101, 1),
+            TelePage::new(999, 1),
+        ]);
+        crawl.page_loaded(vec![TelePage::new(101, 1), TelePage::new(102, 1)]);
+        assert_eq!(crawl.loaded, 2);
+        assert_eq!(crawl.queued(), 2);
+
+        let next = crawl.next_page().unwrap();
+        assert_eq!((next.page, next.sub_page), (101, 1));
+        assert_eq!(crawl.fraction(), 0.5);
+
+        crawl.page_loaded(Vec::new());
+        crawl.page_loaded(Vec::new());
+        assert!(crawl.is_finished());
+        assert_eq!(crawl.fraction(), 1.0);
+    }
+
+    #[test]
+    fn crawl_retries_transient_errors_once() {
+        let mut crawl = CrawlProgress::default();
+        crawl.page_loaded(vec![TelePage::new(101, 1), TelePage::new(102, 1)]);
+
+        // Page is moved to the end of the queue
+        crawl.page_failed(true);
+        assert_eq!(crawl.next_page().unwrap().page, 102);
+        assert_eq!(crawl.failed, 0);
+
+        crawl.page_failed(false);
+        assert_eq!(crawl.failed, 1);
+        crawl.page_failed(true);
+        assert_eq!(crawl.failed, 2);
+        assert!(crawl.is_finished());
+    }
+
+    #[test]
+    fn crawl_requeues_missing_pages() {
+        let mut crawl = CrawlProgress::default();
+        crawl.page_loaded(vec![
+            TelePage::new(101, 1),
+            TelePage::new(102, 1),
+            TelePage::new(103, 1),
+        ]);
+        crawl.page_loaded(Vec::new());
+        crawl.page_failed(false);
+        assert_eq!((crawl.loaded, crawl.failed, crawl.queued()), (2, 1, 1));
+
+        // Only 101 was kept, 100 is loaded again and the failed 102 is tried again
+        crawl.requeue_missing(|page| page.page == 101);
+        assert_eq!((crawl.loaded, crawl.failed, crawl.queued()), (1, 0, 3));
+        let mut queued = Vec::new();
+        while let Some(page) = crawl.next_page() {
+            queued.push(page.page);
+            crawl.page_loaded(Vec::new());
+        }
+        assert_eq!(queued, [103, 100, 102]);
+        assert_eq!(crawl.loaded, 4);
+    }
+
+    #[test]
+    fn readers_of_source_share_crawl() {
+        let crawler = PageCrawler::default();
+        let crawl = crawler.crawl("yle_text");
+        crawl.progress.lock().unwrap().running = true;
+
+        // Another tab of the same reader continues the same crawl
+        let other = crawler.crawl("yle_text");
+        assert!(Arc::ptr_eq(&crawl.progress, &other.progress));
+        assert!(Arc::ptr_eq(&crawl.id, &other.id));
+        assert!(!crawler.crawl("svt_text").progress.lock().unwrap().running);
+
+        // Only the started crawls are saved
+        let saved = crawler.saved();
+        assert_eq!(saved.keys().collect::<Vec<_>>(), ["yle_text"]);
+        assert!(saved["yle_text"].running);
+    }
+}
@@ -0,0 +1,325 @@
+use egui::{Align, InputState, Layout, Rect, Sense, Ui, Vec2};
+
+use super::{common::TelePage, font_styles, OptionSetting, Pages, Shared, Tab, TeleTextSettings};
+
+/// Page of a dashboard tile as it's saved in the settings
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub(super) struct TileSetting {
+    reader: Pages,
+    page: TelePage,
+    refresh_interval: OptionSetting<u64>,
+    /// Seconds each sub-page is shown, tiles without it use the carousel of the tabs
+    carousel_interval: OptionSetting<u64>,
+}
+
+/// Named grid of pages
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(super) struct Dashboard {
+    pub(super) name: String,
+    columns: usize,
+    /// Tiles have their own font size so more pages fit on the screen
+    font_size: f32,
+    tiles: Vec<TileSetting>,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            columns: 2,
+            font_size: 10.0,
+            tiles: Vec::new(),
+        }
+    }
+}
+
+/// Reader of a tile. The rect is from the previous frame
+struct Tile {
+    tab: Tab,
+    rect: Option<Rect>,
+    /// Carousel settings of the tabs are not applied to tiles with their own interval
+    own_carousel: bool,
+}
+
+/// Dashboard that is open, every tile is an independent reader
+pub(super) struct DashboardView {
+    pub(super) name: String,
+    columns: usize,
+    font_size: f32,
+    tiles: Vec<Tile>,
+    /// Keys and pointer buttons control this tile, it's changed by clicking a tile
+    active: usize,
+}
+
+impl DashboardView {
+    pub(super) fn new(
+        dashboard: &Dashboard,
+        egui: &egui::Context,
+        shared: &Shared,
+        settings: &TeleTextSettings,
+    ) -> Self {
+        let tiles = dashboard
+            .tiles
+            .iter()
+            .map(|tile| {
+                let mut tab = Tab::new(
+                    tile.reader.clone(),
+                    tile.refresh_interval.clone(),
+                    egui,
+                    shared,
+                    settings,
+                );
+                let own_carousel = tile.carousel_interval.is_used;
+                if own_carousel {
+                    tab.page.set_carousel_interval(tile.carousel_interval.value);
+                }
+                tab.page.open_page(tile.page);
+                Tile {
+                    tab,
+                    rect: None,
+                    own_carousel,
+                }
+            })
+            .collect();
+
+        Self {
+            name: dashboard.name.clone(),
+            columns: dashboard.columns,
+            font_size: dashboard.font_size,
+            tiles,
+            active: 0,
+        }
+    }
+
+    /// Tile that the menu windows act on while the dashboard is shown
+    pub(super) fn active_tab(&mut self) -> Option<&mut Tab> {
+        self.tiles.get_mut(self.active).map(|tile| &mut tile.tab)
+    }
+
+    /// Apply the carousel and prefetch settings of the tabs that were changed
+    pub(super) fn apply_settings(&mut self, settings: &TeleTextSettings) {
+        for tile in &mut self.tiles {
+            if !tile.own_carousel {
+                settings.set_carousel_interval(&mut tile.tab.page);
+            }
+            tile.tab.page.set_prefetch(settings.prefetch);
+        }
+    }
+
+    /// Only the active tile gets the input so typing a page number changes one tile
+    pub(super) fn handle_input(&mut self, input: &InputState) {
+        if input.pointer.any_pressed() {
+            if let Some(pos) = input.pointer.interact_pos() {
+                let clicked = self
+                    .tiles
+                    .iter()
+                    .position(|tile| tile.rect.is_some_and(|rect| rect.contains(pos)));
+                if let Some(idx) = clicked {
+                    self.active = idx;
+                }
+            }
+        }
+
+        for (idx, tile) in self.tiles.iter_mut().enumerate() {
+            let input = if idx == self.active {
+                input.clone()
+            } else {
+                InputState::default()
+            };
+            tile.tab.page.handle_input(input);
+        }
+    }
+
+    pub(super) fn update(&mut self) {
+        for tile in &mut self.tiles {
+            tile.tab.page.update();
+        }
+    }
+
+    pub(super) fn draw(&mut self, ui: &mut Ui) {
+        if self.tiles.is_empty() {
+            ui.label("The dashboard has no pages, add them in the Dashboards window");
+            return;
+        }
+
+        let columns = self.columns.max(1);
+        let rows = self.tiles.len().div_ceil(columns);
+        let area = ui.available_rect_before_wrap();
+        let size = Vec2::new(area.width() / columns as f32, area.height() / rows as f32);
+        let text_styles = font_styles(self.font_size);
+        let stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+        let active_stroke = ui.visuals().selection.stroke;
+
+        for (idx, tile) in self.tiles.iter_mut().enumerate() {
+            let offset = Vec2::new(
+                (idx % columns) as f32 * size.x,
+                (idx / columns) as f32 * size.y,
+            );
+            let rect = Rect::from_min_size(area.min + offset, size).shrink(2.0);
+
+            // Pages that don't fit in the tile are cut at its border
+            let mut tile_ui =
+                ui.child_ui_with_id_source(rect.shrink(4.0), Layout::top_down(Align::Min), idx);
+            tile_ui.set_clip_rect(rect);
+            tile_ui.style_mut().text_styles = text_styles.clone();
+            tile.tab.page.draw(&mut tile_ui);
+
+            let stroke = if idx == self.active {
+                active_stroke
+            } else {
+                stroke
+            };
+            ui.painter().rect_stroke(rect, 0.0, stroke);
+            tile.rect = Some(rect);
+        }
+
+        ui.allocate_rect(area, Sense::hover());
+    }
+}
+
+/// State of the dashboards window
+#[derive(Default)]
+pub(super) struct DashboardWindow {
+    /// Name of the next new dashboard
+    name: String,
+}
+
+/// Edit the dashboards saved in the settings. Changes to the tiles are shown
+/// when the dashboard is opened again
+pub(super) fn dashboards_window_ui(
+    ui: &mut Ui,
+    egui: &egui::Context,
+    window: &mut DashboardWindow,
+    settings: &mut TeleTextSettings,
+    tab: &Tab,
+    shared: &Shared,
+    view: &mut Option<DashboardView>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.text_edit_singleline(&mut window.name);
+        let name = window.name.trim();
+        let is_new = !name.is_empty() && settings.dashboards.iter().all(|d| d.name != name);
+        if ui.add_enabled(is_new, egui::Button::new("New")).clicked() {
+            settings.dashboards.push(Dashboard {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            window.name.clear();
+        }
+    });
+    ui.separator();
+
+    let readers = Pages::readers(&shared.providers);
+    let carousel_interval = settings.carousel_interval.clone();
+    let mut open = None;
+    let mut delete = None;
+    for (idx, dashboard) in settings.dashboards.iter_mut().enumerate() {
+        egui::CollapsingHeader::new(&dashboard.name)
+            .id_source(idx)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        open = Some(idx);
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = Some(idx);
+                    }
+                });
+
+                let is_open = view.as_ref().is_some_and(|v| v.name == dashboard.name);
+                ui.horizontal(|ui| {
+                    ui.label("Columns");
+                    let columns = egui::DragValue::new(&mut dashboard.columns).clamp_range(1..=8);
+                    if ui.add(columns).changed() && is_open {
+                        if let Some(view) = view.as_mut() {
+                            view.columns = dashboard.columns;
+                        }
+                    }
+                });
+                let font_size =
+                    egui::Slider::new(&mut dashboard.font_size, 6.0..=48.0).text("Font size");
+                if ui.add(font_size).changed() && is_open {
+                    if let Some(view) = view.as_mut() {
+                        view.font_size = dashboard.font_size;
+                    }
+                }
+
+                let mut remove = None;
+                egui::Grid::new(("dashboard_tiles", idx))
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (tile_idx, tile) in dashboard.tiles.iter_mut().enumerate() {
+                            tile_settings_ui(ui, (idx, tile_idx), tile, &readers);
+                            if ui.button("Remove").clicked() {
+                                remove = Some(tile_idx);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(tile_idx) = remove {
+                    dashboard.tiles.remove(tile_idx);
+                }
+
+                if ui.button("Add current page").clicked() {
+                    dashboard.tiles.push(TileSetting {
+                        reader: tab.reader.clone(),
+                        page: tab.page.current_page(),
+                        refresh_interval: tab.refresh_interval.clone(),
+                        carousel_interval: carousel_interval.clone(),
+                    });
+                }
+            });
+    }
+
+    if let Some(idx) = delete {
+        let dashboard = settings.dashboards.remove(idx);
+        if view.as_ref().is_some_and(|v| v.name == dashboard.name) {
+            *view = None;
+            settings.open_dashboard = None;
+        }
+    } else if let Some(idx) = open {
+        let dashboard = &settings.dashboards[idx];
+        *view = Some(DashboardView::new(dashboard, egui, shared, settings));
+        settings.open_dashboard = Some(dashboard.name.clone());
+    }
+}
+
+fn tile_settings_ui(ui: &mut Ui, id: (usize, usize), tile: &mut TileSetting, readers: &[Pages]) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(tile.reader.name())
+        .show_ui(ui, |ui| {
+            for reader in readers {
+                ui.selectable_value(&mut tile.reader, reader.clone(), reader.name());
+            }
+        });
+
+    ui.add(
+        egui::DragValue::new(&mut tile.page.page)
+            .prefix("P")
+            .clamp_range(100..=899),
+    );
+
+    interval_ui(ui, "Refresh", &mut tile.refresh_interval, 30..=1800);
+    interval_ui(ui, "Carousel", &mut tile.carousel_interval, 3..=120);
+}
+
+fn interval_ui(
+    ui: &mut Ui,
+    name: &str,
+    interval: &mut OptionSetting<u64>,
+    range: std::ops::RangeInclusive<u64>,
+) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut interval.is_used, name);
+        if interval.is_used {
+            ui.add(
+                egui::DragValue::new(&mut interval.value)
+                    .suffix(" s")
+                    .clamp_range(range),
+            );
+        }
+    });
+}
@@ -0,0 +1,402 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::archive::MAX_VERSIONS;
+use super::common::TelePage;
+use super::search::{query_words, SearchResult, MAX_RESULTS};
+
+/// Schema changes in order. `user_version` of the database tells how many have been run
+const MIGRATIONS: [&str; 3] = [
+    // Responses of the pages, a new row is added only when the content changes
+    "CREATE TABLE responses (
+        id INTEGER PRIMARY KEY,
+        url TEXT NOT NULL,
+        page INTEGER NOT NULL,
+        sub_page INTEGER NOT NULL,
+        body TEXT NOT NULL,
+        -- What the user sees, NULL when it's the body
+        content TEXT,
+        -- Parsed page as json
+        model TEXT,
+        fetched_at TEXT NOT NULL,
+        -- Last fetch that returned the same content
+        checked_at TEXT NOT NULL
+    );
+    CREATE INDEX responses_url ON responses (url, id);",
+    // Lines of the latest version of each page for the search
+    "CREATE VIRTUAL TABLE page_lines USING fts5(
+        line,
+        title UNINDEXED,
+        source UNINDEXED,
+        url UNINDEXED,
+        page UNINDEXED,
+        sub_page UNINDEXED,
+        row UNINDEXED
+    );",
+    // Sources were type names of the readers and diacritics were removed from the words,
+    // the search of the index kept in memory doesn't remove them
+    "CREATE VIRTUAL TABLE page_words USING fts5(
+        line,
+        title UNINDEXED,
+        source UNINDEXED,
+        url UNINDEXED,
+        page UNINDEXED,
+        sub_page UNINDEXED,
+        row UNINDEXED,
+        tokenize = 'unicode61 remove_diacritics 0'
+    );
+    INSERT INTO page_words (line, title, source, url, page, sub_page, row)
+    SELECT line, title, CASE
+        WHEN source LIKE '%::YleText' THEN 'yle_text'
+        WHEN source LIKE '%::YleImage' THEN 'yle_image'
+        WHEN source LIKE '%::SvtText' THEN 'svt_text'
+        WHEN source LIKE '%::DrText' THEN 'dr_text'
+        ELSE source
+    END, url, page, sub_page, row FROM page_lines;
+    DROP TABLE page_lines;
+    ALTER TABLE page_words RENAME TO page_lines;",
+];
+
+/// Name the app is run with in `main`, eframe keeps the saved state in its data directory
+const APP_NAME: &str = "Teletext Reader";
+
+/// Fetched page that is stored in the database
+pub struct StoredPage<'a> {
+    /// Reader the page was loaded with, search results are opened with the same reader
+    pub source: &'a str,
+    pub url: &'a str,
+    pub page: &'a TelePage,
+    pub body: &'a str,
+    /// Versions are compared by the content instead of the response
+    pub content: &'a str,
+    pub model: Option<String>,
+    /// Title and lines of the page for the search
+    pub text: Option<(String, Vec<String>)>,
+    pub time: chrono::DateTime<chrono::Local>,
+}
+
+/// Responses, archive and search index stored in a SQLite database
+pub struct PageDatabase {
+    conn: Mutex<Connection>,
+}
+
+impl PageDatabase {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(mut conn: Connection) -> rusqlite::Result<Self> {
+        Self::migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (idx + 1) as i64)?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Store the response. Returns true if the content changed and a new version was added
+    pub fn add(&self, stored: &StoredPage) -> rusqlite::Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let latest: Option<(i64, String)> = tx
+            .query_row(
+                "SELECT id, coalesce(content, body) FROM responses
+                WHERE url = ?1 ORDER BY id DESC LIMIT 1",
+                params![stored.url],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        let is_new = match latest {
+            Some((id, content)) if content == stored.content => {
+                tx.execute(
+                    "UPDATE responses SET checked_at = ?1 WHERE id = ?2",
+                    params![stored.time, id],
+                )?;
+                false
+            }
+            _ => {
+                let content = (stored.content != stored.body).then_some(stored.content);
+                tx.execute(
+                    "INSERT INTO responses
+                    (url, page, sub_page, body, content, model, fetched_at, checked_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                    params![
+                        stored.url,
+                        stored.page.page,
+                        stored.page.sub_page,
+                        stored.body,
+                        content,
+                        stored.model,
+                        stored.time
+                    ],
+                )?;
+                // Oldest versions are removed like in the archive kept in memory
+                tx.execute(
+                    "DELETE FROM responses WHERE url = ?1 AND id NOT IN
+                    (SELECT id FROM responses WHERE url = ?1 ORDER BY id DESC LIMIT ?2)",
+                    params![stored.url, MAX_VERSIONS as i64],
+                )?;
+                Self::index_page(&tx, stored)?;
+                true
+            }
+        };
+
+        tx.commit()?;
+        Ok(is_new)
+    }
+
+    /// Replace the lines of the older version in the search index
+    fn index_page(conn: &Connection, stored: &StoredPage) -> rusqlite::Result<()> {
+        let (title, lines) = match &stored.text {
+            Some(text) => text,
+            None => return Ok(()),
+        };
+
+        conn.execute("DELETE FROM page_lines WHERE url = ?1", params![stored.url])?;
+        let mut insert = conn.prepare(
+            "INSERT INTO page_lines (line, title, source, url, page, sub_page, row)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for (row, line) in lines.iter().enumerate() {
+            insert.execute(params![
+                line,
+                title,
+                stored.source,
+                stored.url,
+                stored.page.page,
+                stored.page.sub_page,
+                row as i64
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    /// Latest response of the page and the last time it was fetched
+    pub fn latest(
+        &self,
+        url: &str,
+    ) -> rusqlite::Result<Option<(String, chrono::DateTime<chrono::Local>)>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT body, checked_at FROM responses WHERE url = ?1 ORDER BY id DESC LIMIT 1",
+                params![url],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+
+    /// Fetch times of the versions of the page, oldest first
+    pub fn version_times(
+        &self,
+        url: &str,
+    ) -> rusqlite::Result<Vec<chrono::DateTime<chrono::Local>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut select =
+            conn.prepare("SELECT fetched_at FROM responses WHERE url = ?1 ORDER BY id")?;
+        let times = select.query_map(params![url], |row| row.get(0))?;
+        times.collect()
+    }
+
+    /// Response of the version in the order of `version_times`
+    pub fn version(
+        &self,
+        url: &str,
+        idx: usize,
+    ) -> rusqlite::Result<Option<(String, chrono::DateTime<chrono::Local>)>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT body, fetched_at FROM responses WHERE url = ?1
+                ORDER BY id LIMIT 1 OFFSET ?2",
+                params![url, idx as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+
+    /// Lines with words starting with the words of the query, in page order
+    pub fn search(&self, query: &str) -> rusqlite::Result<Vec<SearchResult>> {
+        // Words are quoted so the query syntax of fts5 isn't used
+        let words: Vec<String> = query_words(query)
+            .iter()
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let mut select = conn.prepare(
+            "SELECT source, page, sub_page, title, line, row FROM page_lines
+            WHERE page_lines MATCH ?1
+            ORDER BY page, sub_page, source, row LIMIT ?2",
+        )?;
+        let results = select.query_map(params![words.join(" "), MAX_RESULTS as i64], |row| {
+            let line: String = row.get(4)?;
+            Ok(SearchResult {
+                source: row.get(0)?,
+                page: TelePage::new(row.get(1)?, row.get(2)?),
+                title: row.get(3)?,
+                line: line.trim().to_string(),
+                row: row.get::<_, i64>(5)? as usize,
+            })
+        })?;
+        results.collect()
+    }
+}
+
+/// Database of the app, None if it cannot be opened. It's next to the state eframe saves,
+/// the path can be changed with `TELETEXT_DATABASE` env variable
+pub fn open() -> Option<PageDatabase> {
+    let path = match std::env::var("TELETEXT_DATABASE") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => data_dir()?.join("teletext.db"),
+    };
+
+    PageDatabase::open(&path)
+        .map_err(|err| log::warn!("Cannot open database {}: {}", path.display(), err))
+        .ok()
+}
+
+/// Data directory of the app, created if it doesn't exist
+fn data_dir() -> Option<PathBuf> {
+    let dirs = directories_next::ProjectDirs::from("", "", APP_NAME)?;
+    let dir = dirs.data_dir();
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        log::warn!("Cannot create directory {}: {}", dir.display(), err);
+        return None;
+    }
+
+    Some(dir.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::search::tests::{found, PAGES, QUERIES};
+    use super::*;
+    use chrono::{Duration, Local};
+
+    const URL: &str = "https://yle.fi/tekstitv/txt/190_0001.htm";
+
+    fn stored<'a>(page: &'a TelePage, body: &'a str, line: &str, minutes: i64) -> StoredPage<'a> {
+        StoredPage {
+            source: "yle",
+            url: URL,
+            page,
+            body,
+            content: body,
+            model: None,
+            text: Some(("JÄÄKIEKKO".into(), vec!["".into(), line.into()])),
+            time: Local::now() + Duration::minutes(minutes),
+        }
+    }
+
+    #[test]
+    fn database_archives_changed_versions() {
+        let database = PageDatabase::open_in_memory().unwrap();
+        let page = TelePage::new(190, 1);
+
+        assert!(database
+            .add(&stored(&page, "1-3", "HIFK - Kärpät 1-3", 0))
+            .unwrap());
+        assert!(!database
+            .add(&stored(&page, "1-3", "HIFK - Kärpät 1-3", 1))
+            .unwrap());
+        assert!(database
+            .add(&stored(&page, "2-3", "HIFK - Kärpät 2-3", 2))
+            .unwrap());
+
+        assert_eq!(database.version_times(URL).unwrap().len(), 2);
+        let (body, _) = database.version(URL, 0).unwrap().unwrap();
+        assert_eq!(body, "1-3");
+        let (body, _) = database.latest(URL).unwrap().unwrap();
+        assert_eq!(body, "2-3");
+    }
+
+    #[test]
+    fn database_removes_oldest_versions() {
+        let database = PageDatabase::open_in_memory().unwrap();
+        let page = TelePage::new(190, 1);
+        // One more than the versions kept of each page
+        for minutes in 0..=200 {
+            let body = format!("{minutes}-3");
+            database.add(&stored(&page, &body, &body, minutes)).unwrap();
+        }
+
+        assert_eq!(database.version_times(URL).unwrap().len(), 200);
+        let (body, _) = database.version(URL, 0).unwrap().unwrap();
+        assert_eq!(body, "1-3");
+    }
+
+    #[test]
+    fn database_search() {
+        let database = PageDatabase::open_in_memory().unwrap();
+        let page = TelePage::new(190, 1);
+        database
+            .add(&stored(&page, "1-3", "HIFK - Kärpät 1-3", 0))
+            .unwrap();
+        database
+            .add(&stored(&page, "2-3", "HIFK - Kärpät 2-3", 1))
+            .unwrap();
+
+        // Only the latest version is searched
+        let results = database.search("kärp").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].source, "yle");
+        assert_eq!(results[0].line, "HIFK - Kärpät 2-3");
+        assert_eq!(results[0].row, 1);
+        assert!(database.search("\"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn database_search_matches_index() {
+        let database = PageDatabase::open_in_memory().unwrap();
+        for (page, title, lines) in PAGES {
+            let url = format!("https://yle.fi/tekstitv/txt/{}_0001.htm", page);
+            let stored = StoredPage {
+                source: "yle_text",
+                url: &url,
+                page: &TelePage::new(page, 1),
+                body: "",
+                content: "",
+                model: None,
+                text: Some((title.into(), lines.iter().map(|l| l.to_string()).collect())),
+                time: chrono::Local::now(),
+            };
+            database.add(&stored).unwrap();
+        }
+
+        for (query, expected) in QUERIES {
+            assert_eq!(
+                found(&database.search(query).unwrap()),
+                expected,
+                "{}",
+                query
+            );
+        }
+    }
+}
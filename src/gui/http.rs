@@ -0,0 +1,286 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use reqwest::{
+    blocking::{Client, Response},
+    header::{self, HeaderName},
+    StatusCode,
+};
+
+use super::common::{check_status, FetchError, FetchFailure, PageResponse};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time for the whole request, including reading the body
+const READ_TIMEOUT: Duration = Duration::from_secs(15);
+/// Server errors and timeouts are retried before the failure is shown
+const MAX_RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/Nykseli/teletext-gui)"
+);
+
+/// Validators of the last response so the next request of the url can be conditional.
+/// The body isn't kept here since the caller has it in its own cache
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Http client of the native builds, shared by all pages, see `client`
+pub struct HttpClient {
+    client: Client,
+    validators: Mutex<HashMap<String, Validators>>,
+}
+
+impl HttpClient {
+    pub fn new(user_agent: &str) -> reqwest::Result<Self> {
+        // Gzip responses are decompressed by reqwest since the `gzip` feature is enabled
+        let client = Client::builder()
+            .user_agent(user_agent)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(READ_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            client,
+            validators: Default::default(),
+        })
+    }
+
+    /// Get the page, retrying server errors and timeouts. `cached` is the body of the
+    /// last response of the url, the request is conditional if it's given. Status of
+    /// the response is 304 and the body is the cached one if the page hasn't changed
+    pub fn get(&self, url: &str, cached: Option<&str>) -> Result<PageResponse, FetchFailure> {
+        let mut attempt = 0;
+        loop {
+            match self.try_get(url, cached) {
+                Err(failure) if attempt < MAX_RETRIES && failure.error.is_transient() => {
+                    attempt += 1;
+                    log::info!("Retrying {} ({}/{})", url, attempt, MAX_RETRIES);
+                    thread::sleep(RETRY_DELAY * attempt);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn try_get(&self, url: &str, cached: Option<&str>) -> Result<PageResponse, FetchFailure> {
+        let mut response = self.send(url, cached.is_some())?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            match cached {
+                Some(body) => {
+                    return Ok(PageResponse {
+                        status: StatusCode::NOT_MODIFIED.as_u16(),
+                        body: body.to_string(),
+                    })
+                }
+                // Server answered a request that wasn't conditional, there's no body to show
+                None => {
+                    self.validators.lock().unwrap().remove(url);
+                    response = self.send(url, false)?;
+                }
+            }
+        }
+
+        let status = response.status();
+        check_status(url, status)?;
+
+        let etag = Self::header_str(&response, header::ETAG);
+        let last_modified = Self::header_str(&response, header::LAST_MODIFIED);
+        let body = response
+            .text()
+            .map_err(|err| Self::request_failure(url, &err, Some(status.as_u16())))?;
+        if etag.is_some() || last_modified.is_some() {
+            let validators = Validators {
+                etag,
+                last_modified,
+            };
+            self.validators
+                .lock()
+                .unwrap()
+                .insert(url.to_string(), validators);
+        }
+
+        Ok(PageResponse {
+            status: status.as_u16(),
+            body,
+        })
+    }
+
+    fn send(&self, url: &str, is_conditional: bool) -> Result<Response, FetchFailure> {
+        let mut request = self.client.get(url);
+        if is_conditional {
+            if let Some(validators) = self.validators.lock().unwrap().get(url) {
+                if let Some(etag) = &validators.etag {
+                    request = request.header(header::IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+        }
+
+        request
+            .send()
+            .map_err(|err| Self::request_failure(url, &err, None))
+    }
+
+    fn header_str(response: &Response, name: HeaderName) -> Option<String> {
+        let value = response.headers().get(name)?;
+        value.to_str().ok().map(str::to_string)
+    }
+
+    fn request_failure(url: &str, err: &reqwest::Error, status: Option<u16>) -> FetchFailure {
+        let error = if err.is_timeout() {
+            FetchError::Timeout
+        } else {
+            FetchError::Network
+        };
+
+        FetchFailure::new(error, url, status)
+    }
+}
+
+/// Client shared by all pages.
+/// User agent can be changed with `TELETEXT_USER_AGENT` env variable
+pub fn client() -> &'static HttpClient {
+    static CLIENT: OnceLock<HttpClient> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        let user_agent =
+            std::env::var("TELETEXT_USER_AGENT").unwrap_or_else(|_| DEFAULT_USER_AGENT.into());
+        HttpClient::new(&user_agent)
+            .or_else(|_| {
+                log::warn!("Invalid user agent: {}", user_agent);
+                HttpClient::new(DEFAULT_USER_AGENT)
+            })
+            .expect("Failed to create the http client")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+    };
+
+    /// Serve the responses in order, one per connection, and send the received requests back
+    fn serve(responses: Vec<&'static str>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/100_0001.htm", listener.local_addr().unwrap());
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                }
+
+                sender
+                    .send(String::from_utf8(request).unwrap().to_lowercase())
+                    .unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    const PAGE: &str = "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nLast-Modified: Tue, 14 Feb 2023 10:00:00 GMT\r\nContent-Length: 4\r\nConnection: close\r\n\r\npage";
+    const NOT_MODIFIED: &str = "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n";
+    const NOT_FOUND: &str =
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const FORBIDDEN: &str =
+        "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    #[test]
+    fn conditional_request() {
+        let (url, requests) = serve(vec![PAGE, NOT_MODIFIED]);
+        let client = HttpClient::new(DEFAULT_USER_AGENT).unwrap();
+
+        let response = client.get(&url, None).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "page");
+        let request = requests.recv().unwrap();
+        assert!(request.contains(&format!(
+            "user-agent: {}",
+            DEFAULT_USER_AGENT.to_lowercase()
+        )));
+        assert!(!request.contains("if-none-match"));
+
+        let response = client.get(&url, Some(&response.body)).unwrap();
+        assert_eq!(response.status, 304);
+        assert_eq!(response.body, "page");
+        let request = requests.recv().unwrap();
+        assert!(request.contains("if-none-match: \"v1\""));
+        assert!(request.contains("if-modified-since: tue, 14 feb 2023 10:00:00 gmt"));
+    }
+
+    #[test]
+    fn not_modified_without_cached_body() {
+        let (url, requests) = serve(vec![PAGE, NOT_MODIFIED, PAGE]);
+        let client = HttpClient::new(DEFAULT_USER_AGENT).unwrap();
+        client.get(&url, None).unwrap();
+        requests.recv().unwrap();
+
+        // Requests without the cached body aren't conditional, and the page is requested
+        // again if the server still says it's not modified
+        let response = client.get(&url, None).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "page");
+        assert!(!requests.recv().unwrap().contains("if-none-match"));
+        assert!(!requests.recv().unwrap().contains("if-none-match"));
+    }
+
+    #[test]
+    fn status_errors() {
+        let (url, _requests) = serve(vec![NOT_FOUND, FORBIDDEN]);
+        let client = HttpClient::new(DEFAULT_USER_AGENT).unwrap();
+
+        let failure = client.get(&url, None).unwrap_err();
+        assert_eq!(failure.error, FetchError::NotFound);
+        assert_eq!(failure.status, Some(404));
+        assert_eq!(failure.url, url);
+
+        let failure = client.get(&url, None).unwrap_err();
+        assert_eq!(failure.error, FetchError::Http(403));
+        assert_eq!(failure.status, Some(403));
+    }
+
+    #[test]
+    fn retry_server_errors() {
+        let (url, requests) = serve(vec![UNAVAILABLE, UNAVAILABLE, PAGE]);
+        let client = HttpClient::new(DEFAULT_USER_AGENT).unwrap();
+
+        let response = client.get(&url, None).unwrap();
+        assert_eq!(response.body, "page");
+        assert_eq!(requests.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn server_errors_give_up() {
+        let (url, requests) = serve(vec![UNAVAILABLE, UNAVAILABLE, UNAVAILABLE]);
+        let client = HttpClient::new(DEFAULT_USER_AGENT).unwrap();
+
+        let failure = client.get(&url, None).unwrap_err();
+        assert_eq!(failure.error, FetchError::Http(503));
+        assert_eq!(failure.status, Some(503));
+        assert_eq!(requests.try_iter().count(), 3);
+    }
+}
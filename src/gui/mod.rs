@@ -1,19 +1,51 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
+mod archive;
 mod common;
+mod crawl;
+mod dashboard;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod database;
+mod dr_text;
+#[cfg(not(target_arch = "wasm32"))]
+mod http;
+mod print;
+mod search;
+mod selection;
 mod svg_icon;
+mod svt_text;
+mod tabs;
+mod teletext;
+mod watch;
 mod yle_image;
 mod yle_text;
 use egui::{Color32, FontFamily, FontId, Style, TextStyle, Ui};
 
-use self::common::{GuiContext, IGuiCtx};
+use crate::parser::{
+    DrText, ExportFormat, HtmlColors, HtmlParser, ImageFormat, ProviderConfig, SvtText, YleImage,
+    YleText,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use self::common::FetchError;
+use self::common::{GuiContext, IGuiCtx, SharedPages};
+pub use self::common::{TelePage, TelePager};
+use self::crawl::CrawlProgress;
+use self::crawl::{PageCrawler, CRAWLS_KEY};
+use self::dashboard::{dashboards_window_ui, Dashboard, DashboardView, DashboardWindow};
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use self::http::client;
+use self::print::{PrintRange, MAX_PRINT_PAGES};
+use self::search::SearchResult;
+use self::tabs::{close_tab, SavedTabs, Tab, TABS_KEY};
+use self::teletext::GuiTeleTextContext;
+use self::watch::PageWatcher;
 use self::yle_image::GuiYleImageContext;
-use self::yle_text::GuiYleTextContext;
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
-struct OptionSetting<T> {
-    is_used: bool,
-    value: T,
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct OptionSetting<T> {
+    pub is_used: bool,
+    pub value: T,
 }
 
 fn def_color_opt(color: [u8; 3]) -> OptionSetting<[u8; 3]> {
@@ -23,25 +55,153 @@ fn def_color_opt(color: [u8; 3]) -> OptionSetting<[u8; 3]> {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-enum Pages {
+/// Reader of a tab
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Pages {
     YleText,
     YleImage,
+    SvtText,
+    DrText,
+    /// Provider from the providers file, identified by its name
+    Provider(String),
 }
 
 impl Pages {
-    fn to_gui(&self, egui: &egui::Context) -> Box<dyn IGuiCtx> {
+    /// Providers that are not in the providers file anymore fall back to Yle Text
+    fn to_gui(&self, egui: &egui::Context, shared: &Shared) -> Box<dyn IGuiCtx> {
         match self {
+            Self::Provider(name) => match shared.providers.iter().find(|p| &p.name == name) {
+                Some(provider) if provider.image.is_some() => Box::new(GuiYleImageContext::new(
+                    self.context(egui, shared).with_provider(provider.clone()),
+                ))
+                    as Box<dyn IGuiCtx>,
+                Some(provider) => Box::new(GuiTeleTextContext::<YleText>::new(
+                    self.context(egui, shared).with_provider(provider.clone()),
+                )) as Box<dyn IGuiCtx>,
+                None => Self::YleText.to_gui(egui, shared),
+            },
             Self::YleImage => {
-                Box::new(GuiYleImageContext::new(GuiContext::new(egui.clone()))) as Box<dyn IGuiCtx>
-            }
-            Self::YleText => {
-                Box::new(GuiYleTextContext::new(GuiContext::new(egui.clone()))) as Box<dyn IGuiCtx>
+                Box::new(GuiYleImageContext::new(self.context(egui, shared))) as Box<dyn IGuiCtx>
             }
+            Self::YleText => Box::new(GuiTeleTextContext::<YleText>::new(
+                self.context(egui, shared),
+            )) as Box<dyn IGuiCtx>,
+            Self::SvtText => Box::new(GuiTeleTextContext::<SvtText>::new(
+                self.context(egui, shared),
+            )) as Box<dyn IGuiCtx>,
+            Self::DrText => Box::new(GuiTeleTextContext::<DrText>::new(
+                self.context(egui, shared),
+            )) as Box<dyn IGuiCtx>,
+        }
+    }
+
+    /// Context of the reader with the pages and the crawl of its source
+    fn context<T: HtmlParser + TelePager + Send + 'static>(
+        &self,
+        egui: &egui::Context,
+        shared: &Shared,
+    ) -> GuiContext<T> {
+        GuiContext::new(egui.clone())
+            .with_pages(shared.pages.clone())
+            .with_crawl(shared.crawler.crawl(self.source()))
+    }
+
+    /// Name shown in the reader menu and the tabs
+    fn name(&self) -> &str {
+        match self {
+            Self::YleText => "Yle Text",
+            Self::YleImage => "Yle Image",
+            Self::SvtText => "SVT Text",
+            Self::DrText => "DR Tekst-TV",
+            Self::Provider(name) => name,
+        }
+    }
+
+    /// Source of the pages loaded with the reader in the page store
+    fn source(&self) -> &str {
+        match self {
+            Self::YleText => YleText::SOURCE,
+            Self::YleImage => YleImage::SOURCE,
+            Self::SvtText => SvtText::SOURCE,
+            Self::DrText => DrText::SOURCE,
+            Self::Provider(name) => name,
+        }
+    }
+
+    /// Built-in readers followed by the providers
+    fn readers(providers: &[Arc<ProviderConfig>]) -> Vec<Self> {
+        let mut readers = vec![Self::YleText, Self::YleImage, Self::SvtText, Self::DrText];
+        readers.extend(
+            providers
+                .iter()
+                .map(|provider| Self::Provider(provider.name.clone())),
+        );
+        readers
+    }
+
+    /// Image readers cannot show text pages, e.g. pages from `.tti` files
+    fn is_image(&self, providers: &[Arc<ProviderConfig>]) -> bool {
+        match self {
+            Self::YleImage => true,
+            Self::Provider(name) => providers
+                .iter()
+                .any(|p| &p.name == name && p.image.is_some()),
+            _ => false,
+        }
+    }
+}
+
+/// Every text style uses the monospace font in the size
+fn font_styles(size: f32) -> BTreeMap<TextStyle, FontId> {
+    [
+        (TextStyle::Heading, FontId::new(size, FontFamily::Monospace)),
+        (TextStyle::Body, FontId::new(size, FontFamily::Monospace)),
+        (
+            TextStyle::Monospace,
+            FontId::new(size, FontFamily::Monospace),
+        ),
+        (TextStyle::Button, FontId::new(size, FontFamily::Monospace)),
+        (TextStyle::Small, FontId::new(size, FontFamily::Monospace)),
+    ]
+    .into()
+}
+
+/// Load the providers from the file in `TELETEXT_PROVIDERS` env variable or
+/// from `providers.toml`. Files ending with `.json` are read as json
+#[cfg(not(target_arch = "wasm32"))]
+fn load_providers() -> Vec<Arc<ProviderConfig>> {
+    use crate::parser::ProvidersFile;
+
+    let path = std::env::var("TELETEXT_PROVIDERS").unwrap_or_else(|_| "providers.toml".into());
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        // Providers file is optional
+        Err(_) => return Vec::new(),
+    };
+
+    let file = if path.ends_with(".json") {
+        ProvidersFile::from_json(&data)
+    } else {
+        ProvidersFile::from_toml(&data)
+    };
+
+    match file {
+        Ok(file) => file.provider.into_iter().map(Arc::new).collect(),
+        Err(_) => {
+            log::warn!("Invalid providers file: {}", path);
+            Vec::new()
         }
     }
 }
 
+/// Providers, the loaded pages and the crawls, shared by the readers of every tab and dashboard
+#[derive(Default)]
+struct Shared {
+    providers: Vec<Arc<ProviderConfig>>,
+    pages: Arc<SharedPages>,
+    crawler: PageCrawler,
+}
+
 impl Default for Pages {
     fn default() -> Self {
         Self::YleText
@@ -56,15 +216,30 @@ struct TeleTextSettings {
     link_color: OptionSetting<[u8; 3]>,
     text_color: OptionSetting<[u8; 3]>,
     background_color: OptionSetting<[u8; 3]>,
+    /// Refresh interval of new tabs, each tab has its own
     refresh_interval: OptionSetting<u64>,
+    /// Seconds each sub-page is shown in the carousel
+    carousel_interval: OptionSetting<u64>,
+    /// Load the pages linked from the current page in the background
+    prefetch: bool,
+    /// "Copy page" includes the page number and the title
+    copy_header: bool,
+    dashboards: Vec<Dashboard>,
+    /// Name of the dashboard that was open when the app was closed
+    open_dashboard: Option<String>,
 }
 
 impl TeleTextSettings {
-    /// Initialize all settings, should be used when app is initalised
-    fn init_all(&self, ctx: &egui::Context, page: &mut Box<dyn IGuiCtx>) {
+    /// Initialize the style, should be used when app is initalised
+    fn init_style(&self, ctx: &egui::Context) {
         self.set_colors(ctx);
         self.set_font_size(ctx);
-        self.set_refresh_interval(page);
+    }
+
+    /// Initialize the settings shared by all tabs, should be used when a tab is opened
+    fn init_page(&self, page: &mut Box<dyn IGuiCtx>) {
+        self.set_carousel_interval(page);
+        page.set_prefetch(self.prefetch);
     }
 
     fn set_colors(&self, ctx: &egui::Context) {
@@ -97,37 +272,15 @@ impl TeleTextSettings {
 
     fn set_font_size(&self, ctx: &egui::Context) {
         let mut style = (*ctx.style()).clone();
-        style.text_styles = [
-            (
-                TextStyle::Heading,
-                FontId::new(self.font_size, FontFamily::Monospace),
-            ),
-            (
-                TextStyle::Body,
-                FontId::new(self.font_size, FontFamily::Monospace),
-            ),
-            (
-                TextStyle::Monospace,
-                FontId::new(self.font_size, FontFamily::Monospace),
-            ),
-            (
-                TextStyle::Button,
-                FontId::new(self.font_size, FontFamily::Monospace),
-            ),
-            (
-                TextStyle::Small,
-                FontId::new(self.font_size, FontFamily::Monospace),
-            ),
-        ]
-        .into();
+        style.text_styles = font_styles(self.font_size);
         ctx.set_style(style);
     }
 
-    fn set_refresh_interval(&self, page: &mut Box<dyn IGuiCtx>) {
-        if self.refresh_interval.is_used {
-            page.set_refresh_interval(self.refresh_interval.value);
+    fn set_carousel_interval(&self, page: &mut Box<dyn IGuiCtx>) {
+        if self.carousel_interval.is_used {
+            page.set_carousel_interval(self.carousel_interval.value);
         } else {
-            page.stop_refresh_interval();
+            page.stop_carousel();
         }
     }
 }
@@ -144,18 +297,138 @@ impl Default for TeleTextSettings {
                 is_used: false,
                 value: 300,
             },
+            carousel_interval: OptionSetting {
+                is_used: false,
+                value: 10,
+            },
+            prefetch: true,
+            copy_header: true,
+            dashboards: Vec::new(),
+            open_dashboard: None,
+        }
+    }
+}
+
+/// State of the `.tti` import/export window
+#[derive(Default)]
+struct TtiWindow {
+    path: String,
+    status: String,
+}
+
+/// State of the page export window
+struct ExportWindow {
+    format: ExportFormat,
+    path: String,
+    image_format: ImageFormat,
+    /// Size of the text pages in the image
+    scale: f32,
+    /// Draw the header above the image pages
+    image_header: bool,
+    image_path: String,
+    status: String,
+}
+
+impl Default for ExportWindow {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Text,
+            path: String::new(),
+            image_format: ImageFormat::Png,
+            scale: 2.0,
+            image_header: false,
+            image_path: String::new(),
+            status: String::new(),
+        }
+    }
+}
+
+/// State of the print window
+struct PrintWindow {
+    range: PrintRange,
+    first: i32,
+    last: i32,
+    sub_pages: bool,
+    path: String,
+}
+
+impl Default for PrintWindow {
+    fn default() -> Self {
+        Self {
+            range: PrintRange::Current,
+            first: 100,
+            last: 110,
+            sub_pages: false,
+            path: "teletext.pdf".to_string(),
+        }
+    }
+}
+
+/// State of the page watch window
+struct WatchWindow {
+    interval: u64,
+    keyword: String,
+}
+
+impl Default for WatchWindow {
+    fn default() -> Self {
+        Self {
+            interval: 60,
+            keyword: String::new(),
         }
     }
 }
 
+/// State of the search box and its results
+#[derive(Default)]
+struct SearchWindow {
+    query: String,
+    results: Vec<SearchResult>,
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TeleTextApp {
     #[serde(skip)]
-    page: Option<Box<dyn IGuiCtx>>,
+    tabs: Vec<Tab>,
+    #[serde(skip)]
+    active_tab: usize,
     #[serde(skip)]
     settings_open: bool,
+    #[serde(skip)]
+    tti_open: bool,
+    #[serde(skip)]
+    tti_window: TtiWindow,
+    #[serde(skip)]
+    export_open: bool,
+    #[serde(skip)]
+    export_window: ExportWindow,
+    #[serde(skip)]
+    print_open: bool,
+    #[serde(skip)]
+    print_window: PrintWindow,
+    #[serde(skip)]
+    watch_open: bool,
+    #[serde(skip)]
+    watch_window: WatchWindow,
+    #[serde(skip)]
+    watcher: PageWatcher,
+    #[serde(skip)]
+    crawl_open: bool,
+    #[serde(skip)]
+    search_open: bool,
+    #[serde(skip)]
+    search_window: SearchWindow,
+    #[serde(skip)]
+    dashboards_open: bool,
+    #[serde(skip)]
+    dashboards_window: DashboardWindow,
+    /// Shown instead of the tabs when it's open
+    #[serde(skip)]
+    dashboard: Option<DashboardView>,
+    #[serde(skip)]
+    shared: Shared,
     settings: TeleTextSettings,
 }
 
@@ -189,14 +462,76 @@ impl TeleTextApp {
             TeleTextSettings::default()
         };
 
-        let mut page = settings.open_page.to_gui(&ctx.egui_ctx);
-        let page_ref = &mut page as &mut Box<dyn IGuiCtx>;
+        #[cfg(not(target_arch = "wasm32"))]
+        let providers = load_providers();
+        #[cfg(target_arch = "wasm32")]
+        let providers = Vec::new();
+
+        settings.init_style(&ctx.egui_ctx);
+        let shared = Shared {
+            providers,
+            pages: Arc::new(SharedPages::new()),
+            crawler: Default::default(),
+        };
+
+        // Crawls continue with a reader of the source, the readers of the tabs share them
+        let crawls: BTreeMap<String, CrawlProgress> = ctx
+            .storage
+            .and_then(|storage| eframe::get_value(storage, CRAWLS_KEY))
+            .unwrap_or_default();
+        let readers = Pages::readers(&shared.providers);
+        for (source, progress) in crawls {
+            if let Some(reader) = readers.iter().find(|reader| reader.source() == source) {
+                reader.to_gui(&ctx.egui_ctx, &shared).resume_crawl(progress);
+            }
+        }
+
+        let saved_tabs: SavedTabs = ctx
+            .storage
+            .and_then(|storage| eframe::get_value(storage, TABS_KEY))
+            .unwrap_or_default();
+        let active_tab = saved_tabs.active_tab();
+        let mut tabs: Vec<Tab> = saved_tabs
+            .tabs
+            .into_iter()
+            .map(|saved| Tab::from_saved(saved, &ctx.egui_ctx, &shared, &settings))
+            .collect();
+        if tabs.is_empty() {
+            tabs.push(Tab::new(
+                settings.open_page.clone(),
+                settings.refresh_interval.clone(),
+                &ctx.egui_ctx,
+                &shared,
+                &settings,
+            ));
+        }
 
-        settings.init_all(&ctx.egui_ctx, page_ref);
+        let dashboard = settings
+            .open_dashboard
+            .as_ref()
+            .and_then(|name| settings.dashboards.iter().find(|d| &d.name == name))
+            .map(|dashboard| DashboardView::new(dashboard, &ctx.egui_ctx, &shared, &settings));
 
         Self {
-            page: Some(page),
+            tabs,
+            active_tab,
             settings_open: false,
+            tti_open: false,
+            tti_window: Default::default(),
+            export_open: false,
+            export_window: Default::default(),
+            print_open: false,
+            print_window: Default::default(),
+            watch_open: false,
+            watch_window: Default::default(),
+            watcher: Default::default(),
+            crawl_open: false,
+            search_open: false,
+            search_window: Default::default(),
+            dashboards_open: false,
+            dashboards_window: Default::default(),
+            dashboard,
+            shared,
             settings,
         }
     }
@@ -206,47 +541,234 @@ impl eframe::App for TeleTextApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+
+        let saved = SavedTabs {
+            tabs: self.tabs.iter().map(Tab::saved).collect(),
+            active: self.active_tab,
+        };
+        eframe::set_value(storage, TABS_KEY, &saved);
+        eframe::set_value(storage, CRAWLS_KEY, &self.shared.crawler.saved());
     }
 
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         let Self {
-            page,
+            tabs,
+            active_tab,
             settings_open,
+            tti_open,
+            tti_window,
+            export_open,
+            export_window,
+            print_open,
+            print_window,
+            watch_open,
+            watch_window,
+            watcher,
+            crawl_open,
+            search_open,
+            search_window,
+            dashboards_open,
+            dashboards_window,
+            dashboard,
+            shared,
             settings,
         } = self;
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            top_menu_bar(ui, ctx, frame, settings_open, page, settings);
+            if let Some(tab) = selected_tab(tabs, *active_tab, dashboard) {
+                top_menu_bar(
+                    ui,
+                    ctx,
+                    frame,
+                    settings_open,
+                    tti_open,
+                    export_open,
+                    print_open,
+                    watch_open,
+                    crawl_open,
+                    search_open,
+                    dashboards_open,
+                    search_window,
+                    tab,
+                    shared,
+                    settings,
+                );
+            }
+            match dashboard {
+                Some(view) => {
+                    let mut close = false;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Dashboard {}", view.name));
+                        close = ui.button("Close dashboard").clicked();
+                    });
+                    if close {
+                        *dashboard = None;
+                        settings.open_dashboard = None;
+                    }
+                }
+                None => tab_bar(ui, ctx, tabs, active_tab, shared, settings),
+            }
         });
 
+        if tabs.is_empty() {
+            return;
+        }
+
+        // Hidden tabs and the tabs behind the dashboard keep refreshing and retrying
+        for tab in tabs.iter_mut() {
+            tab.page.update();
+        }
+        if let Some(view) = dashboard {
+            view.update();
+        }
+
         // .input() locks ctx so we need to copy the data to avoid locks
         let input = ctx.input().to_owned();
+        match dashboard {
+            Some(view) => {
+                if let Some(tab) = view.active_tab() {
+                    copy_shortcut(ctx, &input, &mut tab.page, settings);
+                }
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    view.handle_input(&input);
+                    view.draw(ui);
+                });
+            }
+            None => {
+                copy_shortcut(ctx, &input, &mut tabs[*active_tab].page, settings);
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if let Some(page) = page {
-                page.handle_input(input);
-                page.draw(ui);
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let page = &mut tabs[*active_tab].page;
+                    page.handle_input(input);
+                    page.draw(ui);
+                });
             }
+        }
+
+        // Middle-clicked links are opened next to the tab in the same reader,
+        // links of a tile are opened after the active tab behind the dashboard
+        let new_tab = selected_tab(tabs, *active_tab, dashboard).and_then(|tab| {
+            let page = tab.page.take_new_tab()?;
+            Some((tab.reader.clone(), page))
         });
+        if let Some((reader, new_page)) = new_tab {
+            let mut tab = Tab::new(
+                reader,
+                settings.refresh_interval.clone(),
+                ctx,
+                shared,
+                settings,
+            );
+            tab.page.open_page(new_page);
+            tabs.insert(*active_tab + 1, tab);
+        }
 
         egui::Window::new("Settings")
             .open(settings_open)
             .show(ctx, |ui| {
-                if let Some(page) = page {
-                    settings_window(ui, ctx, settings, page);
-                }
+                settings_window(ui, ctx, settings, tabs, *active_tab, dashboard);
+            });
+
+        egui::Window::new("Dashboards")
+            .open(dashboards_open)
+            .show(ctx, |ui| {
+                dashboards_window_ui(
+                    ui,
+                    ctx,
+                    dashboards_window,
+                    settings,
+                    &tabs[*active_tab],
+                    shared,
+                    dashboard,
+                );
             });
 
+        let tab = match selected_tab(tabs, *active_tab, dashboard) {
+            Some(tab) => tab,
+            None => return,
+        };
+        egui::Window::new("Import/Export .tti")
+            .open(tti_open)
+            .show(ctx, |ui| {
+                tti_window_ui(ui, ctx, tti_window, settings, shared, tab);
+            });
+
+        let page = &mut tab.page;
+        egui::Window::new("Export page")
+            .open(export_open)
+            .show(ctx, |ui| {
+                export_window_ui(ui, export_window, settings, page);
+            });
+
+        egui::Window::new("Print").open(print_open).show(ctx, |ui| {
+            print_window_ui(ui, print_window, page);
+        });
+
+        egui::Window::new("Watch pages")
+            .open(watch_open)
+            .show(ctx, |ui| {
+                watch_window_ui(ui, watch_window, page, watcher);
+            });
+
+        egui::Window::new("Crawl pages")
+            .open(crawl_open)
+            .show(ctx, |ui| {
+                crawl_window_ui(ui, page);
+            });
+
+        let opened = egui::Window::new("Search results")
+            .open(search_open)
+            .show(ctx, |ui| search_window_ui(ui, search_window, tab, shared))
+            .and_then(|response| response.inner.flatten());
+        if let Some((reader, result)) = opened {
+            let mut tab = Tab::new(
+                reader,
+                settings.refresh_interval.clone(),
+                ctx,
+                shared,
+                settings,
+            );
+            tab.page.open_search_result(&result);
+            *active_tab += 1;
+            tabs.insert(*active_tab, tab);
+            // New tab is hidden behind the dashboard
+            *dashboard = None;
+            settings.open_dashboard = None;
+        }
+
         ctx.request_repaint_after(Duration::from_millis(100));
     }
 }
 
+/// Tab the menus and windows act on, the active tile while a dashboard is shown
+fn selected_tab<'a>(
+    tabs: &'a mut [Tab],
+    active: usize,
+    dashboard: &'a mut Option<DashboardView>,
+) -> Option<&'a mut Tab> {
+    match dashboard.as_mut().and_then(DashboardView::active_tab) {
+        Some(tab) => Some(tab),
+        None => tabs.get_mut(active),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn top_menu_bar(
     ui: &mut Ui,
     egui: &egui::Context,
     _frame: &mut eframe::Frame,
     open: &mut bool,
-    page: &mut Option<Box<dyn IGuiCtx>>,
+    _tti_open: &mut bool,
+    export_open: &mut bool,
+    print_open: &mut bool,
+    watch_open: &mut bool,
+    crawl_open: &mut bool,
+    search_open: &mut bool,
+    dashboards_open: &mut bool,
+    search: &mut SearchWindow,
+    tab: &mut Tab,
+    shared: &Shared,
     settings: &mut TeleTextSettings,
 ) {
     egui::menu::bar(ui, |ui| {
@@ -254,17 +776,82 @@ fn top_menu_bar(
             ui.menu_button("Reader", |ui| {
                 if ui.button("Yle Text").clicked() {
                     settings.open_page = Pages::YleText;
-                    *page = Some(Pages::YleText.to_gui(egui));
+                    tab.set_reader(Pages::YleText, egui, shared, settings);
                     ui.close_menu();
                 }
 
                 if ui.button("Yle Image").clicked() {
                     settings.open_page = Pages::YleImage;
-                    *page = Some(Pages::YleImage.to_gui(egui));
+                    tab.set_reader(Pages::YleImage, egui, shared, settings);
                     ui.close_menu();
                 }
+
+                if ui.button("SVT Text").clicked() {
+                    settings.open_page = Pages::SvtText;
+                    tab.set_reader(Pages::SvtText, egui, shared, settings);
+                    ui.close_menu();
+                }
+
+                if ui.button("DR Tekst-TV").clicked() {
+                    settings.open_page = Pages::DrText;
+                    tab.set_reader(Pages::DrText, egui, shared, settings);
+                    ui.close_menu();
+                }
+
+                if !shared.providers.is_empty() {
+                    ui.separator();
+                }
+
+                for provider in &shared.providers {
+                    if ui.button(&provider.name).clicked() {
+                        settings.open_page = Pages::Provider(provider.name.clone());
+                        tab.set_reader(settings.open_page.clone(), egui, shared, settings);
+                        ui.close_menu();
+                    }
+                }
             });
 
+            // No file system access on wasm
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                if ui.button("Import/Export .tti").clicked() {
+                    *_tti_open = true;
+                    ui.close_menu();
+                }
+            }
+
+            if ui.button("Export page").clicked() {
+                *export_open = true;
+                ui.close_menu();
+            }
+
+            if ui.button("Print").clicked() {
+                *print_open = true;
+                ui.close_menu();
+            }
+
+            if ui.button("Copy page").clicked() {
+                if let Some(text) = tab.page.page_text(settings.copy_header) {
+                    ui.output().copied_text = text;
+                }
+                ui.close_menu();
+            }
+
+            if ui.button("Watch pages").clicked() {
+                *watch_open = true;
+                ui.close_menu();
+            }
+
+            if ui.button("Crawl pages").clicked() {
+                *crawl_open = true;
+                ui.close_menu();
+            }
+
+            if ui.button("Dashboards").clicked() {
+                *dashboards_open = true;
+                ui.close_menu();
+            }
+
             if ui.button("Settings").clicked() {
                 *open = true;
                 ui.close_menu();
@@ -280,14 +867,73 @@ fn top_menu_bar(
                 }
             }
         });
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            // Right to left so the button is added before the text field
+            let clicked = ui.button("Search").clicked();
+            let input = egui::TextEdit::singleline(&mut search.query)
+                .hint_text("Search loaded pages")
+                .desired_width(200.0);
+            let resp = ui.add(input);
+            let submitted = resp.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+            if clicked || submitted {
+                search.results = tab.page.search(&search.query);
+                *search_open = true;
+            }
+        });
+    });
+}
+
+/// Tabs can be closed with the middle button, the last tab stays open
+fn tab_bar(
+    ui: &mut Ui,
+    egui: &egui::Context,
+    tabs: &mut Vec<Tab>,
+    active: &mut usize,
+    shared: &Shared,
+    settings: &TeleTextSettings,
+) {
+    let mut close = None;
+    let mut new_tab = false;
+    ui.horizontal_wrapped(|ui| {
+        let closable = tabs.len() > 1;
+        for (idx, tab) in tabs.iter().enumerate() {
+            let resp = ui.selectable_label(idx == *active, tab.title());
+            if resp.clicked() {
+                *active = idx;
+            } else if resp.middle_clicked() && closable {
+                close = Some(idx);
+            }
+
+            if closable && ui.small_button("x").on_hover_text("Close tab").clicked() {
+                close = Some(idx);
+            }
+            ui.separator();
+        }
+
+        new_tab = ui.button("+").on_hover_text("New tab").clicked();
     });
+
+    if let Some(idx) = close {
+        close_tab(tabs, active, idx);
+    }
+
+    // New tabs open the reader of the current tab
+    if new_tab {
+        let reader = tabs[*active].reader.clone();
+        let refresh_interval = settings.refresh_interval.clone();
+        tabs.push(Tab::new(reader, refresh_interval, egui, shared, settings));
+        *active = tabs.len() - 1;
+    }
 }
 
 fn settings_window(
     ui: &mut Ui,
     ctx: &egui::Context,
     settings: &mut TeleTextSettings,
-    page: &mut Box<dyn IGuiCtx>,
+    tabs: &mut [Tab],
+    active: usize,
+    dashboard: &mut Option<DashboardView>,
 ) {
     if ui
         .add(egui::Slider::new(&mut settings.font_size, 8.0..=48.0).text("Font size"))
@@ -314,17 +960,23 @@ fn settings_window(
                 settings.set_colors(ctx);
             }
 
-            ui.label("Refesh interval");
+            // New tabs get the interval that was set last
+            let tab = match selected_tab(tabs, active, dashboard) {
+                Some(tab) => tab,
+                None => return,
+            };
+            ui.label("Refesh interval of the tab");
             if ui
-                .checkbox(&mut settings.refresh_interval.is_used, "use")
+                .checkbox(&mut tab.refresh_interval.is_used, "use")
                 .changed()
             {
-                settings.set_refresh_interval(page);
+                tab.set_refresh_interval();
+                settings.refresh_interval = tab.refresh_interval.clone();
             }
 
-            let interval_val = &mut settings.refresh_interval.value;
+            let interval_val = &mut tab.refresh_interval.value;
 
-            if settings.refresh_interval.is_used
+            if tab.refresh_interval.is_used
                 && ui
                     .add(
                         egui::DragValue::new(interval_val)
@@ -333,11 +985,404 @@ fn settings_window(
                     )
                     .changed()
             {
-                settings.set_refresh_interval(page);
+                tab.set_refresh_interval();
+                settings.refresh_interval = tab.refresh_interval.clone();
             }
 
             ui.end_row();
+
+            ui.label("Sub-page carousel");
+            if ui
+                .checkbox(&mut settings.carousel_interval.is_used, "use")
+                .changed()
+            {
+                for tab in tabs.iter_mut() {
+                    settings.set_carousel_interval(&mut tab.page);
+                }
+                if let Some(view) = dashboard {
+                    view.apply_settings(settings);
+                }
+            }
+
+            let interval_val = &mut settings.carousel_interval.value;
+
+            if settings.carousel_interval.is_used
+                && ui
+                    .add(
+                        egui::DragValue::new(interval_val)
+                            .speed(1.0)
+                            .clamp_range(3..=120),
+                    )
+                    .changed()
+            {
+                for tab in tabs.iter_mut() {
+                    settings.set_carousel_interval(&mut tab.page);
+                }
+                if let Some(view) = dashboard {
+                    view.apply_settings(settings);
+                }
+            }
+
+            ui.end_row();
+
+            ui.label("Prefetch linked pages");
+            if ui.checkbox(&mut settings.prefetch, "use").changed() {
+                for tab in tabs.iter_mut() {
+                    tab.page.set_prefetch(settings.prefetch);
+                }
+                if let Some(view) = dashboard {
+                    view.apply_settings(settings);
+                }
+            }
+
+            ui.end_row();
+
+            ui.label("Copy page with header");
+            ui.checkbox(&mut settings.copy_header, "use");
+            ui.end_row();
+        });
+}
+
+/// Ctrl+C copies the selected text and Ctrl+Shift+C the whole page.
+/// Text fields handle the shortcut themselves when they're focused
+fn copy_shortcut(
+    ctx: &egui::Context,
+    input: &egui::InputState,
+    page: &mut Box<dyn IGuiCtx>,
+    settings: &TeleTextSettings,
+) {
+    if !input.events.contains(&egui::Event::Copy) || ctx.wants_keyboard_input() {
+        return;
+    }
+
+    let text = if input.modifiers.shift {
+        page.page_text(settings.copy_header)
+    } else {
+        page.selected_text()
+    };
+    if let Some(text) = text {
+        ctx.output().copied_text = text;
+    }
+}
+
+fn tti_window_ui(
+    ui: &mut Ui,
+    ctx: &egui::Context,
+    tti: &mut TtiWindow,
+    settings: &mut TeleTextSettings,
+    shared: &Shared,
+    tab: &mut Tab,
+) {
+    ui.horizontal(|ui| {
+        ui.label("File");
+        ui.text_edit_singleline(&mut tti.path);
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Import").clicked() {
+            tti.status = match std::fs::read(&tti.path) {
+                Ok(data) => {
+                    // .tti pages can only be shown in the text readers
+                    if tab.reader.is_image(&shared.providers) {
+                        settings.open_page = Pages::YleText;
+                        tab.set_reader(Pages::YleText, ctx, shared, settings);
+                    }
+
+                    match tab.page.import_tti(&data) {
+                        Ok(_) => format!("Imported {}", tti.path),
+                        Err(_) => format!("{} is not a valid .tti file", tti.path),
+                    }
+                }
+                Err(err) => format!("Cannot read {}: {err}", tti.path),
+            };
+        }
+
+        if ui.button("Export").clicked() {
+            tti.status = match tab.page.export_tti() {
+                Some(data) => match std::fs::write(&tti.path, data) {
+                    Ok(_) => format!("Exported {}", tti.path),
+                    Err(err) => format!("Cannot write {}: {err}", tti.path),
+                },
+                None => "Current page cannot be exported".to_string(),
+            };
+        }
+    });
+
+    if !tti.status.is_empty() {
+        ui.label(&tti.status);
+    }
+}
+
+fn export_window_ui(
+    ui: &mut Ui,
+    export: &mut ExportWindow,
+    settings: &TeleTextSettings,
+    page: &mut Box<dyn IGuiCtx>,
+) {
+    ui.horizontal(|ui| {
+        for format in ExportFormat::ALL {
+            ui.radio_value(&mut export.format, format, format.name());
+        }
+    });
+
+    let colors = HtmlColors {
+        text: settings.text_color.value,
+        link: settings.link_color.value,
+        background: settings.background_color.value,
+    };
+    let format = export.format;
+    let exported = || page.export_page(format, &colors);
+    let not_supported = "Current page cannot be exported".to_string();
+
+    if ui.button("Copy to clipboard").clicked() {
+        export.status = match exported() {
+            Some(text) => {
+                ui.output().copied_text = text;
+                format!("Copied as {}", export.format.name())
+            }
+            None => not_supported.clone(),
+        };
+    }
+
+    // No file system access on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.horizontal(|ui| {
+        ui.label("File");
+        ui.text_edit_singleline(&mut export.path);
+        if ui.button("Save").clicked() {
+            export.status = match exported() {
+                Some(text) => match std::fs::write(&export.path, text) {
+                    Ok(_) => format!("Exported {}", export.path),
+                    Err(err) => format!("Cannot write {}: {err}", export.path),
+                },
+                None => not_supported.clone(),
+            };
+        }
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    image_export_ui(ui, export, &colors, page);
+
+    if !export.status.is_empty() {
+        ui.label(&export.status);
+    }
+}
+
+/// Images can only be saved to a file, so they're not exported on wasm
+#[cfg(not(target_arch = "wasm32"))]
+fn image_export_ui(
+    ui: &mut Ui,
+    export: &mut ExportWindow,
+    colors: &HtmlColors,
+    page: &mut Box<dyn IGuiCtx>,
+) {
+    ui.separator();
+    ui.horizontal(|ui| {
+        for format in ImageFormat::ALL {
+            ui.radio_value(&mut export.image_format, format, format.name());
+        }
+    });
+    ui.add(egui::Slider::new(&mut export.scale, 1.0..=4.0).text("Scale"));
+    ui.checkbox(&mut export.image_header, "Header on image pages");
+
+    ui.horizontal(|ui| {
+        ui.label("File");
+        ui.text_edit_singleline(&mut export.image_path);
+        if ui.button("Save image").clicked() {
+            let image = page.export_image(
+                export.image_format,
+                export.scale,
+                export.image_header,
+                colors,
+            );
+            export.status = match image {
+                Some(Ok(data)) => match std::fs::write(&export.image_path, data) {
+                    Ok(_) => format!("Saved {}", export.image_path),
+                    Err(err) => format!("Cannot write {}: {err}", export.image_path),
+                },
+                Some(Err(err)) => format!("Cannot draw the image: {err}"),
+                None => "Current page cannot be exported".to_string(),
+            };
+        }
+    });
+}
+
+/// Watches are kept when the tab is closed or its reader is changed
+fn watch_window_ui(
+    ui: &mut Ui,
+    watch: &mut WatchWindow,
+    page: &mut Box<dyn IGuiCtx>,
+    watcher: &mut PageWatcher,
+) {
+    egui::Grid::new("watch_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Interval");
+        ui.add(
+            egui::DragValue::new(&mut watch.interval)
+                .speed(1.0)
+                .clamp_range(30..=3600)
+                .suffix(" s"),
+        );
+        ui.end_row();
+
+        ui.label("Only when page contains");
+        ui.text_edit_singleline(&mut watch.keyword);
+        ui.end_row();
+    });
+
+    if ui.button("Watch this page").clicked() {
+        let keyword = watch.keyword.trim();
+        let keyword = (!keyword.is_empty()).then(|| keyword.to_string());
+        watcher.add(page.watch_current_page(watch.interval, keyword));
+    }
+
+    ui.separator();
+    let mut removed = None;
+    for (idx, description) in watcher.descriptions().iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(description);
+            if ui.button("Remove").clicked() {
+                removed = Some(idx);
+            }
         });
+    }
+
+    if let Some(idx) = removed {
+        watcher.remove(idx);
+    }
+}
+
+fn crawl_window_ui(ui: &mut Ui, page: &mut Box<dyn IGuiCtx>) {
+    let progress = page.crawl_progress();
+    ui.label(
+        "Loads the pages 100-899 and their sub-pages so they can be searched and read offline",
+    );
+    ui.add(egui::ProgressBar::new(progress.fraction()).show_percentage());
+    ui.label(progress.description());
+
+    ui.horizontal(|ui| {
+        if progress.running {
+            if ui.button("Pause").clicked() {
+                page.stop_crawl();
+            }
+        } else if !progress.is_finished() {
+            let start = if progress.loaded + progress.failed > 0 {
+                "Continue"
+            } else {
+                "Start"
+            };
+            if ui.button(start).clicked() {
+                page.start_crawl();
+            }
+        }
+
+        if ui.button("Start over").clicked() {
+            page.restart_crawl();
+        }
+    });
+}
+
+fn print_window_ui(ui: &mut Ui, print: &mut PrintWindow, page: &mut Box<dyn IGuiCtx>) {
+    let pages = PrintRange::Pages {
+        first: print.first,
+        last: print.last,
+        sub_pages: print.sub_pages,
+    };
+    ui.radio_value(&mut print.range, PrintRange::Current, "Current page");
+    ui.radio_value(&mut print.range, PrintRange::SubPages, "All sub-pages");
+    ui.horizontal(|ui| {
+        let selected = matches!(print.range, PrintRange::Pages { .. });
+        if ui.radio(selected, "Pages").clicked() {
+            print.range = pages;
+        }
+        ui.add(egui::DragValue::new(&mut print.first).clamp_range(100..=899));
+        ui.label("-");
+        ui.add(egui::DragValue::new(&mut print.last).clamp_range(100..=899));
+        ui.checkbox(&mut print.sub_pages, "with sub-pages");
+    });
+    ui.label(format!("At most {MAX_PRINT_PAGES} pages are printed"));
+
+    // Pdf is saved to a file on native, on wasm the browser prints the pages
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.horizontal(|ui| {
+        ui.label("File");
+        ui.text_edit_singleline(&mut print.path);
+    });
+
+    let progress = page.print_progress();
+    let running = matches!(progress, Some((false, _)));
+    ui.horizontal(|ui| {
+        if running {
+            if ui.button("Stop").clicked() {
+                page.stop_print();
+            }
+        } else {
+            let print_label = if cfg!(target_arch = "wasm32") {
+                "Print"
+            } else {
+                "Save PDF"
+            };
+            if ui.button(print_label).clicked() {
+                // Range is updated from the fields when the pages are selected
+                let range = match print.range {
+                    PrintRange::Pages { .. } => pages,
+                    range => range,
+                };
+                page.start_print(range, print.path.clone());
+            }
+        }
+    });
+
+    if let Some((_, description)) = progress {
+        ui.label(description);
+    }
+}
+
+/// Results of the pages loaded with another reader than the one of `tab` are returned
+/// with their reader so they can be opened in a new tab
+fn search_window_ui(
+    ui: &mut Ui,
+    search: &SearchWindow,
+    tab: &mut Tab,
+    shared: &Shared,
+) -> Option<(Pages, SearchResult)> {
+    if search.results.is_empty() {
+        ui.label(format!(
+            "No loaded page contains \"{}\"",
+            search.query.trim()
+        ));
+        return None;
+    }
+
+    let readers = Pages::readers(&shared.providers);
+    let mut opened = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("search_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                for result in &search.results {
+                    // Pages of the providers that were removed can't be opened
+                    let reader = readers.iter().find(|r| r.source() == result.source);
+                    let link = egui::Link::new(format!("P{}", result.page.page));
+                    if ui.add_enabled(reader.is_some(), link).clicked() {
+                        opened = reader.map(|reader| (reader.clone(), result));
+                    }
+                    ui.label(reader.map_or("", Pages::name));
+                    ui.label(&result.title);
+                    ui.label(&result.line);
+                    ui.end_row();
+                }
+            });
+    });
+
+    match opened {
+        Some((reader, result)) if reader == tab.reader => {
+            tab.page.open_search_result(result);
+            None
+        }
+        Some((reader, result)) => Some((reader, result.clone())),
+        None => None,
+    }
 }
 
 fn color_option(ui: &mut Ui, name: &str, color: &mut OptionSetting<[u8; 3]>) -> bool {
@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+
+use crate::parser::PrintPage;
+
+use super::common::TelePage;
+
+/// Larger ranges are cut so a job doesn't load the whole service
+pub const MAX_PRINT_PAGES: usize = 100;
+
+/// Pages to print
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintRange {
+    /// Sub-page that is open
+    Current,
+    /// Every sub-page of the page that is open
+    SubPages,
+    /// Pages from the first to the last, with or without their sub-pages
+    Pages {
+        first: i32,
+        last: i32,
+        sub_pages: bool,
+    },
+}
+
+/// Pages of the range loaded one at a time, in the order they're printed
+#[derive(Default)]
+pub struct PrintJob {
+    queue: VecDeque<TelePage>,
+    pub pages: Vec<PrintPage>,
+    pub failed: usize,
+    /// Sub-pages of the loaded pages are queued
+    sub_pages: bool,
+    /// Pdf is written here when the pages are loaded, not used on wasm
+    pub path: String,
+    /// Result of the job when it's finished
+    pub status: Option<String>,
+}
+
+impl PrintJob {
+    pub fn new(range: PrintRange, current: TelePage, path: String) -> Self {
+        let (queue, sub_pages) = match range {
+            PrintRange::Current => (VecDeque::from([current]), false),
+            PrintRange::SubPages => (VecDeque::from([TelePage::new(current.page, 1)]), true),
+            PrintRange::Pages {
+                first,
+                last,
+                sub_pages,
+            } => {
+                let pages = (first.min(last)..=first.max(last))
+                    .take(MAX_PRINT_PAGES)
+                    .map(|page| TelePage::new(page, 1));
+                (pages.collect(), sub_pages)
+            }
+        };
+
+        Self {
+            queue,
+            sub_pages,
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// Page that is loaded next. It stays in the queue until it's loaded
+    pub fn next_page(&self) -> Option<TelePage> {
+        if self.pages.len() + self.failed >= MAX_PRINT_PAGES {
+            return None;
+        }
+        self.queue.front().copied()
+    }
+
+    /// The next sub-page is loaded before the other pages so the sub-pages stay together
+    pub fn page_loaded(&mut self, page: Option<PrintPage>, next_sub_page: Option<TelePage>) {
+        let loaded = match self.queue.pop_front() {
+            Some(loaded) => loaded,
+            None => return,
+        };
+
+        match page {
+            Some(page) => self.pages.push(page),
+            // Pages without text, like image pages, are skipped
+            None => self.failed += 1,
+        }
+
+        let next = next_sub_page
+            .filter(|next| next.page == loaded.page && next.sub_page > loaded.sub_page);
+        if let Some(next) = next.filter(|_| self.sub_pages) {
+            self.queue.push_front(next);
+        }
+    }
+
+    pub fn page_failed(&mut self) {
+        if self.queue.pop_front().is_some() {
+            self.failed += 1;
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_page().is_none()
+    }
+
+    pub fn description(&self) -> String {
+        match &self.status {
+            Some(status) => status.clone(),
+            None => match self.next_page() {
+                Some(next) => format!(
+                    "Loading P{}/{}, {} pages loaded",
+                    next.page,
+                    next.sub_page,
+                    self.pages.len()
+                ),
+                None => format!("{} pages loaded", self.pages.len()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_job_keeps_sub_pages_together() {
+        let range = PrintRange::Pages {
+            first: 235,
+            last: 236,
+            sub_pages: true,
+        };
+        let mut job = PrintJob::new(range, TelePage::new(100, 1), String::new());
+        let page = |page, sub_page| Some(PrintPage::new(page, sub_page, "", Vec::new()));
+
+        assert!(job.next_page() == Some(TelePage::new(235, 1)));
+        job.page_loaded(page(235, 1), Some(TelePage::new(235, 2)));
+        assert!(job.next_page() == Some(TelePage::new(235, 2)));
+        // Carousel wraps back to the first sub-page, which is already printed
+        job.page_loaded(page(235, 2), Some(TelePage::new(235, 1)));
+        assert!(job.next_page() == Some(TelePage::new(236, 1)));
+        job.page_failed();
+
+        assert!(job.is_finished());
+        assert_eq!(job.pages.len(), 2);
+        assert_eq!(job.failed, 1);
+        assert_eq!(job.pages[1].header, "P235/2  ");
+    }
+
+    #[test]
+    fn print_job_current_page() {
+        let mut job = PrintJob::new(PrintRange::Current, TelePage::new(235, 3), String::new());
+        assert!(job.next_page() == Some(TelePage::new(235, 3)));
+        job.page_loaded(None, Some(TelePage::new(235, 4)));
+
+        // Sub-pages are only followed when they are printed
+        assert!(job.is_finished());
+        assert_eq!(job.failed, 1);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use super::common::TelePage;
+
+/// Results are limited so common words don't fill the result list
+pub const MAX_RESULTS: usize = 50;
+
+struct IndexedPage {
+    title: String,
+    lines: Vec<String>,
+}
+
+/// Line of a page matching the search
+#[derive(Clone)]
+pub struct SearchResult {
+    /// Reader the page was loaded with, see `PageStore::source`
+    pub source: String,
+    pub page: TelePage,
+    pub title: String,
+    pub line: String,
+    /// Index of the line in the page
+    pub row: usize,
+}
+
+/// Texts of the pages the app has loaded, by reader, page and sub-page
+#[derive(Default)]
+pub struct SearchIndex {
+    pages: HashMap<(String, i32, i32), IndexedPage>,
+}
+
+impl SearchIndex {
+    /// Add the page, replacing the older version of it
+    pub fn add(&mut self, source: &str, page: &TelePage, title: String, lines: Vec<String>) {
+        let key = (source.to_string(), page.page, page.sub_page);
+        self.pages.insert(key, IndexedPage { title, lines });
+    }
+
+    /// Lines with words starting with the words of the query, in page order.
+    /// Matches like the search of the database, see `query_words`
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query_words(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut pages: Vec<(&(String, i32, i32), &IndexedPage)> = self.pages.iter().collect();
+        pages.sort_by_key(|((source, page, sub_page), _)| (*page, *sub_page, source));
+
+        let mut results = Vec::new();
+        for ((source, page, sub_page), indexed) in pages {
+            for (row, line) in indexed.lines.iter().enumerate() {
+                if line_matches(line, &query) {
+                    results.push(SearchResult {
+                        source: source.clone(),
+                        page: TelePage::new(*page, *sub_page),
+                        title: indexed.title.clone(),
+                        line: line.trim().to_string(),
+                        row,
+                    });
+
+                    if results.len() == MAX_RESULTS {
+                        return results;
+                    }
+                }
+            }
+        }
+
+        results
+    }
+}
+
+/// Lowercase words of the query. Words are split at other characters than letters and
+/// digits like the unicode61 tokenizer of the database does, so both find the same lines
+pub fn query_words(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Every word of the query starts a word of the line
+fn line_matches(line: &str, query: &[String]) -> bool {
+    let words = query_words(line);
+    query
+        .iter()
+        .all(|query| words.iter().any(|word| word.starts_with(query.as_str())))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Pages searched with both the index and the database, title and lines by page
+    pub(crate) const PAGES: [(i32, &str, [&str; 2]); 2] = [
+        (
+            100,
+            "UUTISET",
+            ["Myrskyvaroitus Lapissa", "Sähkön hinta nousee"],
+        ),
+        (
+            190,
+            "JÄÄKIEKKO",
+            ["HIFK - Kärpät 1-3", "Tappara - Ilves 2-2"],
+        ),
+    ];
+
+    /// Queries and the page and row of the lines they find
+    pub(crate) const QUERIES: [(&str, &[(i32, usize)]); 9] = [
+        ("kärp", &[(190, 0)]),
+        ("KÄRPÄT hifk", &[(190, 0)]),
+        ("ilves tappara", &[(190, 1)]),
+        ("2-2", &[(190, 1)]),
+        ("sähkö", &[(100, 1)]),
+        ("myrsky", &[(100, 0)]),
+        // Only the starts of the words match
+        ("ärp", &[]),
+        ("sahko", &[]),
+        ("\"", &[]),
+    ];
+
+    pub(crate) fn found(results: &[SearchResult]) -> Vec<(i32, usize)> {
+        results
+            .iter()
+            .map(|result| (result.page.page, result.row))
+            .collect()
+    }
+
+    #[test]
+    fn index_search() {
+        let mut index = SearchIndex::default();
+        for (page, title, lines) in PAGES {
+            let lines = lines.iter().map(|line| line.to_string()).collect();
+            index.add("yle_text", &TelePage::new(page, 1), title.into(), lines);
+        }
+
+        for (query, expected) in QUERIES {
+            assert_eq!(found(&index.search(query)), expected, "{}", query);
+        }
+    }
+}
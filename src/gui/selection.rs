@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+/// Position between the characters of the middle rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPos {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl TextPos {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+}
+
+/// Text selected by dragging over the middle rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSelection {
+    /// Where the drag started
+    pub anchor: TextPos,
+    pub cursor: TextPos,
+    /// Cursor follows the pointer until the button is released
+    pub dragging: bool,
+}
+
+impl TextSelection {
+    pub fn new(pos: TextPos) -> Self {
+        Self {
+            anchor: pos,
+            cursor: pos,
+            dragging: true,
+        }
+    }
+
+    /// Start and end of the selection in reading order
+    pub fn range(&self) -> (TextPos, TextPos) {
+        if self.anchor <= self.cursor {
+            (self.anchor, self.cursor)
+        } else {
+            (self.cursor, self.anchor)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.cursor
+    }
+
+    /// Selected characters of the row that is `len` characters long
+    pub fn columns(&self, row: usize, len: usize) -> Option<Range<usize>> {
+        let (start, end) = self.range();
+        if row < start.row || row > end.row {
+            return None;
+        }
+
+        let first = if row == start.row { start.column } else { 0 };
+        let last = if row == end.row { end.column } else { len };
+        let columns = first.min(len)..last.min(len);
+        (!columns.is_empty()).then_some(columns)
+    }
+
+    /// Selected text of the rows, the rows are on their own lines
+    pub fn text(&self, rows: &[String]) -> String {
+        let (start, end) = self.range();
+        let lines: Vec<String> = rows
+            .iter()
+            .enumerate()
+            .take(end.row + 1)
+            .skip(start.row)
+            .map(|(idx, row)| {
+                let columns = self.columns(idx, row.chars().count()).unwrap_or_default();
+                let line: String = row
+                    .chars()
+                    .skip(columns.start)
+                    .take(columns.len())
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<String> {
+        vec![
+            " LIIGA                          2/4".to_string(),
+            String::new(),
+            " Tappara - Ilves              4-2".to_string(),
+            " HIFK - Kärpät                1-3".to_string(),
+        ]
+    }
+
+    #[test]
+    fn selection_on_one_row() {
+        let mut selection = TextSelection::new(TextPos::new(2, 1));
+        assert!(selection.is_empty());
+        assert_eq!(selection.columns(2, 32), None);
+
+        selection.cursor = TextPos::new(2, 8);
+        assert_eq!(selection.columns(2, 32), Some(1..8));
+        assert_eq!(selection.columns(3, 32), None);
+        assert_eq!(selection.text(&rows()), "Tappara");
+    }
+
+    #[test]
+    fn selection_across_rows() {
+        // Dragged upwards from the middle of the last row
+        let mut selection = TextSelection::new(TextPos::new(3, 14));
+        selection.cursor = TextPos::new(0, 32);
+
+        assert_eq!(
+            selection.range(),
+            (TextPos::new(0, 32), TextPos::new(3, 14))
+        );
+        assert_eq!(selection.columns(0, 35), Some(32..35));
+        assert_eq!(selection.columns(1, 0), None);
+        assert_eq!(selection.columns(2, 32), Some(0..32));
+        assert_eq!(selection.columns(3, 32), Some(0..14));
+        assert_eq!(
+            selection.text(&rows()),
+            "2/4\n\n Tappara - Ilves              4-2\n HIFK - Kärpät"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use crate::parser::{Fastext, SvtText, TeleText};
+
+use super::common::{TelePage, TelePager};
+use super::teletext::navigation_pages;
+
+/// SVT returns all the sub pages in the same response so the sub page is not part of the url
+impl TelePager for SvtText {
+    const SOURCE: &'static str = "svt_text";
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn to_full_page(page: &TelePage) -> String {
+        // https://www.svt.se/text-tv/api/100
+        format!("https://www.svt.se/text-tv/api/{}", page.page)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn to_full_page(page: &TelePage) -> String {
+        // https://www.svt.se/text-tv/api/100
+        let proxy = env!(
+            "TELETEXT_PROXY_URL",
+            "TELETEXT_PROXY_URL env variable is required for wasm builds"
+        );
+        format!("{proxy}/?url=https://www.svt.se/text-tv/api/{}", page.page)
+    }
+
+    fn to_page_str(page: &TelePage) -> String {
+        format!("{}_{:04}", page.page, page.sub_page)
+    }
+
+    fn from_page_str(page: &str) -> Option<TelePage> {
+        let current_page = page.get(0..3)?.parse::<i32>().ok()?;
+        let sub_page = page.get(4..8)?.parse::<i32>().ok()?;
+
+        Some(TelePage::new(current_page, sub_page))
+    }
+
+    fn fastext(&self) -> &Fastext<'_> {
+        &self.teletext.fastext
+    }
+
+    fn changed_rows(&self, previous: &Self) -> Vec<usize> {
+        TeleText::changed_rows(&self.teletext, &previous.teletext)
+    }
+
+    fn plain_text(&self) -> Option<String> {
+        Some(TeleText::to_plain_text(&self.teletext))
+    }
+
+    fn to_json(&self) -> Option<String> {
+        serde_json::to_string(&self.teletext).ok()
+    }
+
+    fn search_text(&self) -> Option<(String, Vec<String>)> {
+        let teletext = &self.teletext;
+        Some((teletext.title.to_string(), TeleText::middle_texts(teletext)))
+    }
+
+    fn prefetch_pages(&self) -> Vec<TelePage> {
+        navigation_pages::<Self>(&self.teletext)
+    }
+}
@@ -0,0 +1,196 @@
+use super::{
+    common::{IGuiCtx, TelePage},
+    OptionSetting, Pages, Shared, TeleTextSettings,
+};
+
+/// Reader with its own history and refresh interval
+pub(super) struct Tab {
+    pub(super) reader: Pages,
+    pub(super) page: Box<dyn IGuiCtx>,
+    pub(super) refresh_interval: OptionSetting<u64>,
+}
+
+impl Tab {
+    pub(super) fn new(
+        reader: Pages,
+        refresh_interval: OptionSetting<u64>,
+        egui: &egui::Context,
+        shared: &Shared,
+        settings: &TeleTextSettings,
+    ) -> Self {
+        let mut page = reader.to_gui(egui, shared);
+        settings.init_page(&mut page);
+
+        let mut tab = Self {
+            reader,
+            page,
+            refresh_interval,
+        };
+        tab.set_refresh_interval();
+        tab
+    }
+
+    pub(super) fn from_saved(
+        saved: SavedTab,
+        egui: &egui::Context,
+        shared: &Shared,
+        settings: &TeleTextSettings,
+    ) -> Self {
+        let mut tab = Self::new(saved.reader, saved.refresh_interval, egui, shared, settings);
+        tab.page.open_page(saved.page);
+        tab
+    }
+
+    pub(super) fn saved(&self) -> SavedTab {
+        SavedTab {
+            reader: self.reader.clone(),
+            page: self.page.current_page(),
+            refresh_interval: self.refresh_interval.clone(),
+        }
+    }
+
+    /// Open the reader in this tab, the refresh interval stays the same
+    pub(super) fn set_reader(
+        &mut self,
+        reader: Pages,
+        egui: &egui::Context,
+        shared: &Shared,
+        settings: &TeleTextSettings,
+    ) {
+        let refresh_interval = self.refresh_interval.clone();
+        *self = Self::new(reader, refresh_interval, egui, shared, settings);
+    }
+
+    pub(super) fn set_refresh_interval(&mut self) {
+        if self.refresh_interval.is_used {
+            self.page.set_refresh_interval(self.refresh_interval.value);
+        } else {
+            self.page.stop_refresh_interval();
+        }
+    }
+
+    pub(super) fn title(&self) -> String {
+        format!("{} P{}", self.reader.name(), self.page.current_page().page)
+    }
+}
+
+/// Tab that is opened again when the app is started
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct SavedTab {
+    pub reader: Pages,
+    pub page: TelePage,
+    pub refresh_interval: OptionSetting<u64>,
+}
+
+/// Tabs that were open when the app was closed
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SavedTabs {
+    pub tabs: Vec<SavedTab>,
+    pub active: usize,
+}
+
+impl SavedTabs {
+    /// Index of the tab that is opened, 0 is the default tab when there are no saved tabs
+    pub fn active_tab(&self) -> usize {
+        self.active.min(self.tabs.len().saturating_sub(1))
+    }
+}
+
+pub(super) const TABS_KEY: &str = "tabs";
+
+/// Remove the tab at `idx`. The active tab stays the same, or if it was closed,
+/// the next tab or the last tab is activated
+pub fn close_tab<T>(tabs: &mut Vec<T>, active: &mut usize, idx: usize) {
+    tabs.remove(idx);
+    if *active > idx || *active >= tabs.len() {
+        *active = active.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_tab(reader: Pages, page: TelePage, refresh: Option<u64>) -> SavedTab {
+        SavedTab {
+            reader,
+            page,
+            refresh_interval: OptionSetting {
+                is_used: refresh.is_some(),
+                value: refresh.unwrap_or(300),
+            },
+        }
+    }
+
+    #[test]
+    fn saved_tabs_round_trip() {
+        let saved = SavedTabs {
+            tabs: vec![
+                saved_tab(Pages::YleText, TelePage::new(100, 1), None),
+                saved_tab(
+                    Pages::Provider("Teksti-TV".into()),
+                    TelePage::new(235, 2),
+                    Some(60),
+                ),
+            ],
+            active: 1,
+        };
+
+        let json = serde_json::to_string(&saved).unwrap();
+        let restored: SavedTabs = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.active_tab(), 1);
+        assert_eq!(restored.tabs.len(), 2);
+
+        let tab = &restored.tabs[0];
+        assert_eq!(tab.reader, Pages::YleText);
+        assert_eq!((tab.page.page, tab.page.sub_page), (100, 1));
+        assert!(!tab.refresh_interval.is_used);
+
+        let tab = &restored.tabs[1];
+        assert_eq!(tab.reader, Pages::Provider("Teksti-TV".into()));
+        assert_eq!((tab.page.page, tab.page.sub_page), (235, 2));
+        assert!(tab.refresh_interval.is_used);
+        assert_eq!(tab.refresh_interval.value, 60);
+    }
+
+    #[test]
+    fn saved_tabs_active_index() {
+        // Storage of an older version has no tabs, the default tab is opened
+        let restored: SavedTabs = serde_json::from_str("{}").unwrap();
+        assert!(restored.tabs.is_empty());
+        assert_eq!(restored.active_tab(), 0);
+
+        let saved = SavedTabs {
+            tabs: vec![saved_tab(Pages::SvtText, TelePage::new(100, 1), None)],
+            active: 3,
+        };
+        assert_eq!(saved.active_tab(), 0);
+    }
+
+    #[test]
+    fn close_tab_keeps_active_tab() {
+        let mut tabs = vec!["a", "b", "c", "d"];
+        let mut active = 2;
+
+        // Tabs after the active tab don't move it
+        close_tab(&mut tabs, &mut active, 3);
+        assert_eq!(tabs[active], "c");
+
+        // Tabs before it do
+        close_tab(&mut tabs, &mut active, 0);
+        assert_eq!((active, tabs[active]), (1, "c"));
+
+        // Closing the last active tab activates the one before it
+        close_tab(&mut tabs, &mut active, 1);
+        assert_eq!((active, tabs[active]), (0, "b"));
+    }
+
+    #[test]
+    fn close_active_tab_activates_next() {
+        let mut tabs = vec!["a", "b", "c"];
+        let mut active = 1;
+        close_tab(&mut tabs, &mut active, 1);
+        assert_eq!((active, tabs[active]), (1, "c"));
+    }
+}
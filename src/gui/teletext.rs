@@ -0,0 +1,641 @@
+use std::{cell::RefCell, ops::Deref, rc::Rc};
+
+use crate::parser::{
+    common::ParseErr, ExportFormat, Fastext, HtmlColors, HtmlItem, HtmlLink, HtmlParser, HtmlText,
+    ImageFormat, TeleText, TextPage, TtiPage, MIDDLE_TEXT_MAX_LEN,
+};
+use egui::{InputState, TextStyle};
+use image::ImageResult;
+
+use super::{
+    common::{FetchState, GuiContext, IGuiCtx, PageDraw, TelePage, TelePager},
+    crawl::CrawlProgress,
+    print::PrintRange,
+    search::SearchResult,
+    selection::{TextPos, TextSelection},
+    svg_icon::{IconName, SvgIcon},
+    watch::PageWatch,
+};
+
+/// Reader for all the providers that have their pages as text
+pub struct GuiTeleText<'a, T: TextPage + HtmlParser + TelePager + Send + 'static> {
+    ui: &'a mut egui::Ui,
+    ctx: Rc<RefCell<&'a mut GuiContext<T>>>,
+    panel_width: f32,
+    char_width: f32,
+    is_small: bool,
+}
+
+impl<'a, T: TextPage + HtmlParser + TelePager + Send + 'static> GuiTeleText<'a, T> {
+    /// Page number and the sub-page indicator, e.g. "P190 2/5"
+    fn get_page_str(&self, sub_page: Option<String>) -> String {
+        let page_buf = &self.ctx.borrow().page_buffer;
+        let page_num = if !page_buf.is_empty() {
+            let mut page_str = "---".as_bytes().to_vec();
+            for (idx, num) in page_buf.iter().enumerate() {
+                page_str[idx] = b'0' + (*num as u8);
+            }
+            String::from_utf8(page_str.to_vec()).unwrap()
+        } else {
+            self.ctx.borrow().current_page.page.to_string()
+        };
+
+        match sub_page {
+            Some(sub_page) => format!("P{page_num} {sub_page}"),
+            None => format!("P{page_num}"),
+        }
+    }
+
+    fn draw_header_small(&mut self, title: &HtmlText, sub_page: Option<String>) {
+        // align with page navigation
+        let chw = self.char_width;
+        let page = self.get_page_str(sub_page);
+        let page_len = (page.chars().count() as f32) * chw;
+        let title_len = (title.chars().count() as f32) * chw;
+        let title_space = (self.panel_width / 2.0) - (title_len / 2.0) - page_len;
+
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.label(page);
+            ui.add_space(title_space);
+            ui.label(title.clone());
+        });
+    }
+
+    fn draw_header_normal(
+        &mut self,
+        title: &HtmlText,
+        sub_page: Option<String>,
+        updated: Option<String>,
+    ) {
+        // align with page navigation
+        let chw = self.char_width;
+        let nav_length = chw * 69.0;
+        let nav_start = (self.panel_width / 2.0) - (nav_length / 2.0);
+        let page = self.get_page_str(sub_page);
+        let page_len = (page.chars().count() as f32) * chw;
+        let now = chrono::Local::now().format("%d.%m. %H:%M:%S");
+        let time = match updated {
+            Some(updated) => format!("{updated}  {now}"),
+            None => now.to_string(),
+        };
+        let time_len = (time.chars().count() as f32) * chw;
+        let title_len = (title.chars().count() as f32) * chw;
+
+        let title_space = (nav_length / 2.0) - (title_len / 2.0) - page_len;
+        let time_space = nav_length - title_space - page_len - title_len - time_len;
+
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(nav_start);
+            ui.label(page);
+            ui.add_space(title_space);
+            ui.label(title.clone());
+            ui.add_space(time_space);
+            ui.label(time);
+        });
+    }
+
+    fn draw_header(&mut self, title: &HtmlText, sub_page: Option<String>, updated: Option<String>) {
+        if self.is_small {
+            self.draw_header_small(title, sub_page);
+        } else {
+            self.draw_header_normal(title, sub_page, updated);
+        }
+    }
+
+    fn draw_page_navigation_small(&mut self, navigation: &[HtmlItem]) {
+        let mut body_font = TextStyle::Body.resolve(self.ui.style());
+        body_font.size *= 3.0;
+        let arrow_width = self.ui.fonts().glyph_width(&body_font, 'W');
+        let chars_len = arrow_width * 4.0 + self.char_width * 9.0;
+        let page_nav_start = (self.panel_width / 2.0) - (chars_len / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(page_nav_start);
+            for (idx, item) in navigation.iter().enumerate() {
+                let icon = match idx {
+                    0 => IconName::ArrowLeft,
+                    1 => IconName::ArrowUp,
+                    2 => IconName::ArrowDown,
+                    3 => IconName::ArrowRight,
+                    _ => unreachable!(), // TODO: generic "error" icon
+                };
+
+                let icon = SvgIcon::from_icon(icon, arrow_width);
+                match item {
+                    HtmlItem::Link(link) => {
+                        let resp = ui.add(icon.into_link());
+                        if resp.clicked() {
+                            ctx.borrow_mut().follow_link(&link.url);
+                        } else if resp.middle_clicked() {
+                            ctx.borrow_mut().open_in_new_tab(&link.url);
+                        }
+                    }
+                    HtmlItem::Text(_) => {
+                        ui.add(icon);
+                    }
+                }
+
+                if idx < 3 {
+                    ui.label(" | ");
+                }
+            }
+        });
+    }
+
+    fn draw_page_navigation_normal(&mut self, navigation: &[HtmlItem]) {
+        // "Edellinen sivu | Edellinen alasivu | Seuraava alasivu | Seuraava sivu" is 69 char
+        let page_nav_start = (self.panel_width / 2.0) - (self.char_width * 69.0 / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(page_nav_start);
+            for (idx, item) in navigation.iter().enumerate() {
+                item.add_to_ui(ui, ctx.clone());
+                if idx < 3 {
+                    ui.label(" | ");
+                }
+            }
+        });
+    }
+
+    fn draw_page_navigation(&mut self, navigation: &[HtmlItem]) {
+        if self.is_small {
+            self.draw_page_navigation_small(navigation);
+        } else {
+            self.draw_page_navigation_normal(navigation);
+        }
+    }
+
+    /// Rows that changed in the last refresh and the selected text are highlighted
+    fn draw_middle(&mut self, rows: &Vec<Vec<HtmlItem>>, changed_rows: &[usize]) {
+        let chw = self.char_width;
+        let middle_text_len = chw * (MIDDLE_TEXT_MAX_LEN as f32);
+        let middle_text_start = (self.panel_width / 2.0) - (middle_text_len / 2.0);
+        let highlight = self.ui.visuals().selection.bg_fill;
+        let selected = self.ui.visuals().widgets.active.bg_fill;
+        let selection = self.ctx.borrow().selection;
+        let ctx = &self.ctx;
+        let mut row_rects = Vec::with_capacity(rows.len());
+        let mut row_lens = Vec::with_capacity(rows.len());
+        for (idx, row) in rows.iter().enumerate() {
+            // Background is painted after the row size is known, but below the row
+            let background = self.ui.painter().add(egui::Shape::Noop);
+            let resp = self.ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.add_space(middle_text_start);
+                for item in row {
+                    item.add_to_ui(ui, ctx.clone());
+                }
+            });
+
+            let mut rect = resp.response.rect;
+            rect.min.x += middle_text_start;
+            rect.max.x = rect.min.x + middle_text_len;
+            let row_len = row.iter().map(|item| item.text().chars().count()).sum();
+
+            let mut shapes = Vec::new();
+            if changed_rows.contains(&idx) {
+                shapes.push(egui::Shape::rect_filled(rect, 0.0, highlight));
+            }
+            if let Some(columns) = selection.and_then(|s| s.columns(idx, row_len)) {
+                let mut selected_rect = rect;
+                selected_rect.min.x = rect.min.x + chw * columns.start as f32;
+                selected_rect.max.x = rect.min.x + chw * columns.end as f32;
+                shapes.push(egui::Shape::rect_filled(selected_rect, 0.0, selected));
+            }
+            self.ui.painter().set(background, egui::Shape::Vec(shapes));
+
+            row_rects.push(rect);
+            row_lens.push(row_len);
+        }
+
+        self.select_text(&row_rects, &row_lens);
+    }
+
+    /// Dragging over the middle rows selects text. Pressing anywhere else clears the selection
+    fn select_text(&mut self, rows: &[egui::Rect], row_lens: &[usize]) {
+        let mut ctx = self.ctx.borrow_mut();
+        let pos = match ctx.pointer.interact_pos() {
+            Some(pos) if !rows.is_empty() => pos,
+            _ => return,
+        };
+
+        let chw = self.char_width;
+        let text_pos = |pos: egui::Pos2| {
+            let row = rows
+                .iter()
+                .position(|rect| pos.y < rect.max.y)
+                .unwrap_or(rows.len() - 1);
+            let column = ((pos.x - rows[row].min.x) / chw).round().max(0.0) as usize;
+            TextPos::new(row, column.min(row_lens[row]))
+        };
+
+        let is_down = ctx.pointer.primary_down();
+        if ctx.pointer.any_pressed() && is_down {
+            // Windows on top of the page don't start a selection
+            let area = rows
+                .iter()
+                .fold(egui::Rect::NOTHING, |area, row| area.union(*row));
+            let on_page = self.ui.ctx().layer_id_at(pos) == Some(self.ui.layer_id());
+            ctx.selection =
+                (on_page && area.contains(pos)).then(|| TextSelection::new(text_pos(pos)));
+        } else if let Some(selection) = &mut ctx.selection {
+            if !selection.dragging {
+                return;
+            }
+
+            if is_down {
+                selection.cursor = text_pos(pos);
+            } else {
+                selection.dragging = false;
+                if selection.is_empty() {
+                    ctx.selection = None;
+                }
+            }
+        }
+    }
+
+    fn draw_sub_pages(&mut self, pages: &[HtmlItem]) {
+        let middle_text_start =
+            (self.panel_width / 2.0) - (self.char_width * (MIDDLE_TEXT_MAX_LEN as f32) / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(middle_text_start);
+            for item in pages {
+                item.add_to_ui(ui, ctx.clone());
+            }
+        });
+    }
+
+    fn draw_bottom_navigation_small(&mut self, navigation: &[HtmlLink]) {
+        // "Teksti-TV" is 9 chars
+        let page_nav_start = (self.panel_width / 2.0) - (self.char_width * 9.0 / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(page_nav_start);
+            if let Some(link) = navigation.last() {
+                link.add_to_ui(ui, ctx.clone());
+            }
+        });
+    }
+
+    fn draw_bottom_navigation_normal(&mut self, navigation: &[HtmlLink]) {
+        // e.g. "Kotimaa | Ulkomaat | Talous | Urheilu | Svenska sidor | Teksti-TV"
+        let text_len = navigation.iter().fold(0.0, |acum, val| {
+            acum + val.inner_text.chars().count() as f32 + 3.0
+        }) - 3.0;
+        let page_nav_start = (self.panel_width / 2.0) - (self.char_width * text_len / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(page_nav_start);
+            for (idx, item) in navigation.iter().enumerate() {
+                item.add_to_ui(ui, ctx.clone());
+                if idx < navigation.len() - 1 {
+                    ui.label(" | ");
+                }
+            }
+        });
+    }
+
+    fn draw_fastext(&mut self, fastext: &Fastext) {
+        if fastext.is_empty() {
+            return;
+        }
+
+        // Reserve two characters for the padding of each button
+        let chars_len = fastext.links().fold(0.0, |acum, (_, link)| {
+            acum + link.inner_text.chars().count() as f32 + 2.0
+        });
+        let fastext_start = (self.panel_width / 2.0) - (self.char_width * chars_len / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal_wrapped(|ui| {
+            ui.add_space(fastext_start.max(0.0));
+            fastext.add_to_ui(ui, ctx.clone());
+        });
+    }
+
+    fn draw_bottom_navigation(&mut self, navigation: &[HtmlLink]) {
+        if self.is_small {
+            self.draw_bottom_navigation_small(navigation);
+        } else {
+            self.draw_bottom_navigation_normal(navigation);
+        }
+    }
+}
+
+impl<'a, T: TextPage + HtmlParser + TelePager + Send + 'static> PageDraw<'a, T>
+    for GuiTeleText<'a, T>
+{
+    fn draw(&mut self) {
+        let ctx = &self.ctx;
+        let state = self.ctx.borrow().state.clone();
+
+        let mut error_action = None;
+        let mut version = None;
+        let mut load = false;
+        match state.lock().unwrap().deref() {
+            FetchState::Complete(page) => {
+                let sub_page = self.ctx.borrow().sub_page_indicator(page);
+                let (updated, mut changed_rows) = self.ctx.borrow().update_status();
+                // Line of the opened search result is highlighted like the changed rows
+                changed_rows.extend(self.ctx.borrow().search_row());
+                let page = page.teletext();
+                self.draw_header(&page.title, sub_page, updated);
+                self.draw_page_navigation(&page.page_navigation);
+                self.draw_middle(&page.middle_rows, &changed_rows);
+                self.draw_sub_pages(&page.sub_pages);
+                self.ui.label("\n");
+                self.draw_page_navigation(&page.page_navigation);
+                self.draw_bottom_navigation(&page.bottom_navigation);
+                self.draw_fastext(&page.fastext);
+                version = self.ctx.borrow().timeline_ui(self.ui);
+            }
+            FetchState::Fetching => {
+                self.ui
+                    .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        ui.label("Loading...");
+                    });
+            }
+            FetchState::Error(failure) | FetchState::InitFailed(failure) => {
+                self.ui
+                    .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        error_action = failure.add_to_ui(ui, ctx.clone());
+                    });
+            }
+            FetchState::Init => {
+                self.ui
+                    .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        ui.label("Opening...");
+                    });
+                load = true;
+            }
+        };
+
+        // Loading changes the state so it's done after the page isn't locked anymore
+        let mut ctx = self.ctx.borrow_mut();
+        if load {
+            ctx.load_current_page();
+        }
+
+        if let Some(link) = ctx.clicked_link.take() {
+            ctx.load_page(&link, true);
+        }
+
+        if let Some(action) = error_action {
+            ctx.error_action(action);
+        }
+
+        // Page is locked while it's drawn so the version is shown afterwards
+        if let Some(idx) = version {
+            ctx.show_version(idx);
+        }
+    }
+
+    fn new(ui: &'a mut egui::Ui, ctx: &'a mut GuiContext<T>) -> Self {
+        let panel_width = ui.available_width();
+        let body_font = TextStyle::Body.resolve(ui.style());
+        let char_width = ui.fonts().glyph_width(&body_font, 'W');
+        // Aligned with page navigation
+        let nav_len = char_width * 69.0;
+        let is_small = nav_len + 4.0 > panel_width;
+
+        Self {
+            ui,
+            ctx: Rc::new(RefCell::new(ctx)),
+            char_width,
+            panel_width,
+            is_small,
+        }
+    }
+}
+
+pub struct GuiTeleTextContext<T: TextPage + HtmlParser + TelePager + Send + 'static> {
+    ctx: GuiContext<T>,
+}
+
+impl<T: TextPage + HtmlParser + TelePager + Send + 'static> GuiTeleTextContext<T> {
+    pub fn new(ctx: GuiContext<T>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<T: TextPage + HtmlParser + TelePager + Send + 'static> IGuiCtx for GuiTeleTextContext<T> {
+    fn handle_input(&mut self, input: InputState) {
+        self.ctx.handle_input(input)
+    }
+
+    fn update(&mut self) {
+        self.ctx.update()
+    }
+
+    fn draw(&mut self, ui: &mut egui::Ui) {
+        GuiTeleText::new(ui, &mut self.ctx).draw();
+    }
+
+    fn set_refresh_interval(&mut self, interval: u64) {
+        self.ctx.set_refresh_interval(interval)
+    }
+
+    fn stop_refresh_interval(&mut self) {
+        self.ctx.stop_refresh_interval()
+    }
+
+    fn set_carousel_interval(&mut self, interval: u64) {
+        self.ctx.set_carousel_interval(interval)
+    }
+
+    fn stop_carousel(&mut self) {
+        self.ctx.stop_carousel()
+    }
+
+    fn set_prefetch(&mut self, enabled: bool) {
+        self.ctx.prefetch = enabled;
+    }
+
+    fn watch_current_page(&self, interval: u64, keyword: Option<String>) -> PageWatch {
+        self.ctx
+            .watch_page(self.ctx.current_page, interval, keyword)
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        self.ctx.store.search(query)
+    }
+
+    fn open_search_result(&mut self, result: &SearchResult) {
+        self.ctx.open_search_result(result)
+    }
+
+    fn crawl_progress(&self) -> CrawlProgress {
+        self.ctx.crawl.progress.lock().unwrap().clone()
+    }
+
+    fn resume_crawl(&mut self, progress: CrawlProgress) {
+        self.ctx.resume_crawl(progress)
+    }
+
+    fn start_crawl(&mut self) {
+        self.ctx.start_crawl()
+    }
+
+    fn stop_crawl(&mut self) {
+        self.ctx.stop_crawl()
+    }
+
+    fn restart_crawl(&mut self) {
+        self.ctx.restart_crawl()
+    }
+
+    fn start_print(&mut self, range: PrintRange, path: String) {
+        self.ctx.start_print(range, path)
+    }
+
+    fn stop_print(&mut self) {
+        self.ctx.stop_print()
+    }
+
+    fn print_progress(&self) -> Option<(bool, String)> {
+        self.ctx.print_progress()
+    }
+
+    fn take_new_tab(&mut self) -> Option<TelePage> {
+        self.ctx.new_tab.take()
+    }
+
+    fn current_page(&self) -> TelePage {
+        self.ctx.current_page
+    }
+
+    fn open_page(&mut self, page: TelePage) {
+        self.ctx.open_page(page)
+    }
+
+    fn return_from_error_page(&mut self) {
+        self.ctx.return_from_error_page()
+    }
+
+    fn load_current_page(&mut self) {
+        self.ctx.load_current_page()
+    }
+
+    fn load_page(&mut self, page: &str, add_to_history: bool) {
+        self.ctx.load_page(page, add_to_history)
+    }
+
+    fn import_tti(&mut self, data: &[u8]) -> Result<(), ParseErr> {
+        let tti = TtiPage::parse(data)?;
+        // Hex pages can't be opened in the readers
+        let page = TelePage::new(tti.page.ok_or(ParseErr::InvalidPage)?, tti.sub_page);
+        self.ctx.show_page(page, T::from_teletext(tti.teletext));
+        Ok(())
+    }
+
+    fn export_tti(&self) -> Option<Vec<u8>> {
+        let current = &self.ctx.current_page;
+        match self.ctx.state.lock().unwrap().deref() {
+            FetchState::Complete(page) => {
+                Some(page.teletext().to_tti(current.page, current.sub_page))
+            }
+            _ => None,
+        }
+    }
+
+    fn export_page(&self, format: ExportFormat, colors: &HtmlColors) -> Option<String> {
+        let current = &self.ctx.current_page;
+        match self.ctx.state.lock().unwrap().deref() {
+            FetchState::Complete(page) => {
+                Some(
+                    page.teletext()
+                        .export(format, current.page, current.sub_page, colors),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        let selection = self.ctx.selection?;
+        match self.ctx.state.lock().unwrap().deref() {
+            FetchState::Complete(page) => Some(selection.text(&page.teletext().middle_texts())),
+            _ => None,
+        }
+    }
+
+    fn page_text(&self, header: bool) -> Option<String> {
+        let current = &self.ctx.current_page;
+        match self.ctx.state.lock().unwrap().deref() {
+            FetchState::Complete(page) => {
+                let teletext = page.teletext();
+                Some(teletext.clipboard_text(current.page, current.sub_page, header))
+            }
+            _ => None,
+        }
+    }
+
+    fn export_image(
+        &self,
+        format: ImageFormat,
+        scale: f32,
+        _header: bool,
+        colors: &HtmlColors,
+    ) -> Option<ImageResult<Vec<u8>>> {
+        let current = &self.ctx.current_page;
+        match self.ctx.state.lock().unwrap().deref() {
+            FetchState::Complete(page) => {
+                let image = page
+                    .teletext()
+                    .to_page_image(current.page, current.sub_page, colors);
+                Some(image.render(format, scale))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Pages linked from the page navigation, sub-pages and bottom navigation, in that order
+pub fn navigation_pages<T: TelePager>(page: &TeleText) -> Vec<TelePage> {
+    let items = page.page_navigation.iter().chain(page.sub_pages.iter());
+    let links = items
+        .filter_map(|item| match item {
+            HtmlItem::Link(link) => Some(link),
+            HtmlItem::Text(_) => None,
+        })
+        .chain(page.bottom_navigation.iter());
+
+    let mut pages: Vec<TelePage> = Vec::new();
+    for link in links {
+        if let Some(page) = T::from_page_str(&link.url) {
+            let is_new = !pages
+                .iter()
+                .any(|p| p.page == page.page && p.sub_page == page.sub_page);
+            if is_new {
+                pages.push(page);
+            }
+        }
+    }
+
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::YleText;
+
+    #[test]
+    fn draw_loads_init_page() {
+        let egui = egui::Context::default();
+        let mut page = GuiTeleTextContext::new(GuiContext::<YleText>::new(egui.clone()));
+        let _ = egui.run(egui::RawInput::default(), |egui| {
+            egui::CentralPanel::default().show(egui, |ui| page.draw(ui));
+        });
+
+        // Drawing returns instead of waiting for the state it holds
+        assert!(!matches!(*page.ctx.state.lock().unwrap(), FetchState::Init));
+    }
+}
@@ -0,0 +1,259 @@
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{prelude::*, JsCast};
+
+use super::common::{PageResponse, TelePage};
+use crate::parser::ProviderConfig;
+
+/// Content of the watched page that is compared between the polls, see `GuiContext::watch_page`
+pub type WatchContent = fn(PageResponse, Option<&ProviderConfig>, &TelePage) -> Option<String>;
+
+/// Page polled in the background for changes, see `PageWatcher`
+#[derive(Clone)]
+pub struct PageWatch {
+    pub page: TelePage,
+    site: String,
+    provider: Option<Arc<ProviderConfig>>,
+    /// Seconds between the polls
+    pub interval: u64,
+    /// Notify only when the changed page contains this text
+    pub keyword: Option<String>,
+    parse: WatchContent,
+    /// Seconds since the last poll
+    elapsed: u64,
+    /// Content of the page in the last poll, None before the first poll
+    content: Arc<Mutex<Option<String>>>,
+}
+
+impl PageWatch {
+    pub fn new(
+        page: TelePage,
+        site: String,
+        provider: Option<Arc<ProviderConfig>>,
+        interval: u64,
+        keyword: Option<String>,
+        parse: WatchContent,
+    ) -> Self {
+        Self {
+            page,
+            site,
+            provider,
+            interval,
+            keyword,
+            parse,
+            elapsed: 0,
+            content: Default::default(),
+        }
+    }
+
+    /// True once per interval, called every second
+    fn should_poll(&mut self) -> bool {
+        self.elapsed += 1;
+        if self.elapsed >= self.interval {
+            self.elapsed = 0;
+            return true;
+        }
+
+        false
+    }
+
+    /// Notify if the page changed since the last poll.
+    /// Invalid pages are ignored so a broken response doesn't cause a notification
+    fn check(&self, response: PageResponse) {
+        let content = match (self.parse)(response, self.provider.as_deref(), &self.page) {
+            Some(content) => content,
+            None => return,
+        };
+
+        let keyword = self.keyword.as_deref();
+        if content_changed(&mut self.content.lock().unwrap(), content, keyword) {
+            let body = match keyword {
+                Some(keyword) => format!("The page contains \"{}\"", keyword),
+                None => String::new(),
+            };
+            notify(&format!("Teletext page {} changed", self.page.page), &body);
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match &self.keyword {
+            Some(keyword) => format!(
+                "P{} every {} s when \"{}\" appears",
+                self.page.page, self.interval, keyword
+            ),
+            None => format!("P{} every {} s", self.page.page, self.interval),
+        }
+    }
+}
+
+/// Watched pages of all tabs. They're polled by one worker, a thread on native and
+/// an interval on wasm, so they're polled whichever page or dashboard is shown
+#[derive(Default)]
+pub struct PageWatcher {
+    watches: Arc<Mutex<Vec<PageWatch>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    running: Arc<AtomicBool>,
+    #[cfg(target_arch = "wasm32")]
+    interval_handle: Option<i32>,
+    /// Called by the interval, it's dropped after the interval is cleared
+    #[cfg(target_arch = "wasm32")]
+    poll_fn: Option<Closure<dyn FnMut()>>,
+}
+
+impl PageWatcher {
+    /// Start polling the page, the worker is started with the first watch
+    pub fn add(&mut self, watch: PageWatch) {
+        #[cfg(target_arch = "wasm32")]
+        request_permission();
+        self.watches.lock().unwrap().push(watch);
+        self.start();
+    }
+
+    pub fn remove(&mut self, idx: usize) {
+        let mut watches = self.watches.lock().unwrap();
+        if idx < watches.len() {
+            watches.remove(idx);
+        }
+    }
+
+    pub fn descriptions(&self) -> Vec<String> {
+        let watches = self.watches.lock().unwrap();
+        watches.iter().map(PageWatch::description).collect()
+    }
+
+    /// Watches whose interval has passed, the lock isn't held while they're polled
+    fn due_watches(watches: &Mutex<Vec<PageWatch>>) -> Vec<PageWatch> {
+        let mut watches = watches.lock().unwrap();
+        watches
+            .iter_mut()
+            .filter_map(|watch| watch.should_poll().then(|| watch.clone()))
+            .collect()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start(&mut self) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let watches = self.watches.clone();
+        let running = self.running.clone();
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_secs(1));
+                for watch in Self::due_watches(&watches) {
+                    match super::http::client().get(&watch.site, None) {
+                        Ok(response) => watch.check(response),
+                        Err(failure) => {
+                            log::info!("Failed to poll page {}: {:?}", watch.site, failure.error)
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start(&mut self) {
+        if self.interval_handle.is_some() {
+            return;
+        }
+
+        let watches = self.watches.clone();
+        let poll_fn = Closure::wrap(Box::new(move || {
+            for watch in Self::due_watches(&watches) {
+                wasm_bindgen_futures::spawn_local(async move {
+                    match super::common::fetch_url(&watch.site).await {
+                        Ok(response) => watch.check(response),
+                        Err(failure) => {
+                            tracing::info!(
+                                "Failed to poll page {}: {:?}",
+                                watch.site,
+                                failure.error
+                            )
+                        }
+                    }
+                });
+            }
+        }) as Box<dyn FnMut()>);
+
+        let handle = web_sys::window()
+            .expect("no global `window` exists")
+            .set_interval_with_callback_and_timeout_and_arguments_0(
+                poll_fn.as_ref().unchecked_ref(),
+                1000,
+            )
+            .unwrap();
+        self.interval_handle = Some(handle);
+        self.poll_fn = Some(poll_fn);
+    }
+}
+
+impl Drop for PageWatcher {
+    fn drop(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.running.store(false, Ordering::Relaxed);
+        #[cfg(target_arch = "wasm32")]
+        if let Some(handle) = self.interval_handle {
+            web_sys::window()
+                .expect("no global `window` exists")
+                .clear_interval_with_handle(handle);
+        }
+    }
+}
+
+/// Store the new content and tell if the user should be notified about it.
+/// The first poll only stores the content since there's nothing to compare it to
+fn content_changed(previous: &mut Option<String>, content: String, keyword: Option<&str>) -> bool {
+    let changed = matches!(previous, Some(previous) if *previous != content);
+    let has_keyword =
+        keyword.is_none_or(|keyword| content.to_lowercase().contains(&keyword.to_lowercase()));
+    *previous = Some(content);
+
+    changed && has_keyword
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn notify(title: &str, body: &str) {
+    let shown = notify_rust::Notification::new()
+        .summary(title)
+        .body(body)
+        .show();
+    if let Err(err) = shown {
+        log::warn!("Cannot show notification: {}", err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn notify(title: &str, body: &str) {
+    use web_sys::{Notification, NotificationOptions, NotificationPermission};
+
+    if Notification::permission() != NotificationPermission::Granted {
+        tracing::warn!("Notifications are not allowed");
+        return;
+    }
+
+    let mut options = NotificationOptions::new();
+    options.body(body);
+    if Notification::new_with_options(title, &options).is_err() {
+        tracing::warn!("Cannot show notification");
+    }
+}
+
+/// Browsers only show notifications after the user has allowed them
+#[cfg(target_arch = "wasm32")]
+fn request_permission() {
+    use web_sys::{Notification, NotificationPermission};
+
+    if Notification::permission() == NotificationPermission::Default {
+        // The answer is checked when the notification is shown
+        let _ = Notification::request_permission();
+    }
+}
@@ -1,13 +1,20 @@
 use std::{cell::RefCell, ops::Deref, rc::Rc};
 
-use egui::{CursorIcon, InputState, TextStyle};
+use egui::{CursorIcon, InputState, PointerButton, TextStyle};
 use egui_extras::RetainedImage;
+use image::ImageResult;
 
-use crate::parser::{common::HtmlImageArea, HtmlLink, HtmlText, YleImage};
+use crate::parser::{
+    common::HtmlImageArea, Fastext, HtmlColors, HtmlLink, HtmlText, ImageFormat, YleImage,
+};
 
 use super::{
     common::{FetchState, GuiContext, IGuiCtx, PageDraw, TelePage, TelePager},
+    crawl::CrawlProgress,
+    print::PrintRange,
+    search::SearchResult,
     svg_icon::{IconName, SvgIcon},
+    watch::PageWatch,
 };
 
 pub struct GuiYleImage<'a> {
@@ -19,7 +26,8 @@ pub struct GuiYleImage<'a> {
 }
 
 impl<'a> GuiYleImage<'a> {
-    fn get_page_str(&self) -> String {
+    /// Page number and the sub-page indicator, e.g. "P190 2/5"
+    fn get_page_str(&self, sub_page: Option<String>) -> String {
         let page_buf = &self.ctx.borrow().page_buffer;
         let page_num = if !page_buf.is_empty() {
             let mut page_str = "---".as_bytes().to_vec();
@@ -31,16 +39,19 @@ impl<'a> GuiYleImage<'a> {
             self.ctx.borrow().current_page.page.to_string()
         };
 
-        format!("P{page_num}")
+        match sub_page {
+            Some(sub_page) => format!("P{page_num} {sub_page}"),
+            None => format!("P{page_num}"),
+        }
     }
 
-    fn draw_header_small(&mut self, title: &HtmlText) {
+    fn draw_header_small(&mut self, title: &HtmlText, sub_page: Option<String>) {
         // align with page navigation
         let chw = self.char_width;
-        let page_len = chw * 4.0;
+        let page = self.get_page_str(sub_page);
+        let page_len = (page.chars().count() as f32) * chw;
         let title_len = (title.chars().count() as f32) * chw;
         let title_space = self.panel_width - title_len - page_len;
-        let page = self.get_page_str();
 
         self.ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
@@ -50,21 +61,30 @@ impl<'a> GuiYleImage<'a> {
         });
     }
 
-    fn draw_header_normal(&mut self, title: &HtmlText) {
+    fn draw_header_normal(
+        &mut self,
+        title: &HtmlText,
+        sub_page: Option<String>,
+        updated: Option<String>,
+    ) {
         // align with page navigation
         let chw = self.char_width;
         let nav_length = chw * 69.0;
         let nav_start = (self.panel_width / 2.0) - (nav_length / 2.0);
-        let page_len = chw * 4.0;
-        let time_len = chw * 15.0;
+        let page = self.get_page_str(sub_page);
+        let page_len = (page.chars().count() as f32) * chw;
+        let now = chrono::Local::now().format("%d.%m. %H:%M:%S");
+        let time = match updated {
+            Some(updated) => format!("{updated}  {now}"),
+            None => now.to_string(),
+        };
+        let time_len = (time.chars().count() as f32) * chw;
         let title = format!("{title} YLE TEKSTI-TV");
         let title_len = (title.chars().count() as f32) * chw;
 
         let title_space = (nav_length / 2.0) - (title_len / 2.0) - page_len;
         let time_space = nav_length - title_space - page_len - title_len - time_len;
 
-        let page = self.get_page_str();
-
         self.ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
             ui.add_space(nav_start);
@@ -72,16 +92,15 @@ impl<'a> GuiYleImage<'a> {
             ui.add_space(title_space);
             ui.label(title.clone());
             ui.add_space(time_space);
-            let now = chrono::Local::now();
-            ui.label(now.format("%d.%m. %H:%M:%S").to_string());
+            ui.label(time);
         });
     }
 
-    fn draw_header(&mut self, title: &HtmlText) {
+    fn draw_header(&mut self, title: &HtmlText, sub_page: Option<String>, updated: Option<String>) {
         if self.is_small {
-            self.draw_header_small(title);
+            self.draw_header_small(title, sub_page);
         } else {
-            self.draw_header_normal(title);
+            self.draw_header_normal(title, sub_page, updated);
         }
     }
 
@@ -89,6 +108,7 @@ impl<'a> GuiYleImage<'a> {
         let mut ctx = self.ctx.borrow_mut();
         let pos = ctx.pointer.hover_pos();
         let clicked = ctx.pointer.primary_released();
+        let middle_clicked = ctx.pointer.button_released(PointerButton::Middle);
         self.ui
             .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                 let image = RetainedImage::from_image_bytes("debug_name", image).unwrap();
@@ -108,7 +128,9 @@ impl<'a> GuiYleImage<'a> {
                             if area.in_area(px, py, scale) {
                                 ui.ctx().output().cursor_icon = CursorIcon::PointingHand;
                                 if clicked {
-                                    ctx.load_page(&area.link, true);
+                                    ctx.follow_link(&area.link);
+                                } else if middle_clicked {
+                                    ctx.open_in_new_tab(&area.link);
                                 }
                                 break;
                             }
@@ -140,9 +162,12 @@ impl<'a> GuiYleImage<'a> {
                 let icon = SvgIcon::from_icon(icon, arrow_width);
                 match item {
                     Some(link) => {
-                        if ui.add(icon.into_link()).clicked() {
-                            ctx.borrow_mut().load_page(&link.url, true);
-                        };
+                        let resp = ui.add(icon.into_link());
+                        if resp.clicked() {
+                            ctx.borrow_mut().follow_link(&link.url);
+                        } else if resp.middle_clicked() {
+                            ctx.borrow_mut().open_in_new_tab(&link.url);
+                        }
                     }
                     None => {
                         ui.add(icon);
@@ -181,11 +206,28 @@ impl<'a> GuiYleImage<'a> {
         self.ui
             .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                 if ui.link("Yle Teksti-TV").clicked() {
-                    ctx.borrow_mut().load_page("100_0001", true);
+                    ctx.borrow_mut().follow_link("100_0001");
                 }
             });
     }
 
+    fn draw_fastext(&mut self, fastext: &Fastext) {
+        if fastext.is_empty() {
+            return;
+        }
+
+        // Reserve two characters for the padding of each button
+        let chars_len = fastext.links().fold(0.0, |acum, (_, link)| {
+            acum + link.inner_text.chars().count() as f32 + 2.0
+        });
+        let fastext_start = (self.panel_width / 2.0) - (self.char_width * chars_len / 2.0);
+        let ctx = &self.ctx;
+        self.ui.horizontal_wrapped(|ui| {
+            ui.add_space(fastext_start.max(0.0));
+            fastext.add_to_ui(ui, ctx.clone());
+        });
+    }
+
     fn draw_page_navigation(&mut self, navigation: &[Option<HtmlLink>]) {
         if self.is_small {
             self.draw_page_navigation_small(navigation);
@@ -200,12 +242,20 @@ impl<'a> PageDraw<'a, YleImage> for GuiYleImage<'a> {
         let ctx = &self.ctx;
         let state = self.ctx.borrow().state.clone();
 
+        let mut error_action = None;
+        let mut version = None;
+        let mut load = false;
         match state.lock().unwrap().deref() {
             FetchState::Complete(page) => {
-                self.draw_header(&page.title);
+                let sub_page = self.ctx.borrow().sub_page_indicator(page);
+                // Changed rows are only highlighted in the text pages
+                let (updated, _) = self.ctx.borrow().update_status();
+                self.draw_header(&page.title, sub_page, updated);
                 self.draw_image(&page.image, &page.image_map);
                 self.draw_page_navigation(&page.botton_navigation);
                 self.draw_home_button();
+                self.draw_fastext(&page.fastext);
+                version = self.ctx.borrow().timeline_ui(self.ui);
             }
             FetchState::Fetching => {
                 self.ui
@@ -213,22 +263,10 @@ impl<'a> PageDraw<'a, YleImage> for GuiYleImage<'a> {
                         ui.label("Loading...");
                     });
             }
-            FetchState::Error => {
-                self.ui
-                    .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-                        ui.label("Load failed...");
-                        if ui.link("Return to previous page").clicked() {
-                            ctx.borrow_mut().return_from_error_page();
-                        }
-                    });
-            }
-            FetchState::InitFailed => {
+            FetchState::Error(failure) | FetchState::InitFailed(failure) => {
                 self.ui
                     .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-                        ui.label("Load failed...");
-                        if ui.link("Try again").clicked() {
-                            ctx.borrow_mut().load_current_page();
-                        }
+                        error_action = failure.add_to_ui(ui, ctx.clone());
                     });
             }
             FetchState::Init => {
@@ -236,9 +274,28 @@ impl<'a> PageDraw<'a, YleImage> for GuiYleImage<'a> {
                     .with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                         ui.label("Opening...");
                     });
-                ctx.borrow_mut().load_current_page();
+                load = true;
             }
         };
+
+        // Loading changes the state so it's done after the page isn't locked anymore
+        let mut ctx = self.ctx.borrow_mut();
+        if load {
+            ctx.load_current_page();
+        }
+
+        if let Some(link) = ctx.clicked_link.take() {
+            ctx.load_page(&link, true);
+        }
+
+        if let Some(action) = error_action {
+            ctx.error_action(action);
+        }
+
+        // Page is locked while it's drawn so the version is shown afterwards
+        if let Some(idx) = version {
+            ctx.show_version(idx);
+        }
     }
 
     fn new(ui: &'a mut egui::Ui, ctx: &'a mut GuiContext<YleImage>) -> Self {
@@ -274,8 +331,11 @@ impl IGuiCtx for GuiYleImageContext {
         self.ctx.handle_input(input)
     }
 
+    fn update(&mut self) {
+        self.ctx.update()
+    }
+
     fn draw(&mut self, ui: &mut egui::Ui) {
-        self.ctx.draw(ui);
         GuiYleImage::new(ui, &mut self.ctx).draw();
     }
 
@@ -287,6 +347,75 @@ impl IGuiCtx for GuiYleImageContext {
         self.ctx.stop_refresh_interval()
     }
 
+    fn set_carousel_interval(&mut self, interval: u64) {
+        self.ctx.set_carousel_interval(interval)
+    }
+
+    fn stop_carousel(&mut self) {
+        self.ctx.stop_carousel()
+    }
+
+    fn set_prefetch(&mut self, enabled: bool) {
+        self.ctx.prefetch = enabled;
+    }
+
+    fn watch_current_page(&self, interval: u64, keyword: Option<String>) -> PageWatch {
+        self.ctx
+            .watch_page(self.ctx.current_page, interval, keyword)
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        self.ctx.store.search(query)
+    }
+
+    fn open_search_result(&mut self, result: &SearchResult) {
+        self.ctx.open_search_result(result)
+    }
+
+    fn crawl_progress(&self) -> CrawlProgress {
+        self.ctx.crawl.progress.lock().unwrap().clone()
+    }
+
+    fn resume_crawl(&mut self, progress: CrawlProgress) {
+        self.ctx.resume_crawl(progress)
+    }
+
+    fn start_crawl(&mut self) {
+        self.ctx.start_crawl()
+    }
+
+    fn stop_crawl(&mut self) {
+        self.ctx.stop_crawl()
+    }
+
+    fn restart_crawl(&mut self) {
+        self.ctx.restart_crawl()
+    }
+
+    fn start_print(&mut self, range: PrintRange, path: String) {
+        self.ctx.start_print(range, path)
+    }
+
+    fn stop_print(&mut self) {
+        self.ctx.stop_print()
+    }
+
+    fn print_progress(&self) -> Option<(bool, String)> {
+        self.ctx.print_progress()
+    }
+
+    fn take_new_tab(&mut self) -> Option<TelePage> {
+        self.ctx.new_tab.take()
+    }
+
+    fn current_page(&self) -> TelePage {
+        self.ctx.current_page
+    }
+
+    fn open_page(&mut self, page: TelePage) {
+        self.ctx.open_page(page)
+    }
+
     fn return_from_error_page(&mut self) {
         self.ctx.return_from_error_page()
     }
@@ -298,9 +427,28 @@ impl IGuiCtx for GuiYleImageContext {
     fn load_page(&mut self, page: &str, add_to_history: bool) {
         self.ctx.load_page(page, add_to_history)
     }
+
+    fn export_image(
+        &self,
+        format: ImageFormat,
+        _scale: f32,
+        header: bool,
+        colors: &HtmlColors,
+    ) -> Option<ImageResult<Vec<u8>>> {
+        let current = &self.ctx.current_page;
+        match self.ctx.state.lock().unwrap().deref() {
+            FetchState::Complete(page) => {
+                let header = header.then_some(*colors);
+                Some(page.export_image(format, current.page, current.sub_page, header))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl TelePager for YleImage {
+    const SOURCE: &'static str = "yle_image";
+
     #[cfg(not(target_arch = "wasm32"))]
     fn to_full_page(page: &TelePage) -> String {
         // https://yle.fi/aihe/yle-ttv/json?P=100_0001
@@ -327,10 +475,52 @@ impl TelePager for YleImage {
         format!("{}_{:04}", page.page, page.sub_page)
     }
 
-    fn from_page_str(page: &str) -> TelePage {
-        let current_page = page[0..3].parse::<i32>().unwrap();
-        let sub_page = page[4..8].parse::<i32>().unwrap();
+    fn from_page_str(page: &str) -> Option<TelePage> {
+        let current_page = page.get(0..3)?.parse::<i32>().ok()?;
+        let sub_page = page.get(4..8)?.parse::<i32>().ok()?;
+
+        Some(TelePage::new(current_page, sub_page))
+    }
+
+    fn fastext(&self) -> &Fastext<'_> {
+        &self.fastext
+    }
+
+    fn prefetch_pages(&self) -> Vec<TelePage> {
+        self.botton_navigation
+            .iter()
+            .flatten()
+            .filter_map(|link| Self::from_page_str(&link.url))
+            .collect()
+    }
+
+    fn to_json(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
 
-        TelePage::new(current_page, sub_page)
+    fn search_text(&self) -> Option<(String, Vec<String>)> {
+        if self.text.is_empty() {
+            return None;
+        }
+
+        let lines = self.text.lines().map(str::to_string).collect();
+        Some((self.title.to_string(), lines))
+    }
+
+    /// Image pages don't tell the number of sub-pages so the next sub-page
+    /// is taken from the navigation
+    fn next_sub_page(&self, current: &TelePage) -> Option<TelePage> {
+        let next = self
+            .botton_navigation
+            .get(2)?
+            .as_ref()
+            .and_then(|link| Self::from_page_str(&link.url))
+            .filter(|next| next.page == current.page && next.sub_page > current.sub_page);
+
+        match next {
+            Some(next) => Some(next),
+            None if current.sub_page > 1 => Some(TelePage::new(current.page, 1)),
+            None => None,
+        }
     }
 }
@@ -0,0 +1,6 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+mod gui;
+pub mod parser;
+
+pub use gui::{TelePage, TelePager, TeleTextApp};
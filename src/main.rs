@@ -1,18 +1,20 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-mod gui;
-mod parser;
-
 // Native main
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
     tracing_subscriber::fmt::init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = yle_tekstitv_gui::cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "Teletext Reader",
         options,
-        Box::new(|cc| Box::new(gui::TeleTextApp::new(cc))),
+        Box::new(|cc| Box::new(yle_tekstitv_gui::TeleTextApp::new(cc))),
     )
     .unwrap();
 }
@@ -31,7 +33,7 @@ fn main() {
         eframe::start_web(
             "the_canvas_id", // hardcode it
             web_options,
-            Box::new(|cc| Box::new(gui::TeleTextApp::new(cc))),
+            Box::new(|cc| Box::new(yle_tekstitv_gui::TeleTextApp::new(cc))),
         )
         .await
         .expect("failed to start eframe");
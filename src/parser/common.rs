@@ -1,11 +1,35 @@
+use std::borrow::Cow;
 use std::fs;
 use std::result::Result;
+use std::sync::Arc;
+
+use super::html::{Tag, Token, Tokenizer};
+use super::provider::ProviderPage;
 
 extern crate html_escape;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParseErr {
+    /// Page is not in the expected format
     InvalidPage,
+    /// Provider responded that the page doesn't exist
+    NotFound,
+    /// Provider responded with a maintenance or service break notice
+    Maintenance,
+}
+
+/// Recognise the error page of a provider from its texts, compared case insensitively.
+/// Pages that are not recognised are `ParseErr::InvalidPage`
+pub fn error_page(page: &str, not_found: &[&str], maintenance: &[&str]) -> ParseErr {
+    let page = page.to_lowercase();
+    let contains_any = |texts: &[&str]| texts.iter().any(|text| page.contains(text));
+    if contains_any(not_found) {
+        ParseErr::NotFound
+    } else if contains_any(maintenance) {
+        ParseErr::Maintenance
+    } else {
+        ParseErr::InvalidPage
+    }
 }
 
 pub struct ParseState<'a> {
@@ -18,17 +42,16 @@ impl<'a> ParseState<'a> {
     }
 }
 
-pub type InnerResult<'a, T> = Result<(&'a mut ParseState<'a>, T), ParseErr>;
+/// Parse state is borrowed for `'s` while the parsed values borrow the html for `'a`
+pub type InnerResult<'s, 'a, T> = Result<(&'s mut ParseState<'a>, T), ParseErr>;
 pub type ParserResult<T> = Result<T, ParseErr>;
 
-// TODO: is this fast enough or do we need to build our own?
-pub fn decode_string(string: &str) -> String {
-    let mut new_string = String::new();
-    html_escape::decode_html_entities_to_string(string, &mut new_string);
-    new_string
+/// Decode html entities. Only allocates when the string contains entities
+pub fn decode_string(string: &str) -> HtmlText<'_> {
+    html_escape::decode_html_entities(string)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TagType {
     Unknown,
     P,
@@ -36,28 +59,145 @@ pub enum TagType {
     Div,
     Pre,
     Link,
+    Area,
     Font,
     Span,
     Center,
 }
 
-// TODO: use this to avoid heap allocs
-// pub type HtmlText<'a> = &'a str;
-pub type HtmlText = String;
+const TAG_TYPES: [(&str, TagType); 9] = [
+    ("p", TagType::P),
+    ("big", TagType::Big),
+    ("div", TagType::Div),
+    ("pre", TagType::Pre),
+    ("a", TagType::Link),
+    ("area", TagType::Area),
+    ("font", TagType::Font),
+    ("span", TagType::Span),
+    ("center", TagType::Center),
+];
+
+/// Text borrowed from the html document, owned only if it had to be decoded
+pub type HtmlText<'a> = Cow<'a, str>;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HtmlLink<'a> {
+    pub url: HtmlText<'a>,
+    pub inner_text: HtmlText<'a>,
+}
 
-#[derive(Debug)]
-pub struct HtmlLink {
-    pub url: HtmlText,
-    pub inner_text: HtmlText,
+impl<'a> HtmlLink<'a> {
+    pub fn into_owned(self) -> HtmlLink<'static> {
+        HtmlLink {
+            url: Cow::Owned(self.url.into_owned()),
+            inner_text: Cow::Owned(self.inner_text.into_owned()),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub enum HtmlItem {
-    Text(HtmlText),
-    Link(HtmlLink),
+#[derive(Debug, Clone, serde::Serialize)]
+pub enum HtmlItem<'a> {
+    Text(HtmlText<'a>),
+    Link(HtmlLink<'a>),
 }
 
-#[derive(Debug)]
+impl<'a> HtmlItem<'a> {
+    pub fn into_owned(self) -> HtmlItem<'static> {
+        match self {
+            Self::Text(text) => HtmlItem::Text(Cow::Owned(text.into_owned())),
+            Self::Link(link) => HtmlItem::Link(link.into_owned()),
+        }
+    }
+
+    /// Text shown for the item
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Text(text) => text,
+            Self::Link(link) => &link.inner_text,
+        }
+    }
+}
+
+/// Link to a teletext page in the same format as in Yle text pages, e.g. "101_0001.htm".
+/// All the text readers understand this format so the links work in every reader
+pub fn page_link(page: i32, inner_text: HtmlText) -> HtmlLink {
+    HtmlLink {
+        url: format!("{page}_0001.htm").into(),
+        inner_text,
+    }
+}
+
+/// Get the page number from a link created with `page_link`
+pub fn link_page(link: &HtmlLink) -> Option<i32> {
+    let page = link.url.get(0..3)?.parse::<i32>().ok()?;
+    if (100..=899).contains(&page) {
+        Some(page)
+    } else {
+        None
+    }
+}
+
+/// Split the row into texts and links. Every three digit number between
+/// 100 and 899 is considered to be a link to the page
+pub fn text_to_items(row: &str) -> Vec<HtmlItem<'_>> {
+    let bytes = row.as_bytes();
+    let mut items: Vec<HtmlItem> = Vec::new();
+    let mut text_start = 0;
+    let mut idx = 0;
+
+    // Digits are ascii so the indices are always at char boundaries
+    while idx < bytes.len() {
+        let is_number = idx + 3 <= bytes.len()
+            && bytes[idx..idx + 3].iter().all(|c| c.is_ascii_digit())
+            && (idx == 0 || !bytes[idx - 1].is_ascii_digit())
+            && !matches!(bytes.get(idx + 3), Some(c) if c.is_ascii_digit());
+
+        if is_number {
+            let number = &row[idx..idx + 3];
+            let page = number.parse::<i32>().unwrap_or(0);
+            if (100..=899).contains(&page) {
+                if text_start < idx {
+                    items.push(HtmlItem::Text(row[text_start..idx].into()));
+                }
+                items.push(HtmlItem::Link(page_link(page, number.into())));
+                idx += 3;
+                text_start = idx;
+                continue;
+            }
+        }
+
+        idx += 1;
+    }
+
+    if text_start < row.len() {
+        items.push(HtmlItem::Text(row[text_start..].into()));
+    }
+
+    items
+}
+
+/// Page navigation in the same format as in Yle text pages. `labels` are the texts of
+/// previous page, previous sub page, next sub page and next page.
+/// Sub page navigation is always shown as a text
+pub fn page_navigation(
+    labels: [&'static str; 4],
+    prev: Option<i32>,
+    next: Option<i32>,
+) -> Vec<HtmlItem<'static>> {
+    let link_or_text = |page: Option<i32>, label: &'static str| match page {
+        Some(page) if (100..=899).contains(&page) => HtmlItem::Link(page_link(page, label.into())),
+        _ => HtmlItem::Text(label.into()),
+    };
+
+    vec![
+        link_or_text(prev, labels[0]),
+        HtmlItem::Text(labels[1].into()),
+        HtmlItem::Text(labels[2].into()),
+        link_or_text(next, labels[3]),
+    ]
+}
+
+#[derive(Debug, serde::Serialize)]
 pub struct HtmlImageArea {
     pub x1: f32,
     pub y1: f32,
@@ -91,43 +231,93 @@ impl HtmlImageArea {
 }
 
 pub trait HtmlParser {
-    /// Get tag type in the current position
+    /// Get the type of the tag that starts at the current position.
+    /// Text and closing tags are `TagType::Unknown`
     fn get_tag_type(current: &str) -> TagType
     where
         Self: Sized,
     {
-        let mut html = current;
+        match Self::peek_token(current) {
+            Some(Token::Start(tag)) => TAG_TYPES
+                .iter()
+                .find(|(name, _)| tag.is(name))
+                .map(|(_, tag_type)| *tag_type)
+                .unwrap_or(TagType::Unknown),
+            _ => TagType::Unknown,
+        }
+    }
 
-        if html.starts_with('<') {
-            html = &html[1..];
+    /// Get the token at the current position without moving forward
+    fn peek_token(current: &str) -> Option<Token<'_>>
+    where
+        Self: Sized,
+    {
+        Tokenizer::new(current).next()
+    }
+
+    /// Move to the next token. Contents of `<script>` and `<style>` are skipped
+    /// since they are not html
+    fn next_token<'s, 'a>(state: &'s mut ParseState<'a>) -> InnerResult<'s, 'a, Token<'a>>
+    where
+        Self: Sized,
+    {
+        let mut tokens = Tokenizer::new(state.current);
+        let token = tokens.next().ok_or(ParseErr::InvalidPage)?;
+        if tokens.in_raw_text() {
+            tokens.next();
         }
 
-        if html.starts_with('p') {
-            return TagType::P;
-        } else if html.starts_with('a') {
-            return TagType::Link;
-        } else if html.starts_with("big") {
-            return TagType::Big;
-        } else if html.starts_with("div") {
-            return TagType::Div;
-        } else if html.starts_with("pre") {
-            return TagType::Pre;
-        } else if html.starts_with("font") {
-            return TagType::Font;
-        } else if html.starts_with("span") {
-            return TagType::Span;
-        } else if html.starts_with("center") {
-            return TagType::Center;
+        state.current = tokens.rest();
+        Ok((state, token))
+    }
+
+    /// Skip to the end of the next start tag with the name, e.g. `<a href="">`
+    fn next_start_tag<'s, 'a>(
+        mut state: &'s mut ParseState<'a>,
+        name: &str,
+    ) -> InnerResult<'s, 'a, Tag<'a>>
+    where
+        Self: Sized,
+    {
+        loop {
+            let (new_state, token) = Self::next_token(state)?;
+            state = new_state;
+            if let Token::Start(tag) = token {
+                if tag.is(name) {
+                    return Ok((state, tag));
+                }
+            }
         }
+    }
 
-        TagType::Unknown
+    /// Text inside the current tag until the closing tag with the name.
+    /// Tags inside the text are ignored
+    fn parse_inner_text<'s, 'a>(
+        mut state: &'s mut ParseState<'a>,
+        name: &str,
+    ) -> InnerResult<'s, 'a, HtmlText<'a>>
+    where
+        Self: Sized,
+    {
+        // Text is only allocated if it has entities or is split by other tags
+        let mut text = HtmlText::Borrowed("");
+        loop {
+            let (new_state, token) = Self::next_token(state)?;
+            state = new_state;
+            match token {
+                Token::Text(inner) if text.is_empty() => text = decode_string(inner),
+                Token::Text(inner) => text.to_mut().push_str(&decode_string(inner)),
+                token if token.is_end(name) => return Ok((state, text)),
+                _ => {}
+            }
+        }
     }
 
     // TODO: combine `skip_next_string` and `skip_next_char` to skip_next_pattern
     //       when the pattern is stable enough to use
     /*  fn skip_next_pattern<P: std::str::pattern::Pattern>(state, pattern: P) {} */
 
-    fn skip_next_char<'a>(state: &'a mut ParseState<'a>, chr: char) -> InnerResult<'a, ()>
+    fn skip_next_char<'s, 'a>(state: &'s mut ParseState<'a>, chr: char) -> InnerResult<'s, 'a, ()>
     where
         Self: Sized,
     {
@@ -137,7 +327,10 @@ pub trait HtmlParser {
         Ok((state, ()))
     }
 
-    fn skip_to_next_char<'a>(state: &'a mut ParseState<'a>, chr: char) -> InnerResult<'a, ()>
+    fn skip_to_next_char<'s, 'a>(
+        state: &'s mut ParseState<'a>,
+        chr: char,
+    ) -> InnerResult<'s, 'a, ()>
     where
         Self: Sized,
     {
@@ -146,7 +339,10 @@ pub trait HtmlParser {
         Ok((state, ()))
     }
 
-    fn skip_next_string<'a>(state: &'a mut ParseState<'a>, string: &str) -> InnerResult<'a, ()>
+    fn skip_next_string<'s, 'a>(
+        state: &'s mut ParseState<'a>,
+        string: &str,
+    ) -> InnerResult<'s, 'a, ()>
     where
         Self: Sized,
     {
@@ -156,43 +352,39 @@ pub trait HtmlParser {
         Ok((state, ()))
     }
 
-    fn skip_next_tag<'a>(
-        mut state: &'a mut ParseState<'a>,
+    /// Skip to the end of the next start or closing tag. Only the whole tag name
+    /// matches so `<pre>` is not a `p` and `<area>` is not an `a`
+    fn skip_next_tag<'s, 'a>(
+        mut state: &'s mut ParseState<'a>,
         tag: &str,
         closing: bool,
-    ) -> InnerResult<'a, ()>
+    ) -> InnerResult<'s, 'a, ()>
     where
         Self: Sized,
     {
-        // TODO: Do we need to heap allocate a new string here?
-        let html_tag = if closing {
-            format!("</{tag}")
-        } else {
-            format!("<{tag}")
-        };
-
-        // First skip the start of the tag definition
-        state = Self::skip_next_string(state, &html_tag)?.0;
-        // Then skip all the way to the end, skipping class="" etc
-        state = Self::skip_next_char(state, '>')?.0;
-        Ok((state, ()))
+        if !closing {
+            return Ok((Self::next_start_tag(state, tag)?.0, ()));
+        }
+
+        loop {
+            let (new_state, token) = Self::next_token(state)?;
+            state = new_state;
+            if token.is_end(tag) {
+                return Ok((state, ()));
+            }
+        }
     }
 
-    fn parse_current_link<'a>(mut state: &'a mut ParseState<'a>) -> InnerResult<'a, HtmlLink>
+    /// Parse the next link, texts and tags before it are skipped
+    fn parse_current_link<'s, 'a>(
+        state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, HtmlLink<'a>>
     where
         Self: Sized,
     {
-        state = Self::skip_next_string(state, "href=\"")?.0;
-        let url_end = state.current.find('"').ok_or(ParseErr::InvalidPage)?;
-        let url = state.current[..url_end].to_string();
-
-        // Go to the end of the link tag
-        state = Self::skip_next_string(state, ">")?.0;
-
-        let inner_end = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-        let inner_text = decode_string(&state.current[..inner_end]);
-
-        state = Self::skip_next_tag(state, "a", true)?.0;
+        let (state, tag) = Self::next_start_tag(state, "a")?;
+        let url = decode_string(tag.attribute("href").ok_or(ParseErr::InvalidPage)?);
+        let (state, inner_text) = Self::parse_inner_text(state, "a")?;
 
         Ok((state, HtmlLink { url, inner_text }))
     }
@@ -200,6 +392,14 @@ pub trait HtmlParser {
     fn parse(self, loader: HtmlLoader) -> ParserResult<Self>
     where
         Self: Sized;
+    /// Parse a page of a provider defined in the providers file.
+    /// Pages that cannot be defined in the file ignore the provider
+    fn parse_with_provider(self, loader: HtmlLoader, _page: &ProviderPage) -> ParserResult<Self>
+    where
+        Self: Sized,
+    {
+        self.parse(loader)
+    }
     fn new() -> Self
     where
         Self: Sized;
@@ -207,7 +407,8 @@ pub trait HtmlParser {
 
 #[derive(Debug)]
 pub struct HtmlLoader {
-    pub page_data: String,
+    /// Shared so the parsed pages can keep borrowing it, see `YleText`
+    pub page_data: Arc<str>,
 }
 
 impl HtmlLoader {
@@ -215,6 +416,8 @@ impl HtmlLoader {
     pub fn new(file: &str) -> HtmlLoader {
         let data = fs::read_to_string(file).unwrap_or_else(|_| panic!("Can't find \"{}\"", file));
 
-        HtmlLoader { page_data: data }
+        HtmlLoader {
+            page_data: data.into(),
+        }
     }
 }
@@ -0,0 +1,154 @@
+use super::common::{
+    decode_string, page_link, page_navigation, HtmlItem, HtmlLoader, HtmlParser, HtmlText,
+    InnerResult, ParseErr, ParseState, ParserResult, TagType,
+};
+use super::fastext::Fastext;
+use super::yle_text::{TeleText, TextPage};
+
+const DR_NAVIGATION_LABELS: [&str; 4] = [
+    "Forrige side",
+    "Forrige underside",
+    "Næste underside",
+    "Næste side",
+];
+
+/// Main sections of DR Tekst-TV, the last one is the index page
+const DR_SECTIONS: [(i32, &str); 6] = [
+    (101, "Nyheder"),
+    (200, "Sport"),
+    (300, "TV"),
+    (400, "Vejret"),
+    (500, "Kultur"),
+    (100, "Tekst-TV"),
+];
+
+/// Contains the fields of DR Tekst-TV site
+#[derive(Debug)]
+pub struct DrText {
+    pub teletext: TeleText<'static>,
+}
+
+impl DrText {
+    /// Links are in format "/cgi-bin/fttv1.exe/101", the page is the last part of the url
+    fn link_to_item<'a>(url: &str, inner_text: HtmlText<'a>) -> HtmlItem<'a> {
+        let page = url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .and_then(|page| page.parse::<i32>().ok())
+            .filter(|page| (100..=899).contains(page));
+
+        match page {
+            Some(page) => HtmlItem::Link(page_link(page, inner_text)),
+            None => HtmlItem::Text(inner_text),
+        }
+    }
+
+    /// Parse the rows of the `<pre>` block. Rows contain texts, links and
+    /// colour spans. Colours are ignored, like in Yle pages
+    fn parse_rows<'s, 'a>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<Vec<HtmlItem<'a>>>> {
+        state = Self::skip_next_tag(state, "pre", false)?.0;
+        // Content starts from the next line
+        if state.current.starts_with("\r\n") {
+            state.current = &state.current[2..];
+        } else if state.current.starts_with('\n') {
+            state.current = &state.current[1..];
+        }
+
+        let mut rows: Vec<Vec<HtmlItem>> = Vec::new();
+        while !state.current.starts_with("</pre>") {
+            let mut row: Vec<HtmlItem> = Vec::new();
+            let parse_text = state.current;
+            let line_len = state.current.find('\n').ok_or(ParseErr::InvalidPage)?;
+            state.current = state.current[..line_len].trim_end_matches('\r');
+
+            while !state.current.is_empty() {
+                match Self::get_tag_type(state.current) {
+                    TagType::Link => {
+                        let (new_state, link) = Self::parse_current_link(state)?;
+                        state = new_state;
+                        row.push(Self::link_to_item(&link.url, link.inner_text));
+                    }
+                    _ if state.current.starts_with('<') => {
+                        // Skip the colour spans and other formatting
+                        state = Self::skip_next_char(state, '>')?.0;
+                    }
+                    _ => {
+                        let text_end = state.current.find('<').unwrap_or(state.current.len());
+                        row.push(HtmlItem::Text(decode_string(&state.current[..text_end])));
+                        state.current = &state.current[text_end..];
+                    }
+                }
+            }
+
+            rows.push(row);
+            state.current = &parse_text[line_len + 1..];
+        }
+
+        Ok((state, rows))
+    }
+
+    /// First row is the header, e.g. " 100 DR TEKST-TV  tir 17 jan 12:34".
+    /// Returns the page number and the title without the number
+    fn parse_header(header: &[HtmlItem]) -> ParserResult<(i32, HtmlText<'static>)> {
+        let text: String = header.iter().map(HtmlItem::text).collect();
+
+        let text = text.trim();
+        let page = text
+            .get(0..3)
+            .and_then(|page| page.parse::<i32>().ok())
+            .ok_or(ParseErr::InvalidPage)?;
+
+        Ok((page, text[3..].trim().to_string().into()))
+    }
+
+    /// Parse the page without copying the texts from the html
+    pub fn parse_str(html: &str) -> ParserResult<TeleText<'_>> {
+        let mut state = ParseState::new(html);
+        let mut rows = Self::parse_rows(&mut state)?.1;
+        if rows.is_empty() {
+            return Err(ParseErr::InvalidPage);
+        }
+
+        let header = rows.remove(0);
+        let (page, title) = Self::parse_header(&header)?;
+        let mut teletext = TeleText {
+            title,
+            page_navigation: page_navigation(DR_NAVIGATION_LABELS, Some(page - 1), Some(page + 1)),
+            middle_rows: rows,
+            bottom_navigation: DR_SECTIONS
+                .iter()
+                .map(|(page, name)| page_link(*page, (*name).into()))
+                .collect(),
+            ..Default::default()
+        };
+        teletext.fastext = Fastext::from_links(&teletext.bottom_navigation);
+
+        Ok(teletext)
+    }
+}
+
+impl TextPage for DrText {
+    fn teletext(&self) -> &TeleText<'_> {
+        &self.teletext
+    }
+
+    fn from_teletext(teletext: TeleText<'static>) -> Self {
+        Self { teletext }
+    }
+}
+
+impl HtmlParser for DrText {
+    fn new() -> Self {
+        Self {
+            teletext: TeleText::default(),
+        }
+    }
+
+    fn parse(mut self, loader: HtmlLoader) -> ParserResult<Self> {
+        self.teletext = Self::parse_str(&loader.page_data)?.into_owned();
+        Ok(self)
+    }
+}
@@ -0,0 +1,337 @@
+use super::common::{link_page, HtmlItem, HtmlLink};
+use super::fastext::FastextKey;
+use super::yle_text::TeleText;
+
+extern crate html_escape;
+
+/// Teletext pages are 40 characters wide
+pub const EXPORT_COLUMNS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// 40 column text without links
+    Text,
+    /// Links are written as `[text](page)`
+    Markdown,
+    /// Standalone html document with the layout and colours of the reader
+    Html,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [Self::Text, Self::Markdown, Self::Html];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Text => "Plain text",
+            Self::Markdown => "Markdown",
+            Self::Html => "HTML",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+
+    /// Format from the name or the extension, e.g. "markdown" or "md"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "text" | "txt" => Some(Self::Text),
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Colours of the html export as rgb, the defaults are the colours of the reader
+#[derive(Clone, Copy)]
+pub struct HtmlColors {
+    pub text: [u8; 3],
+    pub link: [u8; 3],
+    pub background: [u8; 3],
+}
+
+impl Default for HtmlColors {
+    fn default() -> Self {
+        Self {
+            text: [255, 255, 255],
+            link: [17, 159, 244],
+            background: [0, 0, 0],
+        }
+    }
+}
+
+pub(super) fn hex_color(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Header of the page, e.g. "P190/2"
+pub(super) fn page_label(page: i32, sub_page: i32) -> String {
+    if sub_page > 1 {
+        format!("P{page}/{sub_page}")
+    } else {
+        format!("P{page}")
+    }
+}
+
+/// Join the texts with " | " into lines of at most `EXPORT_COLUMNS` characters
+fn wrap_texts(texts: &[&str]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for text in texts.iter().map(|text| text.trim()) {
+        if text.is_empty() {
+            continue;
+        }
+
+        if !line.is_empty() && line.chars().count() + text.chars().count() + 3 > EXPORT_COLUMNS {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push_str(" | ");
+        }
+        line.push_str(text);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Escape the characters that markdown would read as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`' | '#' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl TeleText<'_> {
+    /// Colours are only used in html
+    pub fn export(
+        &self,
+        format: ExportFormat,
+        page: i32,
+        sub_page: i32,
+        colors: &HtmlColors,
+    ) -> String {
+        match format {
+            ExportFormat::Text => self.to_text(page, sub_page),
+            ExportFormat::Markdown => self.to_markdown(page, sub_page),
+            ExportFormat::Html => self.to_html(page, sub_page, colors),
+        }
+    }
+
+    pub(super) fn fastext_links(&self) -> Vec<(FastextKey, &HtmlLink<'_>)> {
+        FastextKey::ALL
+            .iter()
+            .filter_map(|key| self.fastext.get(*key).map(|link| (*key, link)))
+            .collect()
+    }
+
+    /// Page in 40 columns like on a TV. Longer rows are cut and the
+    /// navigation is wrapped on multiple lines
+    pub fn to_text(&self, page: i32, sub_page: i32) -> String {
+        let cut = |text: &str| -> String {
+            let text: String = text.chars().take(EXPORT_COLUMNS).collect();
+            text.trim_end().to_string()
+        };
+        let row_text = |row: &[HtmlItem]| cut(&row.iter().map(HtmlItem::text).collect::<String>());
+
+        let label = page_label(page, sub_page);
+        let title = self.title.trim();
+        // Title is centered on the line like in the reader
+        let padding = EXPORT_COLUMNS.saturating_sub(title.chars().count()) / 2;
+        let padding = padding.saturating_sub(label.chars().count()).max(1);
+        let mut lines = vec![cut(&format!("{label}{}{title}", " ".repeat(padding)))];
+
+        let navigation: Vec<&str> = self.page_navigation.iter().map(HtmlItem::text).collect();
+        lines.extend(wrap_texts(&navigation));
+        lines.extend(self.middle_rows.iter().map(|row| row_text(row)));
+        if !self.sub_pages.is_empty() {
+            lines.push(row_text(&self.sub_pages));
+        }
+
+        let fastext: Vec<&str> = self
+            .fastext_links()
+            .iter()
+            .map(|(_, link)| link.inner_text.as_ref())
+            .collect();
+        lines.extend(wrap_texts(&fastext));
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// Middle rows and sub-pages for the clipboard. With the header, the page and
+    /// the title are on the first line, e.g. "P190/2  JÄÄKIEKKO"
+    pub fn clipboard_text(&self, page: i32, sub_page: i32, header: bool) -> String {
+        let row_text = |row: &[HtmlItem]| -> String {
+            let text: String = row.iter().map(HtmlItem::text).collect();
+            text.trim_end().to_string()
+        };
+
+        let mut lines = Vec::new();
+        if header {
+            let label = page_label(page, sub_page);
+            lines.push(format!("{label}  {}", self.title.trim()));
+            lines.push(String::new());
+        }
+        lines.extend(self.middle_rows.iter().map(|row| row_text(row)));
+        if !self.sub_pages.is_empty() {
+            lines.push(row_text(&self.sub_pages));
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// Rows are separated with hard line breaks so the rows stay on their own lines
+    pub fn to_markdown(&self, page: i32, sub_page: i32) -> String {
+        let link_text = |link: &HtmlLink| match link_page(link) {
+            Some(page) => format!("[{}]({page})", escape_markdown(link.inner_text.trim())),
+            None => escape_markdown(&link.inner_text),
+        };
+        let row_text = |row: &[HtmlItem]| -> String {
+            let text: String = row
+                .iter()
+                .map(|item| match item {
+                    HtmlItem::Text(text) => escape_markdown(text),
+                    HtmlItem::Link(link) => link_text(link),
+                })
+                .collect();
+            text.trim_end().to_string()
+        };
+        let links_text = |links: Vec<&HtmlLink>| -> String {
+            links
+                .into_iter()
+                .map(&link_text)
+                .collect::<Vec<String>>()
+                .join(" | ")
+        };
+
+        let label = page_label(page, sub_page);
+        let mut text = format!("# {label} {}\n\n", escape_markdown(self.title.trim()));
+
+        let navigation: Vec<&HtmlLink> = self
+            .page_navigation
+            .iter()
+            .filter_map(|item| match item {
+                HtmlItem::Link(link) => Some(link),
+                HtmlItem::Text(_) => None,
+            })
+            .collect();
+        if !navigation.is_empty() {
+            text.push_str(&links_text(navigation));
+            text.push_str("\n\n");
+        }
+
+        let rows: Vec<String> = self.middle_rows.iter().map(|row| row_text(row)).collect();
+        text.push_str(&rows.join("  \n"));
+        text.push_str("\n\n");
+
+        if !self.sub_pages.is_empty() {
+            text.push_str(&row_text(&self.sub_pages));
+            text.push_str("\n\n");
+        }
+
+        let fastext: Vec<&HtmlLink> = self.fastext_links().into_iter().map(|(_, l)| l).collect();
+        if !fastext.is_empty() {
+            text.push_str(&links_text(fastext));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Html document that shows the page like the reader. Links keep the urls of the page,
+    /// e.g. "101_0001.htm", so exported pages link to each other when they're in the same folder
+    pub fn to_html(&self, page: i32, sub_page: i32, colors: &HtmlColors) -> String {
+        let link_html = |link: &HtmlLink, class: Option<&str>| {
+            let class = class
+                .map(|class| format!(" class=\"{class}\""))
+                .unwrap_or_default();
+            format!(
+                "<a href=\"{}\"{class}>{}</a>",
+                html_escape::encode_double_quoted_attribute(&link.url),
+                html_escape::encode_text(&link.inner_text)
+            )
+        };
+        let row_html = |row: &[HtmlItem]| -> String {
+            row.iter()
+                .map(|item| match item {
+                    HtmlItem::Text(text) => html_escape::encode_text(text).to_string(),
+                    HtmlItem::Link(link) => link_html(link, None),
+                })
+                .collect()
+        };
+
+        let label = page_label(page, sub_page);
+        let title = html_escape::encode_text(self.title.trim());
+        let mut lines = vec![format!("<span class=\"header\">{label}  {title}</span>")];
+        lines.push(row_html(&self.page_navigation));
+        lines.extend(self.middle_rows.iter().map(|row| row_html(row)));
+        if !self.sub_pages.is_empty() {
+            lines.push(row_html(&self.sub_pages));
+        }
+
+        let fastext: Vec<String> = self
+            .fastext_links()
+            .iter()
+            .map(|(key, link)| match key {
+                FastextKey::Red => link_html(link, Some("red")),
+                FastextKey::Green => link_html(link, Some("green")),
+                FastextKey::Yellow => link_html(link, Some("yellow")),
+                FastextKey::Cyan => link_html(link, Some("cyan")),
+                FastextKey::Index => link_html(link, None),
+            })
+            .collect();
+        lines.push(fastext.join("  "));
+
+        let key_color = |key: FastextKey| hex_color(key.rgb().unwrap_or(colors.link));
+        format!(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{label} {title}</title>
+<style>
+body {{ background: {background}; color: {text}; }}
+pre {{ font-family: \"DejaVu Sans Mono\", monospace; width: {columns}ch; margin: 2em auto; }}
+a {{ color: {link}; text-decoration: none; }}
+.header {{ font-weight: bold; }}
+.red {{ color: {red}; }}
+.green {{ color: {green}; }}
+.yellow {{ color: {yellow}; }}
+.cyan {{ color: {cyan}; }}
+</style>
+</head>
+<body>
+<pre>
+{body}
+</pre>
+</body>
+</html>
+",
+            background = hex_color(colors.background),
+            text = hex_color(colors.text),
+            link = hex_color(colors.link),
+            columns = EXPORT_COLUMNS,
+            red = key_color(FastextKey::Red),
+            green = key_color(FastextKey::Green),
+            yellow = key_color(FastextKey::Yellow),
+            cyan = key_color(FastextKey::Cyan),
+            body = lines.join("\n"),
+        )
+    }
+}
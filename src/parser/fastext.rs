@@ -0,0 +1,99 @@
+use super::common::HtmlLink;
+
+/// Fastext keys as defined in ETS 300 706
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FastextKey {
+    Red,
+    Green,
+    Yellow,
+    Cyan,
+    Index,
+}
+
+impl FastextKey {
+    pub const ALL: [FastextKey; 5] = [
+        FastextKey::Red,
+        FastextKey::Green,
+        FastextKey::Yellow,
+        FastextKey::Cyan,
+        FastextKey::Index,
+    ];
+
+    /// Colour of the key as rgb, index key doesn't have a colour
+    pub fn rgb(&self) -> Option<[u8; 3]> {
+        match self {
+            Self::Red => Some([255, 0, 0]),
+            Self::Green => Some([0, 255, 0]),
+            Self::Yellow => Some([255, 255, 0]),
+            Self::Cyan => Some([0, 255, 255]),
+            Self::Index => None,
+        }
+    }
+}
+
+/// Provider neutral fastext links of a page
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct Fastext<'a> {
+    pub red: Option<HtmlLink<'a>>,
+    pub green: Option<HtmlLink<'a>>,
+    pub yellow: Option<HtmlLink<'a>>,
+    pub cyan: Option<HtmlLink<'a>>,
+    pub index: Option<HtmlLink<'a>>,
+}
+
+impl<'a> Fastext<'a> {
+    /// Create fastext from links where the last link is the index link and
+    /// up to four links before it are the coloured links
+    pub fn from_links(links: &[HtmlLink<'a>]) -> Self {
+        let mut fastext = Self::default();
+        if let Some((index, colors)) = links.split_last() {
+            for (key, link) in FastextKey::ALL.iter().zip(colors.iter().take(4)) {
+                fastext.set(*key, Some(link.clone()));
+            }
+            fastext.index = Some(index.clone());
+        }
+
+        fastext
+    }
+
+    pub fn get(&self, key: FastextKey) -> Option<&HtmlLink<'a>> {
+        match key {
+            FastextKey::Red => self.red.as_ref(),
+            FastextKey::Green => self.green.as_ref(),
+            FastextKey::Yellow => self.yellow.as_ref(),
+            FastextKey::Cyan => self.cyan.as_ref(),
+            FastextKey::Index => self.index.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, key: FastextKey, link: Option<HtmlLink<'a>>) {
+        match key {
+            FastextKey::Red => self.red = link,
+            FastextKey::Green => self.green = link,
+            FastextKey::Yellow => self.yellow = link,
+            FastextKey::Cyan => self.cyan = link,
+            FastextKey::Index => self.index = link,
+        }
+    }
+
+    /// All the keys that have a link, in the order of the keys
+    pub fn links(&self) -> impl Iterator<Item = (FastextKey, &HtmlLink<'a>)> {
+        FastextKey::ALL
+            .iter()
+            .filter_map(move |key| self.get(*key).map(|link| (*key, link)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links().next().is_none()
+    }
+
+    pub fn into_owned(self) -> Fastext<'static> {
+        Fastext {
+            red: self.red.map(HtmlLink::into_owned),
+            green: self.green.map(HtmlLink::into_owned),
+            yellow: self.yellow.map(HtmlLink::into_owned),
+            cyan: self.cyan.map(HtmlLink::into_owned),
+            index: self.index.map(HtmlLink::into_owned),
+        }
+    }
+}
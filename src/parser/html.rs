@@ -0,0 +1,250 @@
+/// Tags whose content is text until the closing tag, even if it contains `<`
+const RAW_TEXT_TAGS: [&str; 2] = ["script", "style"];
+
+/// Html start tag, e.g. `<a href="101_0001.htm">`. Everything borrows the html document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tag<'a> {
+    /// Name as it's written in the document, use `is` to compare names
+    pub name: &'a str,
+    /// Unparsed attributes, use `attribute` or `attributes` to read them
+    raw_attributes: &'a str,
+    /// Tag ends in `/>`
+    pub self_closing: bool,
+}
+
+impl<'a> Tag<'a> {
+    /// Tag names are case insensitive, `<SPAN>` is a `span`
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Value of the attribute with entities still encoded.
+    /// Attributes without value, e.g. `hidden`, return an empty string
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes()
+            .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// All attributes in the order they are in the tag
+    pub fn attributes(&self) -> Attributes<'a> {
+        Attributes {
+            rest: self.raw_attributes,
+        }
+    }
+}
+
+/// Iterator over the `(name, value)` pairs of a tag
+#[derive(Debug, Clone)]
+pub struct Attributes<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self
+            .rest
+            .trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            self.rest = rest;
+            return None;
+        }
+
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        let after_name = rest[name_end..].trim_start();
+
+        let after_eq = match after_name.strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => {
+                self.rest = after_name;
+                return Some((name, ""));
+            }
+        };
+
+        let (value, rest) = match after_eq.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &after_eq[1..];
+                match value.find(quote) {
+                    Some(end) => (&value[..end], &value[end + 1..]),
+                    None => (value, ""),
+                }
+            }
+            _ => {
+                let end = after_eq
+                    .find(|c: char| c.is_whitespace())
+                    .unwrap_or(after_eq.len());
+                (&after_eq[..end], &after_eq[end..])
+            }
+        };
+
+        self.rest = rest;
+        Some((name, value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    Start(Tag<'a>),
+    /// Name of the closing tag, e.g. "pre" for `</pre>`
+    End(&'a str),
+    /// Text between the tags with entities still encoded
+    Text(&'a str),
+}
+
+impl<'a> Token<'a> {
+    pub fn is_start(&self, name: &str) -> bool {
+        matches!(self, Token::Start(tag) if tag.is(name))
+    }
+
+    pub fn is_end(&self, name: &str) -> bool {
+        matches!(self, Token::End(tag) if tag.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Zero-copy html tokenizer. Comments and declarations like `<!DOCTYPE>` are skipped.
+///
+/// The tokenizer is forgiving, like browsers are: unclosed tags and quotes end
+/// at the end of the document and a `<` that doesn't start a tag is text
+#[derive(Debug, Clone)]
+pub struct Tokenizer<'a> {
+    rest: &'a str,
+    /// Set after `<script>` and `<style>` since their content is not html
+    raw_text: Option<&'a str>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(html: &'a str) -> Self {
+        Self {
+            rest: html,
+            raw_text: None,
+        }
+    }
+
+    /// The part of the document that is not tokenized yet
+    pub fn rest(&self) -> &'a str {
+        self.rest
+    }
+
+    /// The next token is the content of `<script>` or `<style>`
+    pub fn in_raw_text(&self) -> bool {
+        self.raw_text.is_some()
+    }
+
+    fn advance(&mut self, len: usize) -> &'a str {
+        let (start, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        start
+    }
+
+    /// Position of the next `<` that starts a tag, comment or declaration
+    fn next_markup(html: &str) -> Option<usize> {
+        let bytes = html.as_bytes();
+        (0..bytes.len()).find(|idx| {
+            bytes[*idx] == b'<'
+                && matches!(bytes.get(idx + 1), Some(c) if c.is_ascii_alphabetic() || matches!(c, b'/' | b'!' | b'?'))
+        })
+    }
+
+    /// Position of the closing tag `</name` ignoring the case
+    fn find_closing(html: &str, name: &str) -> Option<usize> {
+        let bytes = html.as_bytes();
+        let name = name.as_bytes();
+        (0..bytes.len()).find(|idx| {
+            bytes[*idx..].starts_with(b"</")
+                && bytes.len() >= idx + 2 + name.len()
+                && bytes[idx + 2..idx + 2 + name.len()].eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Length of the tag until and including the `>`, skipping the `>` in quotes
+    fn tag_len(html: &str) -> usize {
+        let mut quote: Option<char> = None;
+        for (idx, c) in html.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), _) if q == c => quote = None,
+                (None, '>') => return idx + 1,
+                _ => {}
+            }
+        }
+
+        html.len()
+    }
+
+    fn start_tag(&mut self) -> Token<'a> {
+        let len = Self::tag_len(self.rest);
+        let tag = self.advance(len);
+        let inner = tag[1..].trim_end_matches('>');
+        let name_end = inner
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(inner.len());
+        let (name, raw_attributes) = inner.split_at(name_end);
+        let self_closing = raw_attributes.trim_end().ends_with('/');
+
+        if RAW_TEXT_TAGS
+            .iter()
+            .any(|raw| name.eq_ignore_ascii_case(raw))
+            && !self_closing
+        {
+            self.raw_text = Some(name);
+        }
+
+        Token::Start(Tag {
+            name,
+            raw_attributes,
+            self_closing,
+        })
+    }
+
+    fn end_tag(&mut self) -> Token<'a> {
+        let len = self
+            .rest
+            .find('>')
+            .map(|idx| idx + 1)
+            .unwrap_or(self.rest.len());
+        let tag = self.advance(len);
+        let name = tag[2..].trim_end_matches('>').trim();
+        Token::End(name)
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+
+            if let Some(name) = self.raw_text.take() {
+                let end = Self::find_closing(self.rest, name).unwrap_or(self.rest.len());
+                if end > 0 {
+                    return Some(Token::Text(self.advance(end)));
+                }
+            }
+
+            let markup = Self::next_markup(self.rest).unwrap_or(self.rest.len());
+            if markup > 0 {
+                return Some(Token::Text(self.advance(markup)));
+            }
+
+            if self.rest.starts_with("<!--") {
+                let len = self.rest.find("-->").map(|idx| idx + 3);
+                self.advance(len.unwrap_or(self.rest.len()));
+            } else if self.rest.starts_with("<!") || self.rest.starts_with("<?") {
+                let len = self.rest.find('>').map(|idx| idx + 1);
+                self.advance(len.unwrap_or(self.rest.len()));
+            } else if self.rest.starts_with("</") {
+                return Some(self.end_tag());
+            } else {
+                return Some(self.start_tag());
+            }
+        }
+    }
+}
@@ -1,7 +1,24 @@
 pub mod common;
+pub mod dr_text;
+pub mod export;
+pub mod fastext;
+pub mod html;
+pub mod page_image;
+pub mod print;
+pub mod provider;
+pub mod svt_text;
+pub mod tti;
 pub mod yle_image;
 pub mod yle_text;
 
 pub use common::{HtmlItem, HtmlLink, HtmlLoader, HtmlParser, HtmlText};
+pub use dr_text::DrText;
+pub use export::{ExportFormat, HtmlColors, EXPORT_COLUMNS};
+pub use fastext::{Fastext, FastextKey};
+pub use page_image::{ImageFormat, PageImage};
+pub use print::PrintPage;
+pub use provider::{ProviderConfig, ProviderPage, ProvidersFile};
+pub use svt_text::SvtText;
+pub use tti::TtiPage;
 pub use yle_image::YleImage;
-pub use yle_text::{TeleText, MIDDLE_TEXT_MAX_LEN};
+pub use yle_text::{TeleText, TextPage, YleText, MIDDLE_TEXT_MAX_LEN};
@@ -0,0 +1,396 @@
+use std::io::Cursor;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use base64::{engine::general_purpose, Engine as _};
+use image::{DynamicImage, ImageOutputFormat, ImageResult, Rgb, RgbImage};
+
+use super::common::HtmlItem;
+use super::export::{hex_color, page_label, HtmlColors, EXPORT_COLUMNS};
+use super::yle_image::YleImage;
+use super::yle_text::{TeleText, MIDDLE_TEXT_MAX_LEN};
+
+extern crate html_escape;
+
+/// Same font as in the reader so the images look like the pages
+const FONT: &[u8] = include_bytes!("../../assets/DejaVuSansMono.ttf");
+
+/// Font size in pixels when the scale is 1
+pub const IMAGE_FONT_SIZE: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    /// Text is kept as text, so the font of the viewer is used
+    Svg,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 2] = [Self::Png, Self::Svg];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Svg => "SVG",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+        }
+    }
+}
+
+fn font() -> FontRef<'static> {
+    FontRef::try_from_slice(FONT).unwrap()
+}
+
+/// Size of the characters in pixels
+struct Metrics {
+    size: PxScale,
+    cell_width: f32,
+    line_height: f32,
+    ascent: f32,
+}
+
+impl Metrics {
+    fn new(font: &FontRef, scale: f32) -> Self {
+        let size = PxScale::from(IMAGE_FONT_SIZE * scale);
+        let scaled = font.as_scaled(size);
+        Self {
+            size,
+            // Font is monospace so every character is as wide
+            cell_width: scaled.h_advance(font.glyph_id('W')),
+            line_height: scaled.height() + scaled.line_gap(),
+            ascent: scaled.ascent(),
+        }
+    }
+
+    /// Scale where `EXPORT_COLUMNS` and the margins fill the width
+    fn scale_for_width(width: u32) -> f32 {
+        let metrics = Self::new(&font(), 1.0);
+        width as f32 / (metrics.cell_width * (EXPORT_COLUMNS + 2) as f32)
+    }
+}
+
+/// Text drawn in one colour
+struct Span {
+    text: String,
+    color: [u8; 3],
+}
+
+/// Where the row is placed on the page
+#[derive(Clone, Copy)]
+enum Align {
+    /// Left aligned where the middle rows start in the reader
+    Middle,
+    Center,
+}
+
+/// Rows of the page in the colours of the reader. Rendered independently
+/// of the window so the size only depends on the scale
+pub struct PageImage {
+    rows: Vec<Vec<Span>>,
+    columns: usize,
+    colors: HtmlColors,
+}
+
+fn span_len(row: &[Span]) -> usize {
+    row.iter().map(|span| span.text.chars().count()).sum()
+}
+
+impl PageImage {
+    /// Header has the page on the left and the title in the center, like in the reader
+    fn new(label: &str, title: &str, rows: Vec<(Vec<Span>, Align)>, colors: HtmlColors) -> Self {
+        let label_len = label.chars().count();
+        let title_len = title.chars().count();
+        let columns = rows
+            .iter()
+            .map(|(row, _)| span_len(row))
+            .chain([EXPORT_COLUMNS, label_len + title_len + 1])
+            .max()
+            .unwrap_or(EXPORT_COLUMNS);
+
+        let padding = (columns.saturating_sub(title_len) / 2)
+            .saturating_sub(label_len)
+            .max(1);
+        let header = vec![Span {
+            text: format!("{label}{}{title}", " ".repeat(padding)),
+            color: colors.text,
+        }];
+
+        let mut page_rows = vec![header];
+        for (mut row, align) in rows {
+            let padding = match align {
+                Align::Middle => columns.saturating_sub(MIDDLE_TEXT_MAX_LEN + 1) / 2,
+                Align::Center => columns.saturating_sub(span_len(&row)) / 2,
+            };
+            if padding > 0 {
+                let text = " ".repeat(padding);
+                row.insert(
+                    0,
+                    Span {
+                        text,
+                        color: colors.text,
+                    },
+                );
+            }
+            page_rows.push(row);
+        }
+
+        Self {
+            rows: page_rows,
+            columns,
+            colors,
+        }
+    }
+
+    /// Size in pixels with a margin of one character on every side
+    fn size(&self, metrics: &Metrics) -> (u32, u32) {
+        let margin = metrics.cell_width * 2.0;
+        let width = metrics.cell_width * self.columns as f32 + margin;
+        let height = metrics.line_height * self.rows.len() as f32 + margin;
+        (width.round() as u32, height.round() as u32)
+    }
+
+    /// Spans with the position of their first character and the baseline
+    fn positioned(&self, metrics: &Metrics) -> Vec<(f32, f32, &Span)> {
+        let mut spans = Vec::new();
+        for (row, line) in self.rows.iter().enumerate() {
+            let y = metrics.cell_width + metrics.line_height * row as f32 + metrics.ascent;
+            let mut column = 0;
+            for span in line {
+                let x = metrics.cell_width * (column + 1) as f32;
+                column += span.text.chars().count();
+                if !span.text.trim().is_empty() {
+                    spans.push((x, y, span));
+                }
+            }
+        }
+
+        spans
+    }
+
+    fn to_rgb(&self, scale: f32) -> RgbImage {
+        let font = font();
+        let metrics = Metrics::new(&font, scale);
+        let (width, height) = self.size(&metrics);
+        let mut image = RgbImage::from_pixel(width, height, Rgb(self.colors.background));
+
+        for (x, y, span) in self.positioned(&metrics) {
+            for (idx, c) in span.text.chars().enumerate() {
+                let x = x + metrics.cell_width * idx as f32;
+                let glyph = font
+                    .glyph_id(c)
+                    .with_scale_and_position(metrics.size, point(x, y));
+                let outlined = match font.outline_glyph(glyph) {
+                    Some(outlined) => outlined,
+                    None => continue,
+                };
+
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let px = bounds.min.x as i64 + gx as i64;
+                    let py = bounds.min.y as i64 + gy as i64;
+                    if px >= 0 && py >= 0 && px < width as i64 && py < height as i64 {
+                        let pixel = image.get_pixel_mut(px as u32, py as u32);
+                        blend(pixel, span.color, coverage);
+                    }
+                });
+            }
+        }
+
+        image
+    }
+
+    fn svg_text(&self, metrics: &Metrics) -> String {
+        let mut svg = format!(
+            "<g font-family=\"DejaVu Sans Mono, monospace\" font-size=\"{:.1}\" xml:space=\"preserve\">\n",
+            metrics.size.y
+        );
+        for (x, y, span) in self.positioned(metrics) {
+            svg.push_str(&format!(
+                "<text x=\"{x:.1}\" y=\"{y:.1}\" fill=\"{}\">{}</text>\n",
+                hex_color(span.color),
+                html_escape::encode_text(&span.text)
+            ));
+        }
+        svg.push_str("</g>\n");
+        svg
+    }
+
+    pub fn to_png(&self, scale: f32) -> ImageResult<Vec<u8>> {
+        encode_png(self.to_rgb(scale))
+    }
+
+    pub fn to_svg(&self, scale: f32) -> String {
+        let metrics = Metrics::new(&font(), scale);
+        let (width, height) = self.size(&metrics);
+        svg_document(
+            width,
+            height,
+            self.colors.background,
+            &self.svg_text(&metrics),
+        )
+    }
+
+    pub fn render(&self, format: ImageFormat, scale: f32) -> ImageResult<Vec<u8>> {
+        match format {
+            ImageFormat::Png => self.to_png(scale),
+            ImageFormat::Svg => Ok(self.to_svg(scale).into_bytes()),
+        }
+    }
+}
+
+/// Mix the colour to the pixel by the share of the pixel the glyph covers
+fn blend(pixel: &mut Rgb<u8>, color: [u8; 3], coverage: f32) {
+    let coverage = coverage.clamp(0.0, 1.0);
+    for (channel, color) in pixel.0.iter_mut().zip(color) {
+        let mixed = *channel as f32 * (1.0 - coverage) + color as f32 * coverage;
+        *channel = mixed.round() as u8;
+    }
+}
+
+fn encode_png(image: RgbImage) -> ImageResult<Vec<u8>> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(image).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+    Ok(png)
+}
+
+fn svg_document(width: u32, height: u32, background: [u8; 3], body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">
+<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>
+{body}</svg>
+",
+        hex_color(background)
+    )
+}
+
+fn item_spans(row: &[HtmlItem], colors: &HtmlColors) -> Vec<Span> {
+    row.iter()
+        .map(|item| Span {
+            text: item.text().to_string(),
+            color: match item {
+                HtmlItem::Text(_) => colors.text,
+                HtmlItem::Link(_) => colors.link,
+            },
+        })
+        .collect()
+}
+
+impl TeleText<'_> {
+    /// Header, navigation, middle rows, sub-pages and fastext links of the page
+    pub fn to_page_image(&self, page: i32, sub_page: i32, colors: &HtmlColors) -> PageImage {
+        // Navigation is separated like in the reader, e.g. "Edellinen sivu | Seuraava sivu"
+        let mut navigation = Vec::new();
+        for span in item_spans(&self.page_navigation, colors) {
+            if !navigation.is_empty() {
+                navigation.push(Span {
+                    text: " | ".into(),
+                    color: colors.text,
+                });
+            }
+            navigation.push(span);
+        }
+
+        let mut rows = vec![(navigation, Align::Center)];
+        for row in &self.middle_rows {
+            rows.push((item_spans(row, colors), Align::Middle));
+        }
+        if !self.sub_pages.is_empty() {
+            rows.push((item_spans(&self.sub_pages, colors), Align::Middle));
+        }
+
+        let mut fastext = Vec::new();
+        for (key, link) in self.fastext_links() {
+            if !fastext.is_empty() {
+                fastext.push(Span {
+                    text: "  ".into(),
+                    color: colors.text,
+                });
+            }
+            fastext.push(Span {
+                text: link.inner_text.trim().to_string(),
+                color: key.rgb().unwrap_or(colors.link),
+            });
+        }
+        if !fastext.is_empty() {
+            rows.push((Vec::new(), Align::Middle));
+            rows.push((fastext, Align::Center));
+        }
+
+        PageImage::new(
+            &page_label(page, sub_page),
+            self.title.trim(),
+            rows,
+            *colors,
+        )
+    }
+}
+
+impl YleImage {
+    /// Header like in the reader, e.g. "P100  100/1 YLE TEKSTI-TV"
+    fn image_header(&self, page: i32, sub_page: i32, colors: HtmlColors) -> PageImage {
+        let title = format!("{} YLE TEKSTI-TV", self.title.trim());
+        PageImage::new(&page_label(page, sub_page), &title, Vec::new(), colors)
+    }
+
+    /// The image of the response. With the colours of the header, the header is drawn
+    /// above the image in a font where `EXPORT_COLUMNS` fit in the width of the image
+    pub fn export_image(
+        &self,
+        format: ImageFormat,
+        page: i32,
+        sub_page: i32,
+        header: Option<HtmlColors>,
+    ) -> ImageResult<Vec<u8>> {
+        let colors = match (format, header) {
+            (ImageFormat::Png, None) => return Ok(self.image.clone()),
+            (_, colors) => colors,
+        };
+
+        let image = image::load_from_memory(&self.image)?.to_rgb8();
+        let scale = Metrics::scale_for_width(image.width());
+        let header = colors.map(|colors| self.image_header(page, sub_page, colors));
+
+        match (format, header) {
+            (ImageFormat::Png, Some(header)) => {
+                let header_image = header.to_rgb(scale);
+                let width = image.width().max(header_image.width());
+                let height = header_image.height() + image.height();
+                let background = Rgb(header.colors.background);
+                let mut page_image = RgbImage::from_pixel(width, height, background);
+                image::imageops::replace(&mut page_image, &header_image, 0, 0);
+                let x = (width - image.width()) / 2;
+                let y = header_image.height();
+                image::imageops::replace(&mut page_image, &image, x as i64, y as i64);
+                encode_png(page_image)
+            }
+            (_, header) => {
+                let (mut width, mut top) = (image.width(), 0);
+                let mut body = String::new();
+                let mut background = [0, 0, 0];
+                if let Some(header) = header {
+                    let metrics = Metrics::new(&font(), scale);
+                    let (header_width, header_height) = header.size(&metrics);
+                    width = width.max(header_width);
+                    top = header_height;
+                    background = header.colors.background;
+                    body = header.svg_text(&metrics);
+                }
+
+                body.push_str(&format!(
+                    "<image x=\"{}\" y=\"{top}\" width=\"{}\" height=\"{}\" href=\"data:image/png;base64,{}\"/>\n",
+                    (width - image.width()) / 2,
+                    image.width(),
+                    image.height(),
+                    general_purpose::STANDARD.encode(&self.image)
+                ));
+                let height = top + image.height();
+                Ok(svg_document(width, height, background, &body).into_bytes())
+            }
+        }
+    }
+}
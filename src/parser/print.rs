@@ -0,0 +1,193 @@
+use super::export::page_label;
+
+extern crate html_escape;
+
+/// A4 in points
+const SHEET_WIDTH: f32 = 595.0;
+const SHEET_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 72.0;
+const FONT_SIZE: f32 = 12.0;
+const LINE_HEIGHT: f32 = 16.0;
+/// Lines that fit on a sheet above the sheet number
+const SHEET_LINES: usize = ((SHEET_HEIGHT - MARGIN * 2.0) / LINE_HEIGHT) as usize;
+
+/// Page laid out for printing, black text on white
+#[derive(Debug, Clone)]
+pub struct PrintPage {
+    /// Page and title, e.g. "P235/2  JÄÄKIEKKO"
+    pub header: String,
+    pub lines: Vec<String>,
+}
+
+impl PrintPage {
+    pub fn new(page: i32, sub_page: i32, title: &str, lines: Vec<String>) -> Self {
+        Self {
+            header: format!("{}  {}", page_label(page, sub_page), title.trim()),
+            lines: lines
+                .iter()
+                .map(|line| line.trim_end().to_string())
+                .collect(),
+        }
+    }
+
+    /// Header, an empty line and the rows of the page
+    fn sheet_lines(&self) -> Vec<(bool, &str)> {
+        let mut lines = vec![(true, self.header.as_str()), (false, "")];
+        lines.extend(self.lines.iter().map(|line| (false, line.as_str())));
+        lines
+    }
+}
+
+/// Text as a pdf string in WinAnsiEncoding. Characters the standard fonts
+/// don't have are replaced with '?'
+fn pdf_string(text: &str) -> String {
+    let mut string = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                string.push('\\');
+                string.push(c);
+            }
+            ' '..='~' => string.push(c),
+            // Latin-1 characters have the same codes in WinAnsiEncoding
+            '\u{a0}'..='\u{ff}' => string.push_str(&format!("\\{:03o}", c as u32)),
+            '€' => string.push_str("\\200"),
+            _ => string.push('?'),
+        }
+    }
+    string.push(')');
+    string
+}
+
+/// Objects of the document with their byte offsets for the cross-reference table
+struct PdfWriter {
+    data: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        Self {
+            // Binary comment tells that the file is not plain text
+            data: b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    fn object(&mut self, body: &str) {
+        self.offsets.push(self.data.len());
+        let id = self.offsets.len();
+        self.data
+            .extend(format!("{id} 0 obj\n{body}\nendobj\n").as_bytes());
+    }
+
+    fn stream(&mut self, content: &str) {
+        let body = format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len()
+        );
+        self.object(&body);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.data.len();
+        let size = self.offsets.len() + 1;
+        let mut table = format!("xref\n0 {size}\n0000000000 65535 f \n");
+        for offset in &self.offsets {
+            table.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {size} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n"
+        ));
+        self.data.extend(table.as_bytes());
+        self.data
+    }
+}
+
+/// Pdf document with every page starting on a new A4 sheet.
+/// Uses the standard Courier font so no fonts are embedded
+pub fn to_pdf(pages: &[PrintPage]) -> Vec<u8> {
+    let mut sheets: Vec<Vec<(bool, &str)>> = Vec::new();
+    for page in pages {
+        let lines = page.sheet_lines();
+        sheets.extend(lines.chunks(SHEET_LINES).map(<[_]>::to_vec));
+    }
+
+    // Catalog, page tree and the two fonts come before the sheets
+    let first_sheet = 5;
+    let kids: Vec<String> = (0..sheets.len())
+        .map(|idx| format!("{} 0 R", first_sheet + idx * 2))
+        .collect();
+
+    let mut pdf = PdfWriter::new();
+    pdf.object("<< /Type /Catalog /Pages 2 0 R >>");
+    pdf.object(&format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        sheets.len()
+    ));
+    pdf.object("<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>");
+    pdf.object(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier-Bold /Encoding /WinAnsiEncoding >>",
+    );
+
+    for (idx, lines) in sheets.iter().enumerate() {
+        pdf.object(&format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {SHEET_WIDTH} {SHEET_HEIGHT}] \
+            /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            first_sheet + idx * 2 + 1
+        ));
+
+        let top = SHEET_HEIGHT - MARGIN;
+        let mut content = format!("BT\n{LINE_HEIGHT} TL\n{MARGIN} {top} Td\n");
+        for (bold, line) in lines {
+            let font = if *bold { "F2" } else { "F1" };
+            content.push_str(&format!(
+                "/{font} {FONT_SIZE} Tf {} Tj T*\n",
+                pdf_string(line)
+            ));
+        }
+        content.push_str("ET\n");
+
+        let number = format!("{}/{}", idx + 1, sheets.len());
+        content.push_str(&format!(
+            "BT /F1 9 Tf {} {} Td {} Tj ET",
+            SHEET_WIDTH / 2.0,
+            MARGIN / 2.0,
+            pdf_string(&number)
+        ));
+        pdf.stream(&content);
+    }
+
+    pdf.finish()
+}
+
+/// Html document for the print dialog of the browser, every page on its own sheet.
+/// The print dialog opens when the document is loaded
+pub fn to_print_html(pages: &[PrintPage]) -> String {
+    let mut body = String::new();
+    for page in pages {
+        body.push_str(&format!(
+            "<pre><b>{}</b>\n\n{}</pre>\n",
+            html_escape::encode_text(&page.header),
+            html_escape::encode_text(&page.lines.join("\n"))
+        ));
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<style>
+body {{ background: #ffffff; color: #000000; }}
+pre {{ font-family: \"DejaVu Sans Mono\", monospace; font-size: {FONT_SIZE}pt; break-after: page; }}
+pre:last-child {{ break-after: auto; }}
+</style>
+</head>
+<body onload=\"window.print()\">
+{body}</body>
+</html>
+"
+    )
+}
@@ -0,0 +1,392 @@
+use base64::{engine::general_purpose, Engine as _};
+
+use super::common::{
+    decode_string, page_link, HtmlImageArea, HtmlItem, HtmlLink, HtmlLoader, HtmlParser, HtmlText,
+    ParseErr, ParseState, ParserResult,
+};
+use super::fastext::Fastext;
+use super::html::{Tag, Token, Tokenizer};
+use super::yle_image::YleImage;
+use super::yle_text::TeleText;
+
+const PROVIDER_NAVIGATION_LABELS: [&str; 4] = [
+    "Previous page",
+    "Previous sub page",
+    "Next sub page",
+    "Next page",
+];
+
+fn default_link_attribute() -> String {
+    "href".into()
+}
+
+fn default_index_page() -> i32 {
+    100
+}
+
+/// Format of the provider responses
+#[derive(Debug, Default, Clone, Copy, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseType {
+    /// Selectors are tag names separated by spaces, e.g. "div pre"
+    #[default]
+    Html,
+    /// Selectors are paths separated by dots, e.g. "data.0.content.text".
+    /// Selected values are handled as html fragments
+    Json,
+}
+
+/// Section shown in the bottom navigation
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ProviderSection {
+    pub page: i32,
+    pub name: String,
+}
+
+/// Teletext service defined in the providers file, e.g.
+/// ```toml
+/// [[provider]]
+/// name = "Example Text"
+/// url = "https://example.com/ttv/{page}_{sub_page}.htm"
+/// response = "html"
+/// title = "h1"
+/// rows = "div pre"
+/// ```
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ProviderConfig {
+    /// Name shown in the reader menu
+    pub name: String,
+    /// `{page}` is replaced with the page number and `{sub_page}` with
+    /// four digit sub page number
+    pub url: String,
+    #[serde(default)]
+    pub response: ResponseType,
+    /// Selector of the page title
+    pub title: Option<String>,
+    /// Selector of the page rows, each line of the selected text is one row
+    pub rows: Option<String>,
+    /// Attribute of the links in rows and image maps that contains the page number
+    #[serde(default = "default_link_attribute")]
+    pub link_attribute: String,
+    /// Selector of the base64 encoded png image.
+    /// Providers with an image are shown in the image reader
+    pub image: Option<String>,
+    /// Selector of the html image map containing `<area>` elements
+    pub image_map: Option<String>,
+    /// Sections shown in the bottom navigation and as fastext links
+    #[serde(default)]
+    pub sections: Vec<ProviderSection>,
+    #[serde(default = "default_index_page")]
+    pub index_page: i32,
+}
+
+/// Contents of the providers file
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct ProvidersFile {
+    #[serde(default)]
+    pub provider: Vec<ProviderConfig>,
+}
+
+impl ProvidersFile {
+    pub fn from_toml(data: &str) -> ParserResult<Self> {
+        toml::from_str(data).map_err(|_| ParseErr::InvalidPage)
+    }
+
+    pub fn from_json(data: &str) -> ParserResult<Self> {
+        serde_json::from_str(data).map_err(|_| ParseErr::InvalidPage)
+    }
+}
+
+/// Page requested from a provider
+pub struct ProviderPage<'a> {
+    pub provider: &'a ProviderConfig,
+    pub page: i32,
+    pub sub_page: i32,
+}
+
+impl ProviderConfig {
+    pub fn page_url(&self, page: i32, sub_page: i32) -> String {
+        self.url
+            .replace("{page}", &page.to_string())
+            .replace("{sub_page}", &format!("{sub_page:04}"))
+    }
+
+    /// Links to the sections, the index page is the last one
+    fn section_links(&self) -> Vec<HtmlLink<'static>> {
+        let mut links: Vec<HtmlLink> = self
+            .sections
+            .iter()
+            .map(|section| page_link(section.page, section.name.clone().into()))
+            .collect();
+        links.push(page_link(self.index_page, self.name.clone().into()));
+        links
+    }
+}
+
+/// Link to a page in the "101_0001" format used by all the readers
+fn provider_link(page: i32, sub_page: i32, inner_text: &'static str) -> Option<HtmlLink<'static>> {
+    if (100..=899).contains(&page) && sub_page > 0 {
+        Some(HtmlLink {
+            url: format!("{page}_{sub_page:04}").into(),
+            inner_text: inner_text.into(),
+        })
+    } else {
+        None
+    }
+}
+
+/// First three digit number of a link, e.g. "/ttv/101_0002.htm" is page 101
+fn attribute_page(value: &str) -> Option<i32> {
+    let bytes = value.as_bytes();
+    (0..bytes.len().saturating_sub(2))
+        .filter(|idx| bytes[*idx..*idx + 3].iter().all(|b| b.is_ascii_digit()))
+        .filter(|idx| *idx == 0 || !bytes[idx - 1].is_ascii_digit())
+        .filter_map(|idx| value[idx..idx + 3].parse::<i32>().ok())
+        .find(|page| (100..=899).contains(page))
+}
+
+/// Select the inner html of the tags, e.g. "div pre" selects the contents of
+/// the first `<pre>` inside the first `<div>`
+fn select_html<'a>(html: &'a str, selector: &str) -> ParserResult<&'a str> {
+    let mut tokens = Tokenizer::new(html);
+    let mut last_tag = "";
+    for tag in selector.split_whitespace() {
+        tokens
+            .find(|token| token.is_start(tag))
+            .ok_or(ParseErr::InvalidPage)?;
+        last_tag = tag;
+    }
+
+    // Inner html ends at the closing tag of the same level
+    let start = html.len() - tokens.rest().len();
+    let mut depth = 0;
+    loop {
+        let end = html.len() - tokens.rest().len();
+        match tokens.next() {
+            Some(Token::Start(tag)) if tag.is(last_tag) && !tag.self_closing => depth += 1,
+            Some(token) if token.is_end(last_tag) => {
+                if depth == 0 {
+                    return Ok(&html[start..end]);
+                }
+                depth -= 1;
+            }
+            Some(_) => {}
+            None => return Ok(&html[start..]),
+        }
+    }
+}
+
+/// Select a string value from json, e.g. "data.0.content.text"
+fn select_json(json: &serde_json::Value, selector: &str) -> ParserResult<String> {
+    let mut value = json;
+    for key in selector.split('.') {
+        value = match key.parse::<usize>() {
+            Ok(idx) if value.is_array() => value.get(idx),
+            _ => value.get(key),
+        }
+        .ok_or(ParseErr::InvalidPage)?;
+    }
+
+    match value {
+        serde_json::Value::String(string) => Ok(string.clone()),
+        other => Ok(other.to_string()),
+    }
+}
+
+/// Response of a provider, parsed according to the response type
+enum Document<'a> {
+    Html(&'a str),
+    Json(serde_json::Value),
+}
+
+impl<'a> Document<'a> {
+    fn new(data: &'a str, response: ResponseType) -> ParserResult<Self> {
+        match response {
+            ResponseType::Html => Ok(Self::Html(data)),
+            ResponseType::Json => serde_json::from_str(data)
+                .map(Self::Json)
+                .map_err(|_| ParseErr::InvalidPage),
+        }
+    }
+
+    /// Returns None if the provider doesn't define the selector
+    fn select(&self, selector: &Option<String>) -> ParserResult<Option<String>> {
+        let selector = match selector {
+            Some(selector) => selector,
+            None => return Ok(None),
+        };
+
+        match self {
+            Self::Html(html) => select_html(html, selector).map(|s| Some(s.into())),
+            Self::Json(json) => select_json(json, selector).map(Some),
+        }
+    }
+}
+
+impl<'a> ProviderPage<'a> {
+    fn link_item<'h>(&self, tag: &Tag, inner_text: HtmlText<'h>) -> HtmlItem<'h> {
+        match tag
+            .attribute(&self.provider.link_attribute)
+            .and_then(attribute_page)
+        {
+            Some(page) => HtmlItem::Link(page_link(page, inner_text)),
+            None => HtmlItem::Text(inner_text),
+        }
+    }
+
+    /// Parse the rows of the html fragment. Links are parsed with the link attribute
+    /// and all other tags are ignored
+    fn parse_rows<'h, P: HtmlParser>(&self, html: &'h str) -> ParserResult<Vec<Vec<HtmlItem<'h>>>> {
+        let html = html.strip_prefix("\r\n").unwrap_or(html);
+        let html = html.strip_prefix('\n').unwrap_or(html);
+
+        let mut rows: Vec<Vec<HtmlItem>> = vec![Vec::new()];
+        let mut state = &mut ParseState::new(html);
+        while let Some(token) = P::peek_token(state.current) {
+            match token {
+                Token::Start(tag) if tag.is("a") => {
+                    let (new_state, inner_text) =
+                        P::parse_inner_text(P::next_token(state)?.0, "a")?;
+                    state = new_state;
+                    let row = rows.last_mut().ok_or(ParseErr::InvalidPage)?;
+                    row.push(self.link_item(&tag, inner_text));
+                }
+                Token::Text(text) => {
+                    for (idx, line) in text.split('\n').enumerate() {
+                        if idx != 0 {
+                            rows.push(Vec::new());
+                        }
+
+                        let line = line.trim_end_matches('\r');
+                        if !line.is_empty() {
+                            let row = rows.last_mut().ok_or(ParseErr::InvalidPage)?;
+                            row.push(HtmlItem::Text(decode_string(line)));
+                        }
+                    }
+                    state = P::next_token(state)?.0;
+                }
+                _ => state = P::next_token(state)?.0,
+            }
+        }
+
+        // Like `lines`, the last line break doesn't start a new row
+        if matches!(rows.last(), Some(row) if row.is_empty()) {
+            rows.pop();
+        }
+
+        Ok(rows)
+    }
+
+    fn parse_image_map(&self, html: &str) -> ParserResult<Vec<HtmlImageArea>> {
+        let mut map: Vec<HtmlImageArea> = Vec::new();
+        let areas = Tokenizer::new(html).filter_map(|token| match token {
+            Token::Start(tag) if tag.is("area") => Some(tag),
+            _ => None,
+        });
+
+        for area in areas {
+            let coords: Vec<f32> = area
+                .attribute("coords")
+                .unwrap_or("")
+                .split(',')
+                .filter_map(|c| c.trim().parse::<f32>().ok())
+                .collect();
+            let page = area
+                .attribute(&self.provider.link_attribute)
+                .and_then(attribute_page);
+            // Only rectangles with links are used
+            if let (Some(page), [x1, y1, x2, y2]) = (page, coords.as_slice()) {
+                map.push(HtmlImageArea::new(
+                    *x1,
+                    *y1,
+                    *x2,
+                    *y2,
+                    format!("{page}_0001"),
+                ));
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Image is either a data url, html containing data url or plain base64
+    fn parse_image(&self, image: &str) -> ParserResult<Vec<u8>> {
+        let start = image.find("base64,").map(|idx| idx + 7).unwrap_or(0);
+        let end = image[start..]
+            .find(|c: char| c == '"' || c == '\'' || c.is_whitespace())
+            .map(|idx| start + idx)
+            .unwrap_or(image.len());
+
+        general_purpose::STANDARD
+            .decode(&image[start..end])
+            .map_err(|_| ParseErr::InvalidPage)
+    }
+
+    fn title(&self, title: Option<String>) -> HtmlText<'static> {
+        match title {
+            Some(title) => decode_string(title.trim()).into_owned().into(),
+            None => self.provider.name.clone().into(),
+        }
+    }
+
+    pub fn parse_text(&self, loader: HtmlLoader) -> ParserResult<TeleText<'static>> {
+        let provider = self.provider;
+        let document = Document::new(&loader.page_data, provider.response)?;
+        let title = document.select(&provider.title)?;
+        let rows = document
+            .select(&provider.rows)?
+            .ok_or(ParseErr::InvalidPage)?;
+
+        let (page, sub_page) = (self.page, self.sub_page);
+        let nav = PROVIDER_NAVIGATION_LABELS;
+        let link_or_text = |link: Option<HtmlLink<'static>>, label: &'static str| match link {
+            Some(link) => HtmlItem::Link(link),
+            None => HtmlItem::Text(label.into()),
+        };
+
+        let mut teletext = TeleText::default();
+        teletext.title = self.title(title);
+        teletext.page_navigation = vec![
+            link_or_text(provider_link(page - 1, 1, nav[0]), nav[0]),
+            link_or_text(provider_link(page, sub_page - 1, nav[1]), nav[1]),
+            link_or_text(provider_link(page, sub_page + 1, nav[2]), nav[2]),
+            link_or_text(provider_link(page + 1, 1, nav[3]), nav[3]),
+        ];
+        teletext.middle_rows = self.parse_rows::<TeleText>(&rows)?;
+        teletext.bottom_navigation = provider.section_links();
+        teletext.fastext = Fastext::from_links(&teletext.bottom_navigation);
+
+        // Rows borrow the selected html
+        Ok(teletext.into_owned())
+    }
+
+    pub fn parse_image_page(&self, loader: HtmlLoader) -> ParserResult<YleImage> {
+        let provider = self.provider;
+        let document = Document::new(&loader.page_data, provider.response)?;
+        let title = document.select(&provider.title)?;
+        let image = document
+            .select(&provider.image)?
+            .ok_or(ParseErr::InvalidPage)?;
+        let image_map = document.select(&provider.image_map)?;
+
+        let (page, sub_page) = (self.page, self.sub_page);
+        let nav = PROVIDER_NAVIGATION_LABELS;
+
+        let mut image_page = YleImage::new();
+        image_page.title = self.title(title);
+        image_page.image = self.parse_image(&image)?;
+        image_page.image_map = match image_map {
+            Some(image_map) => self.parse_image_map(&image_map)?,
+            None => Vec::new(),
+        };
+        image_page.botton_navigation = vec![
+            provider_link(page - 1, 1, nav[0]),
+            provider_link(page, sub_page - 1, nav[1]),
+            provider_link(page, sub_page + 1, nav[2]),
+            provider_link(page + 1, 1, nav[3]),
+        ];
+        image_page.fastext = Fastext::from_links(&provider.section_links());
+
+        Ok(image_page)
+    }
+}
@@ -0,0 +1,132 @@
+use super::common::{
+    page_link, page_navigation, text_to_items, HtmlItem, HtmlLoader, HtmlParser, HtmlText,
+    ParseErr, ParserResult,
+};
+use super::fastext::Fastext;
+use super::yle_text::{TeleText, TextPage};
+
+const SVT_NAVIGATION_LABELS: [&str; 4] = [
+    "Föregående sida",
+    "Föregående undersida",
+    "Nästa undersida",
+    "Nästa sida",
+];
+
+/// Main sections of SVT Text, the last one is the index page
+const SVT_SECTIONS: [(i32, &str); 6] = [
+    (101, "Inrikes"),
+    (104, "Utrikes"),
+    (200, "Ekonomi"),
+    (300, "Sport"),
+    (600, "TV"),
+    (100, "SVT Text"),
+];
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SJSubPage {
+    /// e.g. "100"
+    sub_page_number: String,
+    /// Text repesentation of the page, one row per line
+    alt_text: String,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SJData {
+    /// e.g. "100"
+    page_number: String,
+    /// Empty if there's no previous page
+    prev_page: String,
+    /// Empty if there's no next page
+    next_page: String,
+    sub_pages: Vec<SJSubPage>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct SvtJson {
+    /// "success" when the page exists
+    status: String,
+    data: SJData,
+}
+
+/// Contains the fields of SVT Text site.
+/// SVT returns all the sub pages in the same response so they are shown one after another
+#[derive(Debug)]
+pub struct SvtText {
+    pub teletext: TeleText<'static>,
+}
+
+impl SvtText {
+    /// First row of the page is the header, e.g. "100 SVT Text   Tisdag 17 jan 2023".
+    /// Title is the header without the page number
+    fn parse_title<'a>(header: &'a str, page_number: &str) -> HtmlText<'a> {
+        header.trim().trim_start_matches(page_number).trim().into()
+    }
+
+    fn parse_middle(sub_pages: &[SJSubPage]) -> Vec<Vec<HtmlItem<'_>>> {
+        let mut middle_rows: Vec<Vec<HtmlItem>> = Vec::new();
+        for (idx, sub_page) in sub_pages.iter().enumerate() {
+            // Separate the sub pages with an empty row
+            if idx != 0 {
+                middle_rows.push(Vec::new());
+            }
+
+            // Skip the header row since it's shown as the title
+            for row in sub_page.alt_text.lines().skip(1) {
+                middle_rows.push(text_to_items(row.trim_end()));
+            }
+        }
+
+        middle_rows
+    }
+}
+
+impl TextPage for SvtText {
+    fn teletext(&self) -> &TeleText<'_> {
+        &self.teletext
+    }
+
+    fn from_teletext(teletext: TeleText<'static>) -> Self {
+        Self { teletext }
+    }
+}
+
+impl HtmlParser for SvtText {
+    fn new() -> Self {
+        Self {
+            teletext: TeleText::default(),
+        }
+    }
+
+    fn parse(mut self, loader: HtmlLoader) -> ParserResult<Self> {
+        let json: SvtJson =
+            serde_json::from_str(&loader.page_data).map_err(|_| ParseErr::InvalidPage)?;
+        // Missing pages are returned as errors without sub pages
+        if json.status != "success" || json.data.sub_pages.is_empty() {
+            return Err(ParseErr::NotFound);
+        }
+
+        // The texts borrow the json so they are copied at the end
+        let data = &json.data;
+        let header = data.sub_pages[0].alt_text.lines().next().unwrap_or("");
+        let mut teletext = TeleText {
+            title: Self::parse_title(header, &data.page_number),
+            page_navigation: page_navigation(
+                SVT_NAVIGATION_LABELS,
+                data.prev_page.parse::<i32>().ok(),
+                data.next_page.parse::<i32>().ok(),
+            ),
+            middle_rows: Self::parse_middle(&data.sub_pages),
+            bottom_navigation: SVT_SECTIONS
+                .iter()
+                .map(|(page, name)| page_link(*page, (*name).into()))
+                .collect(),
+            ..Default::default()
+        };
+        teletext.fastext = Fastext::from_links(&teletext.bottom_navigation);
+        self.teletext = teletext.into_owned();
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,358 @@
+use super::common::{
+    link_page, page_link, page_navigation, text_to_items, HtmlItem, ParseErr, ParserResult,
+};
+use super::fastext::{Fastext, FastextKey};
+use super::yle_text::{TeleText, MIDDLE_TEXT_MAX_LEN};
+
+/// Rows 1-24 are the displayable rows of a teletext page, row 0 is the header
+const TTI_MAX_ROW: usize = 24;
+/// FL line has red, green, yellow, cyan, link 4 and index
+const FASTEXT_LINK_COUNT: usize = 6;
+/// Positions of the fastext keys in FL line, the fifth link is not used
+const FL_KEYS: [(usize, FastextKey); 5] = [
+    (0, FastextKey::Red),
+    (1, FastextKey::Green),
+    (2, FastextKey::Yellow),
+    (3, FastextKey::Cyan),
+    (5, FastextKey::Index),
+];
+/// Page number used in FL lines when the link is not in use
+const TTI_NO_LINK: &str = "8ff";
+const TTI_ESC: u8 = 0x1b;
+const YLE_NAVIGATION_LABELS: [&str; 4] = [
+    "Edellinen sivu",
+    "Edellinen alasivu",
+    "Seuraava alasivu",
+    "Seuraava sivu",
+];
+
+/// Swedish/Finnish national option subset of the G0 character set.
+/// Pairs of (teletext byte, unicode char)
+const NATIONAL_CHARS: [(u8, char); 13] = [
+    (b'#', '#'),
+    (b'$', '¤'),
+    (b'@', 'É'),
+    (b'[', 'Ä'),
+    (b'\\', 'Ö'),
+    (b']', 'Å'),
+    (b'^', 'Ü'),
+    (b'_', '_'),
+    (b'`', 'é'),
+    (b'{', 'ä'),
+    (b'|', 'ö'),
+    (b'}', 'å'),
+    (b'~', 'ü'),
+];
+
+/// Page parsed from a MRG `.tti` file
+#[derive(Debug)]
+pub struct TtiPage {
+    /// Page number as it's written in the file, it's hex, e.g. 0x235 or 0x1A0
+    pub number: u16,
+    /// Page in the readers. Pages with hex digits are not shown to viewers so
+    /// the readers have no page for them
+    pub page: Option<i32>,
+    pub sub_page: i32,
+    pub teletext: TeleText<'static>,
+}
+
+/// Convert teletext byte into unicode. Control codes are shown as spaces
+fn tti_byte_to_char(byte: u8) -> char {
+    let byte = byte & 0x7f;
+    if byte < 0x20 || byte == 0x7f {
+        return ' ';
+    }
+
+    NATIONAL_CHARS
+        .iter()
+        .find(|(tti, _)| *tti == byte)
+        .map(|(_, chr)| *chr)
+        .unwrap_or(byte as char)
+}
+
+/// Convert unicode into teletext byte. Chars without teletext representation become '?'
+fn char_to_tti_byte(chr: char) -> u8 {
+    if let Some((tti, _)) = NATIONAL_CHARS.iter().find(|(_, c)| *c == chr) {
+        return *tti;
+    }
+
+    // Characters replaced by the national option subset cannot be used as is
+    if chr.is_ascii()
+        && !chr.is_ascii_control()
+        && !NATIONAL_CHARS.iter().any(|(t, _)| *t == chr as u8)
+    {
+        return chr as u8;
+    }
+
+    b'?'
+}
+
+/// Hex number read as a decimal number, e.g. 0x235 is 235.
+/// None if the number has the hex digits A-F
+fn decimal_digits(number: u16) -> Option<i32> {
+    format!("{number:x}").parse().ok()
+}
+
+/// Pages of the FL line by their position, None when the slot is not in use.
+/// Empty slots are kept so the links after them don't move
+fn parse_fl_line(value: &[u8]) -> [Option<i32>; FASTEXT_LINK_COUNT] {
+    let fl = String::from_utf8_lossy(value);
+    let mut links = [None; FASTEXT_LINK_COUNT];
+    for (link, page) in links.iter_mut().zip(fl.split(',')) {
+        *link = u16::from_str_radix(page.trim(), 16)
+            .ok()
+            .and_then(decimal_digits)
+            .filter(|page| (100..=899).contains(page));
+    }
+
+    links
+}
+
+/// Decode the text part of OL line
+fn decode_tti_row(row: &[u8]) -> String {
+    let mut text = String::with_capacity(row.len());
+    let mut bytes = row.iter();
+    while let Some(byte) = bytes.next() {
+        if *byte == TTI_ESC {
+            // Escaped control codes are written as `ESC` + code + 0x40
+            if let Some(code) = bytes.next() {
+                text.push(tti_byte_to_char(code.wrapping_sub(0x40)));
+            }
+        } else {
+            text.push(tti_byte_to_char(*byte));
+        }
+    }
+
+    text
+}
+
+impl TtiPage {
+    /// Parse the first page in the `.tti` file.
+    /// `.tti` files are not utf-8 so the data is handled as bytes
+    pub fn parse(data: &[u8]) -> ParserResult<Self> {
+        let mut number: Option<u16> = None;
+        let mut sub_page = 1;
+        let mut title: Option<String> = None;
+        let mut rows: Vec<Option<String>> = Vec::new();
+        let mut fastext = Fastext::default();
+
+        for line in data.split(|b| *b == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.len() < 3 || line[2] != b',' {
+                continue;
+            }
+
+            let (command, value) = (&line[..2], &line[3..]);
+            match command {
+                b"DE" => title = Some(decode_tti_row(value).trim().to_string()),
+                b"PN" => {
+                    // Only the first page of the file is used
+                    if number.is_some() {
+                        break;
+                    }
+                    // PN is the hex page number followed by two digit sub page, e.g. 10001
+                    let pn = String::from_utf8_lossy(value);
+                    let pn = pn.get(0..3).ok_or(ParseErr::InvalidPage)?;
+                    let pn = u16::from_str_radix(pn, 16).map_err(|_| ParseErr::InvalidPage)?;
+                    number = Some(pn);
+                }
+                b"SC" => {
+                    // Sub code is hex too, the sub pages are written with decimal digits.
+                    // Sub code 0000 means the page has no sub pages
+                    let sc = String::from_utf8_lossy(value);
+                    let sc = u16::from_str_radix(sc.trim(), 16).unwrap_or(0);
+                    sub_page = decimal_digits(sc).unwrap_or(sc.into()).max(1);
+                }
+                b"OL" => {
+                    let split = value
+                        .iter()
+                        .position(|b| *b == b',')
+                        .ok_or(ParseErr::InvalidPage)?;
+                    let row = String::from_utf8_lossy(&value[..split]);
+                    let row = row.parse::<usize>().map_err(|_| ParseErr::InvalidPage)?;
+                    if row == 0 || row > TTI_MAX_ROW {
+                        continue;
+                    }
+
+                    if rows.len() < row {
+                        rows.resize(row, None);
+                    }
+                    rows[row - 1] = Some(decode_tti_row(&value[split + 1..]));
+                }
+                b"FL" => {
+                    let links = parse_fl_line(value);
+                    for (pos, key) in FL_KEYS.iter() {
+                        let link = links[*pos].map(|page| page_link(page, page.to_string().into()));
+                        fastext.set(*key, link);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let number = number.ok_or(ParseErr::InvalidPage)?;
+        let page = decimal_digits(number);
+        let middle_rows = rows
+            .iter()
+            .map(|row| row.as_deref().map(text_to_items).unwrap_or_default())
+            .map(|row| row.into_iter().map(HtmlItem::into_owned).collect())
+            .collect();
+
+        let teletext = TeleText {
+            title: title.unwrap_or_else(|| format!("P{number:X}")).into(),
+            page_navigation: page_navigation(
+                YLE_NAVIGATION_LABELS,
+                page.map(|page| page - 1),
+                page.map(|page| page + 1),
+            ),
+            bottom_navigation: fastext.links().map(|(_, link)| link.clone()).collect(),
+            sub_pages: Vec::new(),
+            middle_rows,
+            fastext,
+        };
+
+        Ok(Self {
+            number,
+            page,
+            sub_page,
+            teletext,
+        })
+    }
+}
+
+impl TeleText<'_> {
+    /// Export the page in `.tti` format. Fastext links are written in the FL line
+    pub fn to_tti(&self, page: i32, sub_page: i32) -> Vec<u8> {
+        let mut tti: Vec<u8> = Vec::new();
+        let mut push_line = |command: &str, value: &[u8]| {
+            tti.extend_from_slice(command.as_bytes());
+            tti.push(b',');
+            tti.extend_from_slice(value);
+            tti.extend_from_slice(b"\r\n");
+        };
+
+        let title: Vec<u8> = self.title.chars().map(char_to_tti_byte).collect();
+        push_line("DE", &title);
+        push_line("PN", format!("{page}{:02}", sub_page % 100).as_bytes());
+        push_line("SC", format!("{sub_page:04}").as_bytes());
+        // Transmit page with the Swedish/Finnish character set
+        push_line("PS", b"8000");
+
+        for (idx, row) in self.middle_rows.iter().take(TTI_MAX_ROW).enumerate() {
+            let text: String = row.iter().map(HtmlItem::text).collect();
+
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            let mut line = format!("{},", idx + 1).into_bytes();
+            line.extend(
+                text.chars()
+                    .take(MIDDLE_TEXT_MAX_LEN + 1)
+                    .map(char_to_tti_byte),
+            );
+            push_line("OL", &line);
+        }
+
+        let mut links = [None; FASTEXT_LINK_COUNT];
+        for (pos, key) in FL_KEYS.iter() {
+            links[*pos] = self.fastext.get(*key).and_then(link_page);
+        }
+        let fastext: Vec<String> = links
+            .iter()
+            .map(|link| match link {
+                Some(page) => page.to_string(),
+                None => TTI_NO_LINK.to_string(),
+            })
+            .collect();
+        push_line("FL", fastext.join(",").as_bytes());
+
+        tti
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tti(fl: &str) -> Vec<u8> {
+        format!("DE,Tulokset\r\nPN,23502\r\nSC,0002\r\nOL,1,J{{{{KIEKKO\r\nOL,3,HIFK - K{{rp{{t 3-1\r\n{fl}\r\n")
+            .into_bytes()
+    }
+
+    fn row_text(page: &TtiPage, row: usize) -> String {
+        page.teletext.middle_rows[row]
+            .iter()
+            .map(|item| match item {
+                HtmlItem::Text(text) => &**text,
+                HtmlItem::Link(link) => &*link.inner_text,
+            })
+            .collect()
+    }
+
+    fn link_pages(page: &TtiPage) -> Vec<Option<i32>> {
+        page.teletext
+            .bottom_navigation
+            .iter()
+            .map(link_page)
+            .collect()
+    }
+
+    fn fastext_pages(page: &TtiPage) -> Vec<Option<i32>> {
+        FL_KEYS
+            .iter()
+            .map(|(_, key)| page.teletext.fastext.get(*key).and_then(link_page))
+            .collect()
+    }
+
+    #[test]
+    fn tti_round_trip() {
+        let fl = "FL,101,102,103,104,8ff,100";
+        let page = TtiPage::parse(&tti(fl)).unwrap();
+        assert_eq!(page.number, 0x235);
+        assert_eq!((page.page, page.sub_page), (Some(235), 2));
+        assert_eq!(page.teletext.title, "Tulokset");
+        assert_eq!(row_text(&page, 0), "JääKIEKKO");
+        assert!(row_text(&page, 1).is_empty());
+        assert_eq!(
+            link_pages(&page),
+            [Some(101), Some(102), Some(103), Some(104), Some(100)]
+        );
+
+        let exported = page.teletext.to_tti(235, page.sub_page);
+        let text = String::from_utf8_lossy(&exported);
+        assert!(text.contains("PN,23502\r\n"));
+        assert!(text.contains(&format!("{fl}\r\n")));
+
+        let imported = TtiPage::parse(&exported).unwrap();
+        assert_eq!((imported.page, imported.sub_page), (Some(235), 2));
+        assert_eq!(row_text(&imported, 2).trim_end(), "HIFK - Kärpät 3-1");
+        assert_eq!(link_pages(&imported), link_pages(&page));
+    }
+
+    #[test]
+    fn tti_keeps_fastext_positions() {
+        let fl = "FL,8ff,200,8ff,8ff,8ff,100";
+        let page = TtiPage::parse(&tti(fl)).unwrap();
+        assert_eq!(
+            fastext_pages(&page),
+            [None, Some(200), None, None, Some(100)]
+        );
+
+        let exported = page.teletext.to_tti(235, page.sub_page);
+        assert!(String::from_utf8_lossy(&exported).contains(&format!("{fl}\r\n")));
+    }
+
+    #[test]
+    fn tti_hex_page_numbers() {
+        let page = TtiPage::parse(b"PN,1A001\r\nSC,0010\r\nOL,1,Test\r\n").unwrap();
+        assert_eq!(page.number, 0x1a0);
+        assert_eq!(page.page, None);
+        // Sub pages are written with decimal digits in the hex sub code
+        assert_eq!(page.sub_page, 10);
+        assert_eq!(page.teletext.title, "P1A0");
+        assert_eq!(row_text(&page, 0), "Test");
+
+        assert!(TtiPage::parse(b"PN,1G001\r\nOL,1,Test\r\n").is_err());
+    }
+}
@@ -2,8 +2,11 @@ use base64::{engine::general_purpose, Engine as _};
 
 use super::common::{
     decode_string, HtmlImageArea, HtmlLink, HtmlLoader, HtmlParser, HtmlText, InnerResult,
-    ParseErr, ParseState, ParserResult, TagType,
+    ParseErr, ParseState, ParserResult,
 };
+use super::fastext::{Fastext, FastextKey};
+use super::html::Token;
+use super::provider::ProviderPage;
 
 extern crate html_escape;
 
@@ -53,6 +56,12 @@ struct IJData {
     content: IJDataContent,
 }
 
+/// Only the meta is read first since error responses don't have the data
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct ImageJsonMeta {
+    meta: IJMeta,
+}
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct ImageJson {
     meta: IJMeta,
@@ -61,125 +70,148 @@ struct ImageJson {
 }
 
 /// Contains the fields of Yle image site
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct YleImage {
-    pub title: HtmlText,
+    pub title: HtmlText<'static>,
+    /// Text shown in the image, empty if the page doesn't have it
+    pub text: String,
+    /// The png is in the response, so it's not repeated in the json
+    #[serde(skip)]
     pub image: Vec<u8>,
     pub image_map: Vec<HtmlImageArea>,
-    pub botton_navigation: Vec<Option<HtmlLink>>,
+    pub botton_navigation: Vec<Option<HtmlLink<'static>>>,
+    pub fastext: Fastext<'static>,
 }
 
 impl YleImage {
-    fn parse_image<'a>(state: &'a mut ParseState<'a>) -> InnerResult<'a, Vec<u8>> {
-        let state = Self::skip_next_string(state, "data:image/png;base64,")?.0;
-        let image_end = state.current.find('"').ok_or(ParseErr::InvalidPage)?;
+    fn parse_image<'s, 'a>(state: &'s mut ParseState<'a>) -> InnerResult<'s, 'a, Vec<u8>> {
+        let (state, img) = Self::next_start_tag(state, "img")?;
+        let data = img
+            .attribute("src")
+            .and_then(|src| src.strip_prefix("data:image/png;base64,"))
+            .ok_or(ParseErr::InvalidPage)?;
         let image = general_purpose::STANDARD
-            .decode(&state.current[..image_end])
-            .unwrap();
+            .decode(data)
+            .map_err(|_| ParseErr::InvalidPage)?;
 
         Ok((state, image))
     }
 
-    fn parse_bottom_nav_link<'a>(mut state: &'a mut ParseState<'a>) -> InnerResult<'a, HtmlLink> {
-        state = Self::skip_next_string(state, "data-yle-ttv-page-name=\"")?.0;
-        let url_end = state.current.find('"').ok_or(ParseErr::InvalidPage)?;
-        let url = state.current[..url_end].to_string();
-
-        // Go to the end of the link tag
-        state = Self::skip_next_char(state, '>')?.0;
-        let inner_text = if state.current.starts_with('<') {
-            // Skip the span open
-            state = Self::skip_next_char(state, '>')?.0;
-            let span_end = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-            let span_inner = &state.current[..span_end];
-            // Skip the span close
-            state = Self::skip_next_char(state, '>')?.0;
-            let link_start = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-            let span_out = decode_string(&state.current[..link_start]);
-            format!("{} {}", span_inner.trim(), span_out.trim())
-        } else {
-            // Get the string before span
-            let span_start = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-            let span_out = decode_string(&state.current[..span_start]);
-            // Skip the span open
-            state = Self::skip_next_char(state, '>')?.0;
-            let span_end = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-            let span_inner = &state.current[..span_end];
-            format!("{} {}", span_out.trim(), span_inner.trim())
-        };
+    fn parse_bottom_nav_link<'s, 'a>(
+        state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, HtmlLink<'a>> {
+        let (mut state, link) = Self::next_start_tag(state, "a")?;
+        let url = link
+            .attribute("data-yle-ttv-page-name")
+            .ok_or(ParseErr::InvalidPage)?
+            .into();
 
-        state = Self::skip_next_tag(state, "a", true)?.0;
+        // Text is split with a span, e.g. "<span>Seuraava</span> sivu"
+        let mut texts: Vec<HtmlText> = Vec::new();
+        loop {
+            let (new_state, token) = Self::next_token(state)?;
+            state = new_state;
+            match token {
+                Token::Text(text) if !text.trim().is_empty() => {
+                    texts.push(decode_string(text.trim()));
+                }
+                token if token.is_end("a") => break,
+                _ => {}
+            }
+        }
+
+        let inner_text = match texts.len() {
+            1 => texts.remove(0),
+            _ => texts.join(" ").into(),
+        };
         Ok((state, HtmlLink { url, inner_text }))
     }
 
-    fn parse_bottom_navigation<'a>(
-        mut state: &'a mut ParseState<'a>,
-    ) -> InnerResult<'a, Vec<Option<HtmlLink>>> {
+    fn parse_bottom_navigation<'s, 'a>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<Option<HtmlLink<'a>>>> {
         let mut nav_links: Vec<Option<HtmlLink>> = Vec::new();
-        while !state.current.is_empty() {
-            state = Self::skip_next_char(state, '<')?.0;
-            let tag = Self::get_tag_type(state.current);
-            match tag {
+        while let Some(token) = Self::peek_token(state.current) {
+            match token {
                 // Spans without link are the hidden navs
-                TagType::Span => {
+                token if token.is_start("span") => {
                     state = Self::skip_next_tag(state, "span", true)?.0;
                     nav_links.push(None);
                 }
                 // Div is the text page input, but we hadle it in title, like in text version
-                TagType::Div => {
-                    state = Self::skip_next_tag(state, "form", true)?.0;
+                token if token.is_start("div") => {
                     state = Self::skip_next_tag(state, "div", true)?.0;
                 }
                 // Links contain the actual pages
-                TagType::Link => {
+                token if token.is_start("a") => {
                     let (new_state, link) = Self::parse_bottom_nav_link(state)?;
                     state = new_state;
                     nav_links.push(Some(link));
                 }
+                Token::Text(text) if text.trim().is_empty() => {
+                    state = Self::next_token(state)?.0;
+                }
                 // Everything else is invalid
                 _ => return Err(ParseErr::InvalidPage),
             }
-
-            state.current = if let Some(chr_start) = state.current.find('<') {
-                &state.current[chr_start..]
-            } else {
-                ""
-            };
         }
 
         Ok((state, nav_links))
     }
 
-    fn parse_image_map<'a>(
-        mut state: &'a mut ParseState<'a>,
-    ) -> InnerResult<'a, Vec<HtmlImageArea>> {
+    /// Image pages don't have fastext links so use the page navigation instead,
+    /// like TV sets do when the page doesn't define the links
+    fn navigation_to_fastext<'a>(navigation: &[Option<HtmlLink<'a>>]) -> Fastext<'a> {
+        let mut fastext = Fastext::default();
+        // Navigation is in order of previous page, previous sub page,
+        // next sub page and next page
+        let keys = [
+            FastextKey::Red,
+            FastextKey::Yellow,
+            FastextKey::Cyan,
+            FastextKey::Green,
+        ];
+        for (key, link) in keys.iter().zip(navigation.iter()) {
+            fastext.set(*key, link.clone());
+        }
+
+        fastext.index = Some(HtmlLink {
+            url: "100_0001".into(),
+            inner_text: "Yle Teksti-TV".into(),
+        });
+        fastext
+    }
+
+    fn parse_image_map<'s, 'a>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<HtmlImageArea>> {
         state = Self::skip_next_tag(state, "map", false)?.0;
-        state = Self::skip_next_char(state, '<')?.0;
 
         let mut map: Vec<HtmlImageArea> = Vec::new();
-        while !state.current.starts_with("/map>") {
-            let area_line = state.current.find('>').unwrap();
-            let area_line = &state.current[..area_line];
+        loop {
+            let (new_state, token) = Self::next_token(state)?;
+            state = new_state;
+            let area = match token {
+                Token::Start(tag) if tag.is("area") => tag,
+                token if token.is_end("map") => break,
+                _ => continue,
+            };
 
             // Not all areas contain the new page so we can just ignore them
-            if area_line.contains("data-yle-ttv-page-name") {
-                let mut line_state = ParseState::new(area_line);
-                let line_state = Self::skip_next_string(&mut line_state, "coords=\"")?.0;
-                let coord_end = line_state.current.find('"').ok_or(ParseErr::InvalidPage)?;
-                let coord = line_state.current[..coord_end].to_string();
-                let coords: Vec<f32> = coord
+            if let Some(page) = area.attribute("data-yle-ttv-page-name") {
+                let coords = area
+                    .attribute("coords")
+                    .unwrap_or("")
                     .split(',')
-                    .map(|c| c.parse::<f32>().unwrap())
-                    .collect();
-                let line_state = Self::skip_next_string(line_state, "data-yle-ttv-page-name=\"")?.0;
-                let page_end = line_state.current.find('"').ok_or(ParseErr::InvalidPage)?;
-                let page = line_state.current[..page_end].to_string();
-                map.push(HtmlImageArea::new(
-                    coords[0], coords[1], coords[2], coords[3], page,
-                ));
+                    .map(|c| c.trim().parse::<f32>())
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|_| ParseErr::InvalidPage)?;
+                if let [x1, y1, x2, y2] = coords[..] {
+                    map.push(HtmlImageArea::new(x1, y1, x2, y2, page.to_string()));
+                } else {
+                    return Err(ParseErr::InvalidPage);
+                }
             }
-
-            state = Self::skip_next_char(state, '<')?.0;
         }
 
         Ok((state, map))
@@ -190,23 +222,46 @@ impl HtmlParser for YleImage {
     fn new() -> Self {
         Self {
             title: "".into(),
+            text: String::new(),
             image: Vec::new(),
             image_map: Vec::new(),
             botton_navigation: Vec::new(),
+            fastext: Default::default(),
         }
     }
 
     fn parse(mut self, loader: HtmlLoader) -> ParserResult<Self> {
+        let response: ImageJsonMeta =
+            serde_json::from_str(&loader.page_data).map_err(|_| ParseErr::InvalidPage)?;
+        // The code is the http status code of the page
+        match response.meta.code.as_str() {
+            "404" => return Err(ParseErr::NotFound),
+            code if code.starts_with('5') => return Err(ParseErr::Maintenance),
+            _ => {}
+        }
+
         let json: ImageJson =
             serde_json::from_str(&loader.page_data).map_err(|_| ParseErr::InvalidPage)?;
-        self.title = json.data[0].info.page.label.clone();
-        let mut state = ParseState::new(&json.data[0].content.image);
+        let data = json.data.first().ok_or(ParseErr::InvalidPage)?;
+        self.title = data.info.page.label.clone().into();
+        self.text = data.content.text.clone();
+        let mut state = ParseState::new(&data.content.image);
         self.image = Self::parse_image(&mut state)?.1;
-        let mut state = ParseState::new(&json.data[0].content.pagination);
-        self.botton_navigation = Self::parse_bottom_navigation(&mut state)?.1;
-        let mut state = ParseState::new(&json.data[0].content.image_map);
+        let mut state = ParseState::new(&data.content.pagination);
+        // Links borrow the json so they are copied
+        self.botton_navigation = Self::parse_bottom_navigation(&mut state)?
+            .1
+            .into_iter()
+            .map(|link| link.map(HtmlLink::into_owned))
+            .collect();
+        self.fastext = Self::navigation_to_fastext(&self.botton_navigation);
+        let mut state = ParseState::new(&data.content.image_map);
         self.image_map = Self::parse_image_map(&mut state)?.1;
 
         Ok(self)
     }
+
+    fn parse_with_provider(self, loader: HtmlLoader, page: &ProviderPage) -> ParserResult<Self> {
+        page.parse_image_page(loader)
+    }
 }
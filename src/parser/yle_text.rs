@@ -1,9 +1,15 @@
 use std::result::Result;
+use std::sync::Arc;
+
+use self_cell::self_cell;
 
 use super::common::{
-    decode_string, HtmlItem, HtmlLink, HtmlLoader, HtmlParser, HtmlText, InnerResult, ParseErr,
-    ParseState, ParserResult, TagType,
+    decode_string, error_page, HtmlItem, HtmlLink, HtmlLoader, HtmlParser, HtmlText, InnerResult,
+    ParseErr, ParseState, ParserResult,
 };
+use super::fastext::Fastext;
+use super::html::Token;
+use super::provider::ProviderPage;
 
 extern crate html_escape;
 
@@ -13,63 +19,232 @@ const BOTTOM_NAVIGATION_SIZE: usize = 6;
 const HTML_LINK_SIZE: usize = 12;
 /// The middle texts are always maximum of 39 characters
 pub const MIDDLE_TEXT_MAX_LEN: usize = 39;
+/// Texts of the pages Yle shows instead of teletext pages, in lower case
+const NOT_FOUND_TEXTS: [&str; 2] = ["sivua ei löytynyt", "sivua ei ole olemassa"];
+const MAINTENANCE_TEXTS: [&str; 3] = ["huoltokatko", "huoltotöi", "häiriö"];
+
+/// Trim spaces and `&nbsp;` from both ends without decoding the text
+fn trim_nbsp(text: &str) -> &str {
+    let mut text = text.trim();
+    while let Some(trimmed) = text
+        .strip_prefix("&nbsp;")
+        .or_else(|| text.strip_suffix("&nbsp;"))
+    {
+        text = trimmed.trim();
+    }
+
+    text
+}
+
+/// Contains the fields of Yle telext site.
+/// Texts borrow the html of the page, see `TeleText::parse_str`
+#[derive(Debug, Default, serde::Serialize)]
+pub struct TeleText<'a> {
+    pub title: HtmlText<'a>,
+    pub page_navigation: Vec<HtmlItem<'a>>,
+    pub bottom_navigation: Vec<HtmlLink<'a>>,
+    pub sub_pages: Vec<HtmlItem<'a>>,
+    pub middle_rows: Vec<Vec<HtmlItem<'a>>>,
+    pub fastext: Fastext<'a>,
+}
+
+/// Page of a teletext service that provides its pages as text.
+/// All text pages are parsed into `TeleText` so the providers can share the same reader
+pub trait TextPage {
+    fn teletext(&self) -> &TeleText<'_>;
+    fn from_teletext(teletext: TeleText<'static>) -> Self
+    where
+        Self: Sized;
+}
+
+impl<'a> TextPage for TeleText<'a> {
+    fn teletext(&self) -> &TeleText<'_> {
+        self
+    }
+
+    fn from_teletext(teletext: TeleText<'static>) -> Self {
+        teletext
+    }
+}
+
+self_cell!(
+    /// Yle page that keeps the html it was parsed from.
+    /// Texts of the page borrow the html instead of being copied like in `TeleText::into_owned`
+    pub struct YleText {
+        owner: Arc<str>,
+        #[covariant]
+        dependent: TeleText,
+    }
+
+    impl {Debug}
+);
+
+impl TextPage for YleText {
+    fn teletext(&self) -> &TeleText<'_> {
+        self.borrow_dependent()
+    }
 
-/// Contains the fields of Yle telext site
-#[derive(Debug)]
-pub struct TeleText {
-    pub title: HtmlText,
-    pub page_navigation: Vec<HtmlItem>,
-    pub bottom_navigation: Vec<HtmlLink>,
-    pub sub_pages: Vec<HtmlItem>,
-    pub middle_rows: Vec<Vec<HtmlItem>>,
+    fn from_teletext(teletext: TeleText<'static>) -> Self {
+        Self::new("".into(), |_| teletext)
+    }
+}
+
+impl HtmlParser for YleText {
+    fn new() -> Self {
+        Self::from_teletext(TeleText::default())
+    }
+
+    fn parse(self, loader: HtmlLoader) -> ParserResult<Self> {
+        Self::try_new(loader.page_data, |html| TeleText::parse_str(html))
+    }
+
+    fn parse_with_provider(self, loader: HtmlLoader, page: &ProviderPage) -> ParserResult<Self> {
+        page.parse_text(loader).map(Self::from_teletext)
+    }
 }
 
-impl TeleText {
+impl<'a> TeleText<'a> {
+    /// Parse the page without copying the texts from the html.
+    /// Texts are only allocated when html entities need to be decoded
+    pub fn parse_str(html: &'a str) -> ParserResult<Self> {
+        // Not found and maintenance pages are only looked for if the page isn't valid
+        Self::parse_page(html).map_err(|_| error_page(html, &NOT_FOUND_TEXTS, &MAINTENANCE_TEXTS))
+    }
+
+    fn parse_page(html: &'a str) -> ParserResult<Self> {
+        let mut teletext = Self::default();
+        let mut state = ParseState::new(html);
+        let (state, title) = Self::parse_title(&mut state)?;
+        teletext.title = title;
+        let (state, top_nav) = Self::parse_top_navigation(state)?;
+        teletext.page_navigation = top_nav;
+        let (state, middle) = Self::parse_middle(state)?;
+        teletext.middle_rows = middle;
+        let (state, sub_pages) = Self::parse_sub_pages(state)?;
+        teletext.sub_pages = sub_pages;
+        teletext.bottom_navigation = Self::parse_bottom_navigation(state)?.1;
+        // Bottom navigation has the four main categories and the index page
+        // in the same way as the fastext keys
+        teletext.fastext = Fastext::from_links(&teletext.bottom_navigation);
+
+        Ok(teletext)
+    }
+
+    /// Copy the borrowed texts so the page can outlive the html
+    pub fn into_owned(self) -> TeleText<'static> {
+        let items = |items: Vec<HtmlItem>| -> Vec<HtmlItem<'static>> {
+            items.into_iter().map(HtmlItem::into_owned).collect()
+        };
+
+        TeleText {
+            title: HtmlText::Owned(self.title.into_owned()),
+            page_navigation: items(self.page_navigation),
+            bottom_navigation: self
+                .bottom_navigation
+                .into_iter()
+                .map(HtmlLink::into_owned)
+                .collect(),
+            sub_pages: items(self.sub_pages),
+            middle_rows: self.middle_rows.into_iter().map(items).collect(),
+            fastext: self.fastext.into_owned(),
+        }
+    }
+
+    /// Number of sub-pages listed at the bottom of the page, e.g. "1 2 3".
+    /// None if the page doesn't have sub-pages
+    pub fn sub_page_count(&self) -> Option<i32> {
+        self.sub_pages
+            .iter()
+            .flat_map(|item| item.text().split_whitespace())
+            .filter_map(|num| num.parse::<i32>().ok())
+            .max()
+    }
+
+    /// Indexes of the middle rows that are different in the `previous` version of the page.
+    /// Rows are compared by their text so only changes the user can see are found
+    pub fn changed_rows(&self, previous: &TeleText) -> Vec<usize> {
+        let row_text = |row: &[HtmlItem]| row.iter().map(HtmlItem::text).collect::<String>();
+        self.middle_rows
+            .iter()
+            .enumerate()
+            .filter(|(idx, row)| match previous.middle_rows.get(*idx) {
+                Some(old) => row_text(row) != row_text(old),
+                None => !row.is_empty(),
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Text of each middle row
+    pub fn middle_texts(&self) -> Vec<String> {
+        self.middle_rows
+            .iter()
+            .map(|row| row.iter().map(HtmlItem::text).collect())
+            .collect()
+    }
+
+    /// Page as plain text in the same order as the reader shows it.
+    /// Navigation items are separated with " | " like in the reader
+    pub fn to_plain_text(&self) -> String {
+        let row_text = |row: &[HtmlItem]| row.iter().map(HtmlItem::text).collect::<String>();
+        let page_navigation: Vec<&str> = self.page_navigation.iter().map(HtmlItem::text).collect();
+        let bottom_navigation: Vec<&str> = self
+            .bottom_navigation
+            .iter()
+            .map(|link| link.inner_text.as_ref())
+            .collect();
+
+        let mut lines: Vec<String> = vec![self.title.to_string(), page_navigation.join(" | ")];
+        lines.extend(self.middle_rows.iter().map(|row| row_text(row)));
+        if !self.sub_pages.is_empty() {
+            lines.push(row_text(&self.sub_pages));
+        }
+        lines.push(bottom_navigation.join(" | "));
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+}
+
+impl<'a> TeleText<'a> {
     /// Parse the title part of yle teletext page
-    fn parse_title<'a>(state: &'a mut ParseState<'a>) -> InnerResult<'a, HtmlText> {
+    fn parse_title<'s>(state: &'s mut ParseState<'a>) -> InnerResult<'s, 'a, HtmlText<'a>> {
         // Title is always between `<big></big>`
         let state = Self::skip_next_tag(state, "big", false)?.0;
-        // Text ends at the start of the next html tag
-        let text_end = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-        // self.title = decode_string(&state.current()[0..text_end]);
-        let title = decode_string(&state.current[0..text_end]);
-        Ok((state, title))
+        Self::parse_inner_text(state, "big")
     }
+
     /// Parse the top navigation par tof yle teletext page
-    fn parse_top_navigation<'a>(
-        mut state: &'a mut ParseState<'a>,
-    ) -> InnerResult<'a, Vec<HtmlItem>> {
-        state = Self::skip_next_tag(state, "SPAN", false)?.0;
+    fn parse_top_navigation<'s>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<HtmlItem<'a>>> {
+        state = Self::skip_next_tag(state, "span", false)?.0;
 
         let mut navigation: Vec<HtmlItem> = Vec::new();
-        for ii in 0..TOP_NAVIGATION_SIZE {
-            let last_link = ii == TOP_NAVIGATION_SIZE - 1;
-            if ii != 0 {
-                state = Self::skip_next_string(state, "&nbsp;")?.0;
-            }
-
-            match Self::get_tag_type(state.current) {
-                TagType::Link => {
+        loop {
+            match Self::peek_token(state.current) {
+                Some(token) if token.is_start("a") => {
                     let (new_state, link) = Self::parse_current_link(state)?;
                     state = new_state;
                     navigation.push(HtmlItem::Link(link));
                 }
-                _ => {
-                    // There is only texts and links in top nav so if
-                    // it's not a link, parse it as a text
-
-                    // The text ends either in &nbsp; or start of a html tag
-                    let endchar = if last_link { '<' } else { '&' };
-                    let text_end = state.current.find(endchar).ok_or(ParseErr::InvalidPage)?;
-                    let text = state.current[..text_end].to_string();
-                    navigation.push(HtmlItem::Text(text));
-                    state = Self::skip_next_char(state, endchar)?.0;
+                Some(Token::Text(text)) => {
+                    // Items are separated with "&nbsp;|&nbsp;" so the texts
+                    // between the links can contain multiple items
+                    let items = text.split('|').map(trim_nbsp).filter(|t| !t.is_empty());
+                    navigation.extend(items.map(|item| HtmlItem::Text(decode_string(item))));
+                    state = Self::next_token(state)?.0;
                 }
+                Some(token) if token.is_end("span") => break,
+                // Formatting inside the navigation is ignored
+                Some(_) => state = Self::next_token(state)?.0,
+                None => return Err(ParseErr::InvalidPage),
             }
+        }
 
-            if !last_link {
-                state = Self::skip_next_string(state, "nbsp;|")?.0;
-            }
+        if navigation.len() != TOP_NAVIGATION_SIZE {
+            return Err(ParseErr::InvalidPage);
         }
 
         Ok((state, navigation))
@@ -77,9 +252,9 @@ impl TeleText {
 
     /// If the current link isn't a valid teletext link, this will Err
     /// and return a `HtmlText` instead of the `HtmlLink`
-    fn parse_middle_link<'a>(
-        mut state: &'a mut ParseState<'a>,
-    ) -> InnerResult<'a, Result<HtmlLink, HtmlText>> {
+    fn parse_middle_link<'s>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Result<HtmlLink<'a>, HtmlText<'a>>> {
         let (new_state, link) = Self::parse_current_link(state)?;
         state = new_state;
 
@@ -90,104 +265,96 @@ impl TeleText {
         Ok((state, Ok(link)))
     }
 
-    fn parse_middle<'a>(mut state: &'a mut ParseState<'a>) -> InnerResult<'a, Vec<Vec<HtmlItem>>> {
+    fn parse_middle<'s>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<Vec<HtmlItem<'a>>>> {
         state = Self::skip_next_tag(state, "pre", false)?.0;
 
-        let mut middle_rows: Vec<Vec<HtmlItem>> = Vec::new();
-        while !state.current.starts_with("</pre>") {
-            let mut row: Vec<HtmlItem> = Vec::new();
-            // ref the current string
-            let parse_text = state.current;
-            // each middle row is in a regular line so lets find the new line
-            // so we can now the size of it, so we can skip the line after parsing
-            let line_len = state.current.find('\r').ok_or(ParseErr::InvalidPage)?;
-            // Temporarly ref the current text as the row_text we're parsing
-            state.current = &state.current[..line_len];
-
-            // lines that start with '&' don't actualy contain any text
-            if parse_text.is_empty() || parse_text.starts_with('&') {
-                middle_rows.push(row);
-                state.current = &parse_text[line_len + 2..]; // +2 for "\r\n"
-                continue;
-            }
-
-            while !state.current.is_empty() {
-                match Self::get_tag_type(state.current) {
-                    TagType::Link => {
-                        let (new_state, middle) = Self::parse_middle_link(state)?;
-                        state = new_state;
-                        match middle {
-                            Ok(link) => {
-                                row.push(HtmlItem::Link(link));
-                            }
-                            Err(text) => {
-                                row.push(HtmlItem::Text(text));
-                            }
-                        }
+        // Each line inside the `<pre>` is a row
+        let mut middle_rows: Vec<Vec<HtmlItem>> = vec![Vec::new()];
+        loop {
+            let row = middle_rows.last_mut().ok_or(ParseErr::InvalidPage)?;
+            match Self::peek_token(state.current) {
+                Some(token) if token.is_start("a") => {
+                    let (new_state, middle) = Self::parse_middle_link(state)?;
+                    state = new_state;
+                    match middle {
+                        Ok(link) => row.push(HtmlItem::Link(link)),
+                        Err(text) => row.push(HtmlItem::Text(text)),
                     }
-                    _ => {
-                        // There is only texts and links in middle so if
-                        // it's not a link, parse it as a text
-
-                        let link_start = state.current.find('<');
-                        let row_str = if let Some(start) = link_start {
-                            // link_start is some so we can unwrap it here safely
-                            decode_string(&state.current[..start])
-                        } else {
-                            // If '<' is not found, the rest of the line
-                            // is the string, since there are no more links
-                            decode_string(state.current)
-                        };
-
-                        if let Some(start) = link_start {
-                            state.current = &state.current[start..];
-                        } else {
-                            state.current = "";
+                }
+                Some(Token::Text(text)) => {
+                    for (idx, line) in text.split('\n').enumerate() {
+                        if idx != 0 {
+                            middle_rows.push(Vec::new());
                         }
 
-                        row.push(HtmlItem::Text(row_str));
+                        let line = line.trim_end_matches('\r');
+                        if !line.is_empty() {
+                            let row = middle_rows.last_mut().ok_or(ParseErr::InvalidPage)?;
+                            row.push(HtmlItem::Text(decode_string(line)));
+                        }
                     }
+                    state = Self::next_token(state)?.0;
                 }
+                Some(token) if token.is_end("pre") => break,
+                // There is only texts and links in middle so other tags are ignored
+                Some(_) => state = Self::next_token(state)?.0,
+                None => return Err(ParseErr::InvalidPage),
             }
+        }
+
+        // The last line ends right before `</pre>`
+        if matches!(middle_rows.last(), Some(row) if row.is_empty()) {
+            middle_rows.pop();
+        }
 
-            // Pushed the crated row and make the text refer
-            // to the whole document again
-            middle_rows.push(row);
-            state.current = &parse_text[line_len + 2..]; // +2 for "\r\n"
+        // Lines with only spaces, like "&nbsp;", don't actualy contain any text
+        for row in middle_rows.iter_mut() {
+            if row
+                .iter()
+                .all(|item| matches!(item, HtmlItem::Text(text) if text.trim().is_empty()))
+            {
+                row.clear();
+            }
         }
 
         Ok((state, middle_rows))
     }
 
-    fn parse_sub_pages<'a>(mut state: &'a mut ParseState<'a>) -> InnerResult<'a, Vec<HtmlItem>> {
+    fn parse_sub_pages<'s>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<HtmlItem<'a>>> {
         state = Self::skip_next_tag(state, "p", false)?.0;
 
         let mut sub_pages: Vec<HtmlItem> = Vec::new();
-        while !state.current.starts_with("</p>") {
-            match Self::get_tag_type(state.current) {
-                TagType::Font => {
+        loop {
+            match Self::peek_token(state.current) {
+                // Font contains the "Alasivut:" label
+                Some(token) if token.is_start("font") => {
                     state = Self::skip_next_tag(state, "font", true)?.0;
                 }
-                TagType::Link => {
+                Some(token) if token.is_start("a") => {
                     let (new_state, link) = Self::parse_current_link(state)?;
                     state = new_state;
                     sub_pages.push(HtmlItem::Link(link));
                 }
-                _ => {
-                    let link_start = state.current.find('<').ok_or(ParseErr::InvalidPage)?;
-                    let row_str = state.current[..link_start].to_string();
-                    sub_pages.push(HtmlItem::Text(row_str));
-                    state.current = &state.current[link_start..];
+                Some(Token::Text(text)) => {
+                    sub_pages.push(HtmlItem::Text(decode_string(text)));
+                    state = Self::next_token(state)?.0;
                 }
+                Some(token) if token.is_end("p") => break,
+                Some(_) => state = Self::next_token(state)?.0,
+                None => return Err(ParseErr::InvalidPage),
             }
         }
 
         Ok((state, sub_pages))
     }
 
-    fn parse_bottom_navigation<'a>(
-        mut state: &'a mut ParseState<'a>,
-    ) -> InnerResult<'a, Vec<HtmlLink>> {
+    fn parse_bottom_navigation<'s>(
+        mut state: &'s mut ParseState<'a>,
+    ) -> InnerResult<'s, 'a, Vec<HtmlLink<'a>>> {
         state = Self::skip_next_tag(state, "p", false)?.0;
         let mut links: Vec<HtmlLink> = Vec::new();
         for _ in 0..BOTTOM_NAVIGATION_SIZE {
@@ -200,29 +367,16 @@ impl TeleText {
     }
 }
 
-impl HtmlParser for TeleText {
-    fn new() -> TeleText {
-        TeleText {
-            title: "".to_string(),
-            page_navigation: vec![],
-            bottom_navigation: vec![],
-            sub_pages: vec![],
-            middle_rows: vec![],
-        }
+impl<'a> HtmlParser for TeleText<'a> {
+    fn new() -> Self {
+        Self::default()
     }
 
-    fn parse(mut self, loader: HtmlLoader) -> ParserResult<Self> {
-        let mut state = ParseState::new(&loader.page_data);
-        let (state, title) = Self::parse_title(&mut state)?;
-        self.title = title;
-        let (state, top_nav) = Self::parse_top_navigation(state)?;
-        self.page_navigation = top_nav;
-        let (state, middle) = Self::parse_middle(state)?;
-        self.middle_rows = middle;
-        let (state, sub_pages) = Self::parse_sub_pages(state)?;
-        self.sub_pages = sub_pages;
-        self.bottom_navigation = Self::parse_bottom_navigation(state)?.1;
+    fn parse(self, loader: HtmlLoader) -> ParserResult<Self> {
+        TeleText::parse_str(&loader.page_data).map(TeleText::into_owned)
+    }
 
-        Ok(self)
+    fn parse_with_provider(self, loader: HtmlLoader, page: &ProviderPage) -> ParserResult<Self> {
+        page.parse_text(loader)
     }
 }
@@ -0,0 +1,38 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+
+use yle_tekstitv_gui::parser::HtmlItem;
+
+pub fn row_text(row: &[HtmlItem]) -> String {
+    row.iter().map(HtmlItem::text).collect()
+}
+
+pub fn row_links<'a>(row: &'a [HtmlItem]) -> Vec<&'a str> {
+    row.iter()
+        .filter_map(|item| match item {
+            HtmlItem::Link(link) => Some(link.url.as_ref()),
+            HtmlItem::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Compare the text with `tests/fixtures/snapshots/<name>.txt`.
+/// Run the tests with `UPDATE_SNAPSHOTS=1` to write the snapshots instead
+pub fn assert_snapshot(name: &str, text: &str) {
+    let path = format!("tests/fixtures/snapshots/{name}.txt");
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, text).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing snapshot \"{}\", run with UPDATE_SNAPSHOTS=1", path));
+    assert_eq!(
+        snapshot, text,
+        "\"{}\" doesn't match the snapshot, run with UPDATE_SNAPSHOTS=1 if the change is intended",
+        path
+    );
+}
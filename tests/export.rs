@@ -0,0 +1,66 @@
+use yle_tekstitv_gui::parser::{
+    ExportFormat, HtmlColors, HtmlLoader, HtmlParser, TeleText, EXPORT_COLUMNS,
+};
+
+fn export(format: ExportFormat) -> String {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/100.htm");
+    let page = TeleText::new().parse(loader).unwrap();
+    page.export(format, 100, 1, &HtmlColors::default())
+}
+
+#[test]
+fn export_text() {
+    let text = export(ExportFormat::Text);
+
+    assert!(text.starts_with("P100"));
+    assert!(text.lines().next().unwrap().contains("YLE TEKSTI-TV"));
+    assert!(text.contains(" 103 Myrskyvaroitus & tulvat\n"));
+    assert!(text
+        .lines()
+        .all(|line| line.chars().count() <= EXPORT_COLUMNS));
+}
+
+#[test]
+fn export_markdown() {
+    let text = export(ExportFormat::Markdown);
+
+    assert!(text.starts_with("# P100 YLE TEKSTI-TV\n"));
+    assert!(text.contains("[103](103)"));
+    // Rows end with hard line breaks
+    assert!(text.contains("tulvat  \n"));
+}
+
+#[test]
+fn export_html() {
+    let text = export(ExportFormat::Html);
+
+    assert!(text.starts_with("<!DOCTYPE html>"));
+    assert!(text.contains("<title>P100 YLE TEKSTI-TV</title>"));
+    assert!(text.contains("href=\"103_0001.htm\""));
+    assert!(text.contains("Myrskyvaroitus &amp; tulvat"));
+    assert!(text.contains("background: #000000;"));
+}
+
+#[test]
+fn export_format_names() {
+    assert_eq!(ExportFormat::from_name("md"), Some(ExportFormat::Markdown));
+    assert_eq!(ExportFormat::from_name("HTML"), Some(ExportFormat::Html));
+    assert_eq!(ExportFormat::from_name("pdf"), None);
+    for format in ExportFormat::ALL {
+        assert_eq!(ExportFormat::from_name(format.extension()), Some(format));
+    }
+}
+
+#[test]
+fn clipboard_text() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/100.htm");
+    let page = TeleText::new().parse(loader).unwrap();
+
+    let text = page.clipboard_text(100, 1, true);
+    assert!(text.starts_with("P100  YLE TEKSTI-TV\n\n"));
+    assert!(text.contains("\n 103 Myrskyvaroitus & tulvat\n"));
+
+    let text = page.clipboard_text(100, 1, false);
+    assert!(!text.contains("P100"));
+    assert!(text.lines().all(|line| line == line.trim_end()));
+}
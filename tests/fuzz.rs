@@ -0,0 +1,59 @@
+//! Minimized inputs that crashed the parsers, see the fuzz targets in `fuzz/`
+use std::fs;
+
+use yle_tekstitv_gui::parser::common::ParseErr;
+use yle_tekstitv_gui::parser::{
+    DrText, HtmlLoader, HtmlParser, SvtText, TeleText, YleImage, YleText,
+};
+use yle_tekstitv_gui::TelePager;
+
+fn corpus(dir: &str) -> Vec<String> {
+    let mut files: Vec<String> = fs::read_dir(format!("tests/fixtures/fuzz/{dir}"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path().display().to_string())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn yle_text_corpus() {
+    for file in corpus("yle_text") {
+        let loader = HtmlLoader::new(&file);
+        let err = TeleText::new().parse(loader).unwrap_err();
+        assert_eq!(err, ParseErr::InvalidPage, "{}", file);
+    }
+}
+
+#[test]
+fn yle_image_corpus() {
+    for file in corpus("yle_image") {
+        let loader = HtmlLoader::new(&file);
+        let err = YleImage::new().parse(loader).unwrap_err();
+        assert_eq!(err, ParseErr::InvalidPage, "{}", file);
+    }
+}
+
+#[test]
+fn page_str_corpus() {
+    let invalid = [
+        "",
+        "10",
+        "100_",
+        "100_000",
+        "ä00_0001",
+        "10ä_0001",
+        "abc_0001",
+    ];
+    for page in invalid {
+        assert!(YleText::from_page_str(page).is_none(), "{:?}", page);
+        assert!(YleImage::from_page_str(page).is_none(), "{:?}", page);
+        assert!(SvtText::from_page_str(page).is_none(), "{:?}", page);
+        assert!(DrText::from_page_str(page).is_none(), "{:?}", page);
+    }
+
+    let page = YleText::from_page_str("190_0002.htm").unwrap();
+    assert_eq!((page.page, page.sub_page), (190, 2));
+    let page = YleImage::from_page_str("190_0002").unwrap();
+    assert_eq!((page.page, page.sub_page), (190, 2));
+}
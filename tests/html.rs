@@ -0,0 +1,73 @@
+use yle_tekstitv_gui::parser::{
+    common::{ParseState, TagType},
+    html::{Token, Tokenizer},
+    HtmlParser, TeleText,
+};
+
+#[test]
+fn tokenize_tags_and_text() {
+    let html = "<!DOCTYPE html><!-- <a href=\"x\"> --><P CLASS=x>Text &amp; more</P><br/>";
+    let tokens: Vec<Token> = Tokenizer::new(html).collect();
+
+    assert_eq!(tokens.len(), 4);
+    assert!(tokens[0].is_start("p"));
+    assert_eq!(tokens[1], Token::Text("Text &amp; more"));
+    assert!(tokens[2].is_end("p"));
+    match tokens[3] {
+        Token::Start(tag) => assert!(tag.is("br") && tag.self_closing),
+        _ => panic!("br should be a start tag"),
+    }
+}
+
+#[test]
+fn tag_names_must_match_fully() {
+    assert_eq!(TeleText::get_tag_type("<pre>"), TagType::Pre);
+    assert_eq!(TeleText::get_tag_type("<p class=\"x\">"), TagType::P);
+    assert_eq!(TeleText::get_tag_type("<area href=\"x\">"), TagType::Area);
+    assert_eq!(TeleText::get_tag_type("<A HREF=\"x\">"), TagType::Link);
+    assert_eq!(TeleText::get_tag_type("<abbr>"), TagType::Unknown);
+    // Texts are not tags even if they start with a tag name
+    assert_eq!(TeleText::get_tag_type("page"), TagType::Unknown);
+
+    let mut state = ParseState::new("<pre>pre</pre><p>p</p>");
+    let state = TeleText::skip_next_tag(&mut state, "p", false).unwrap().0;
+    assert_eq!(state.current, "p</p>");
+}
+
+#[test]
+fn attributes() {
+    let html = "<a class='link' data-x = \"1 > 0\" HREF=101_0001.htm hidden>";
+    let tag = match Tokenizer::new(html).next() {
+        Some(Token::Start(tag)) => tag,
+        _ => panic!("a should be a start tag"),
+    };
+
+    assert_eq!(tag.attribute("href"), Some("101_0001.htm"));
+    assert_eq!(tag.attribute("class"), Some("link"));
+    assert_eq!(tag.attribute("data-x"), Some("1 > 0"));
+    assert_eq!(tag.attribute("hidden"), Some(""));
+    assert_eq!(tag.attribute("title"), None);
+    assert_eq!(tag.attributes().count(), 4);
+}
+
+#[test]
+fn script_content_is_text() {
+    let html = "<script>if (a<b) { x = \"</p>\"; }</SCRIPT><p>";
+    let tokens: Vec<Token> = Tokenizer::new(html).collect();
+
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[1], Token::Text("if (a<b) { x = \"</p>\"; }"));
+    assert!(tokens[2].is_end("script"));
+    assert!(tokens[3].is_start("p"));
+}
+
+#[test]
+fn unclosed_markup() {
+    let tokens: Vec<Token> = Tokenizer::new("1 < 2 <a href=\"x").collect();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], Token::Text("1 < 2 "));
+    match tokens[1] {
+        Token::Start(tag) => assert_eq!(tag.attribute("href"), Some("x")),
+        _ => panic!("a should be a start tag"),
+    }
+}
@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+
+use image::{GenericImageView, Rgb, RgbImage};
+use yle_tekstitv_gui::parser::{
+    Fastext, HtmlColors, HtmlLoader, HtmlParser, ImageFormat, TeleText, YleImage,
+};
+
+fn yle_text() -> TeleText<'static> {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/100.htm");
+    TeleText::new().parse(loader).unwrap()
+}
+
+fn yle_image(image: Vec<u8>) -> YleImage {
+    YleImage {
+        title: Cow::Borrowed("100/1"),
+        text: String::new(),
+        image,
+        image_map: Vec::new(),
+        botton_navigation: Vec::new(),
+        fastext: Fastext::default(),
+    }
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let image = RgbImage::from_pixel(width, height, Rgb([10, 20, 30]));
+    let mut png = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut png),
+            image::ImageOutputFormat::Png,
+        )
+        .unwrap();
+    png
+}
+
+#[test]
+fn text_page_png() {
+    let colors = HtmlColors::default();
+    let page = yle_text().to_page_image(100, 1, &colors);
+
+    let small = image::load_from_memory(&page.to_png(1.0).unwrap()).unwrap();
+    let large = image::load_from_memory(&page.to_png(2.0).unwrap()).unwrap();
+    // Size only depends on the scale
+    assert!(small.width() > 40 * 8);
+    assert!(large.width().abs_diff(small.width() * 2) <= 2);
+    assert!(large.height().abs_diff(small.height() * 2) <= 2);
+
+    let large = large.to_rgb8();
+    assert_eq!(large.get_pixel(0, 0).0, colors.background);
+    assert!(large.pixels().any(|pixel| pixel.0 == colors.text));
+    assert!(large.pixels().any(|pixel| pixel.0 == colors.link));
+}
+
+#[test]
+fn text_page_svg() {
+    let colors = HtmlColors::default();
+    let svg = yle_text().to_page_image(100, 1, &colors).to_svg(1.0);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains("fill=\"#000000\""));
+    assert!(svg.contains("fill=\"#119ff4\">103</text>"));
+    assert!(svg.contains("Myrskyvaroitus &amp; tulvat</text>"));
+    assert!(svg.contains(">P100"));
+}
+
+#[test]
+fn image_page_original() {
+    let page = yle_image(png(480, 360));
+    let image = page.export_image(ImageFormat::Png, 100, 1, None).unwrap();
+    assert_eq!(image, page.image);
+}
+
+#[test]
+fn image_page_with_header() {
+    let page = yle_image(png(480, 360));
+    let colors = HtmlColors::default();
+
+    let image = page
+        .export_image(ImageFormat::Png, 100, 1, Some(colors))
+        .unwrap();
+    let image = image::load_from_memory(&image).unwrap();
+    assert_eq!(image.width(), 480);
+    assert!(image.height() > 360);
+    // Header is above the image
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(0, image.height() - 1).0, [10, 20, 30, 255]);
+
+    let svg = page
+        .export_image(ImageFormat::Svg, 100, 1, Some(colors))
+        .unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+    assert!(svg.contains("100/1 YLE TEKSTI-TV</text>"));
+    assert!(svg.contains("width=\"480\" height=\"360\" href=\"data:image/png;base64,"));
+}
@@ -0,0 +1,48 @@
+use yle_tekstitv_gui::parser::print::{to_pdf, to_print_html};
+use yle_tekstitv_gui::parser::PrintPage;
+
+fn pages() -> Vec<PrintPage> {
+    let lines = vec![
+        " Tappara - Ilves   4-2".to_string(),
+        " (jatkoaika)  ".into(),
+    ];
+    vec![
+        PrintPage::new(235, 1, "JÄÄKIEKKO", lines.clone()),
+        PrintPage::new(235, 2, "JÄÄKIEKKO", lines),
+    ]
+}
+
+#[test]
+fn print_pdf() {
+    let pdf = to_pdf(&pages());
+    let text = String::from_utf8_lossy(&pdf);
+
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    assert!(text.ends_with("%%EOF\n"));
+    assert!(text.contains("/Count 2"));
+    assert!(text.contains("/BaseFont /Courier-Bold"));
+    // Latin-1 characters are written with their WinAnsi codes
+    assert!(text.contains("(P235/2  J\\304\\304KIEKKO) Tj"));
+    assert!(text.contains("( \\(jatkoaika\\)) Tj"));
+    assert!(text.contains("(2/2) Tj"));
+
+    // Every object is where the cross-reference table says
+    let startxref = text.rfind("startxref\n").unwrap();
+    let xref: usize = text[startxref..].lines().nth(1).unwrap().parse().unwrap();
+    let table = std::str::from_utf8(&pdf[xref..]).unwrap();
+    assert!(table.starts_with("xref\n0 9\n"));
+    for (idx, entry) in table.lines().skip(3).take(8).enumerate() {
+        let offset: usize = entry[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj", idx + 1).as_bytes()));
+    }
+}
+
+#[test]
+fn print_html() {
+    let html = to_print_html(&pages());
+
+    assert!(html.contains("<body onload=\"window.print()\">"));
+    assert!(html.contains("background: #ffffff; color: #000000;"));
+    assert_eq!(html.matches("<pre><b>P235").count(), 2);
+    assert!(html.contains("<pre><b>P235/2  JÄÄKIEKKO</b>\n\n Tappara - Ilves   4-2\n"));
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::{row_links, row_text};
+use yle_tekstitv_gui::parser::common::ParseErr;
+use yle_tekstitv_gui::parser::{
+    DrText, FastextKey, HtmlItem, HtmlLoader, HtmlParser, ProviderPage, ProvidersFile, SvtText,
+    TextPage,
+};
+
+#[test]
+fn svt_text_page() {
+    let loader = HtmlLoader::new("tests/fixtures/svt_text/100.json");
+    let page = SvtText::new().parse(loader).unwrap();
+    let page = page.teletext();
+
+    assert_eq!(page.title, "SVT Text         Tisdag 17 jan 2023");
+    // 8 rows from the first sub page, separator and 2 rows from the second
+    assert_eq!(page.middle_rows.len(), 11);
+    assert_eq!(
+        row_text(&page.middle_rows[1]),
+        " Regeringen vill skärpa straffen  106"
+    );
+    assert_eq!(row_links(&page.middle_rows[1]), vec!["106_0001.htm"]);
+    assert_eq!(
+        row_links(&page.middle_rows[4]),
+        vec!["101_0001.htm", "104_0001.htm", "300_0001.htm"]
+    );
+    // Four digit numbers are not links
+    assert!(row_links(&page.middle_rows[9]).is_empty());
+    assert_eq!(row_links(&page.middle_rows[10]), vec!["130_0001.htm"]);
+
+    // There's no previous page for 100
+    assert!(matches!(page.page_navigation[0], HtmlItem::Text(_)));
+    match &page.page_navigation[3] {
+        HtmlItem::Link(link) => assert_eq!(link.url, "101_0001.htm"),
+        HtmlItem::Text(_) => panic!("next page should be a link"),
+    }
+
+    assert_eq!(page.bottom_navigation.len(), 6);
+    assert_eq!(
+        page.fastext.get(FastextKey::Red).unwrap().url,
+        "101_0001.htm"
+    );
+    assert_eq!(
+        page.fastext.get(FastextKey::Index).unwrap().url,
+        "100_0001.htm"
+    );
+}
+
+#[test]
+fn svt_text_missing_page() {
+    let loader = HtmlLoader::new("tests/fixtures/svt_text/999.json");
+    let err = SvtText::new().parse(loader).unwrap_err();
+    assert_eq!(err, ParseErr::NotFound);
+}
+
+#[test]
+fn dr_text_page() {
+    let loader = HtmlLoader::new("tests/fixtures/dr_text/100.htm");
+    let page = DrText::new().parse(loader).unwrap();
+    let page = page.teletext();
+
+    assert_eq!(page.title, "DR TEKST-TV   tir 17 jan 12:34");
+    assert_eq!(page.middle_rows.len(), 6);
+    assert_eq!(row_text(&page.middle_rows[0]), "  NYHEDER");
+    assert_eq!(
+        row_text(&page.middle_rows[2]),
+        "  Stormvarsel for hele Jylland & Fyn 110"
+    );
+    assert_eq!(row_links(&page.middle_rows[2]), vec!["110_0001.htm"]);
+    assert!(page.middle_rows[3].is_empty());
+    // Links outside of the page range are shown as texts
+    assert!(row_links(&page.middle_rows[5]).is_empty());
+
+    // 99 is not a valid page
+    assert!(matches!(page.page_navigation[0], HtmlItem::Text(_)));
+    match &page.page_navigation[3] {
+        HtmlItem::Link(link) => assert_eq!(link.url, "101_0001.htm"),
+        HtmlItem::Text(_) => panic!("next page should be a link"),
+    }
+    assert_eq!(
+        page.fastext.get(FastextKey::Index).unwrap().inner_text,
+        "Tekst-TV"
+    );
+}
+
+fn providers() -> ProvidersFile {
+    let data = std::fs::read_to_string("tests/fixtures/providers/providers.toml").unwrap();
+    ProvidersFile::from_toml(&data).unwrap()
+}
+
+#[test]
+fn provider_html_page() {
+    let providers = providers();
+    let provider = &providers.provider[0];
+    assert_eq!(
+        provider.page_url(100, 1),
+        "https://www.dr.dk/cgi-bin/fttv1.exe/100"
+    );
+
+    let page = ProviderPage {
+        provider,
+        page: 100,
+        sub_page: 1,
+    };
+    let loader = HtmlLoader::new("tests/fixtures/dr_text/100.htm");
+    let page = page.parse_text(loader).unwrap();
+
+    assert_eq!(page.title, "DR Tekst-TV 100");
+    assert_eq!(page.middle_rows.len(), 7);
+    assert_eq!(
+        row_text(&page.middle_rows[3]),
+        "  Stormvarsel for hele Jylland & Fyn 110"
+    );
+    assert_eq!(row_links(&page.middle_rows[3]), vec!["110_0001.htm"]);
+    // Links to pages that cannot exist are shown as text
+    assert!(row_links(&page.middle_rows[6]).is_empty());
+
+    // There's no previous sub page for the first sub page
+    assert!(matches!(page.page_navigation[1], HtmlItem::Text(_)));
+    assert_eq!(page.bottom_navigation.len(), 3);
+    assert_eq!(
+        page.fastext.get(FastextKey::Green).unwrap().url,
+        "200_0001.htm"
+    );
+    assert_eq!(
+        page.fastext.get(FastextKey::Index).unwrap().url,
+        "100_0001.htm"
+    );
+}
+
+#[test]
+fn provider_json_page() {
+    let providers = providers();
+    let page = ProviderPage {
+        provider: &providers.provider[1],
+        page: 100,
+        sub_page: 1,
+    };
+    let loader = HtmlLoader::new("tests/fixtures/svt_text/100.json");
+    let page = page.parse_text(loader).unwrap();
+
+    // Title defaults to the provider name
+    assert_eq!(page.title, "SVT Text");
+    assert_eq!(page.middle_rows.len(), 3);
+
+    let loader = HtmlLoader::new("tests/fixtures/svt_text/999.json");
+    let page = ProviderPage {
+        provider: &providers.provider[1],
+        page: 999,
+        sub_page: 1,
+    };
+    assert!(page.parse_text(loader).is_err());
+}
@@ -0,0 +1,31 @@
+//! Plain text renderings of the fixture pages. The snapshots are in `tests/fixtures/snapshots`
+mod common;
+
+use common::assert_snapshot;
+use yle_tekstitv_gui::parser::{DrText, HtmlLoader, HtmlParser, SvtText, TeleText, TextPage};
+
+fn yle_text(file: &str) -> String {
+    let loader = HtmlLoader::new(&format!("tests/fixtures/yle_text/{file}.htm"));
+    TeleText::new().parse(loader).unwrap().to_plain_text()
+}
+
+#[test]
+fn yle_text_snapshots() {
+    for file in ["100", "190_0002", "899"] {
+        assert_snapshot(&format!("yle_text_{file}"), &yle_text(file));
+    }
+}
+
+#[test]
+fn svt_text_snapshot() {
+    let loader = HtmlLoader::new("tests/fixtures/svt_text/100.json");
+    let page = SvtText::new().parse(loader).unwrap();
+    assert_snapshot("svt_text_100", &page.teletext().to_plain_text());
+}
+
+#[test]
+fn dr_text_snapshot() {
+    let loader = HtmlLoader::new("tests/fixtures/dr_text/100.htm");
+    let page = DrText::new().parse(loader).unwrap();
+    assert_snapshot("dr_text_100", &page.teletext().to_plain_text());
+}
@@ -0,0 +1,65 @@
+use yle_tekstitv_gui::parser::common::ParseErr;
+use yle_tekstitv_gui::parser::{FastextKey, HtmlLoader, HtmlParser, YleImage};
+
+#[test]
+fn yle_image_page() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_image/100.json");
+    let page = YleImage::new().parse(loader).unwrap();
+
+    assert_eq!(page.title, "100/1");
+    assert_eq!(page.image, vec![137, 80, 78, 71, 13, 10, 26, 10]);
+
+    // Areas without a page are ignored
+    assert_eq!(page.image_map.len(), 2);
+    assert_eq!(page.image_map[1].link, "200_0001");
+    assert_eq!(page.image_map[1].y2, 40.0);
+
+    assert_eq!(page.botton_navigation.len(), 4);
+    assert!(page.botton_navigation[0].is_none());
+    let next_sub_page = page.botton_navigation[2].as_ref().unwrap();
+    assert_eq!(next_sub_page.url, "100_0002");
+    assert_eq!(next_sub_page.inner_text, "Seuraava alasivu");
+    let next_page = page.botton_navigation[3].as_ref().unwrap();
+    assert_eq!(next_page.inner_text, "Seuraava sivu");
+}
+
+#[test]
+fn yle_image_multiple_sub_pages() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_image/190_0002.json");
+    let page = YleImage::new().parse(loader).unwrap();
+
+    assert_eq!(page.title, "190/2");
+    assert_eq!(page.text, "JÄÄKIEKKO 190");
+    let urls: Vec<&str> = page
+        .botton_navigation
+        .iter()
+        .map(|link| link.as_ref().unwrap().url.as_ref())
+        .collect();
+    assert_eq!(urls, vec!["189_0001", "190_0001", "190_0003", "191_0001"]);
+    assert_eq!(
+        page.botton_navigation[1].as_ref().unwrap().inner_text,
+        "Edellinen alasivu"
+    );
+
+    assert_eq!(page.image_map.len(), 2);
+    assert_eq!(page.image_map[0].link, "192_0001");
+    assert!(page.image_map[0].in_area(20.0, 210.0, 1.0));
+
+    // Navigation is used as fastext
+    assert_eq!(page.fastext.get(FastextKey::Red).unwrap().url, "189_0001");
+    assert_eq!(page.fastext.get(FastextKey::Green).unwrap().url, "191_0001");
+}
+
+#[test]
+fn yle_image_not_found_page() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_image/404.json");
+    let err = YleImage::new().parse(loader).unwrap_err();
+    assert_eq!(err, ParseErr::NotFound);
+}
+
+#[test]
+fn yle_image_error_page() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_image/error.json");
+    let err = YleImage::new().parse(loader).unwrap_err();
+    assert_eq!(err, ParseErr::Maintenance);
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::{row_links, row_text};
+use yle_tekstitv_gui::parser::common::ParseErr;
+use yle_tekstitv_gui::parser::{
+    FastextKey, HtmlItem, HtmlLoader, HtmlParser, TeleText, TextPage, YleText,
+};
+
+#[test]
+fn yle_text_page() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/100.htm");
+    let page = TeleText::new().parse(loader).unwrap();
+
+    assert_eq!(page.title, "YLE TEKSTI-TV");
+    assert_eq!(page.page_navigation.len(), 4);
+    assert!(matches!(page.page_navigation[1], HtmlItem::Text(_)));
+    assert_eq!(
+        row_links(&page.page_navigation),
+        vec!["099_0001.htm", "100_0002.htm", "101_0001.htm"]
+    );
+
+    assert_eq!(page.middle_rows.len(), 6);
+    assert!(page.middle_rows[0].is_empty());
+    assert_eq!(
+        row_text(&page.middle_rows[2]),
+        " 103 Myrskyvaroitus & tulvat"
+    );
+    assert_eq!(row_links(&page.middle_rows[2]), vec!["103_0001.htm"]);
+    assert!(page.middle_rows[3].is_empty());
+    // Links outside teletext are shown as text
+    assert_eq!(row_text(&page.middle_rows[5]), " Katso yle.fi verkosta");
+    assert!(row_links(&page.middle_rows[5]).is_empty());
+
+    assert_eq!(row_text(&page.sub_pages), "1 2 3");
+    assert_eq!(
+        row_links(&page.sub_pages),
+        vec!["100_0001.htm", "100_0002.htm"]
+    );
+    assert_eq!(page.bottom_navigation.len(), 6);
+    assert_eq!(
+        page.fastext.get(FastextKey::Index).unwrap().inner_text,
+        "Teksti-TV"
+    );
+}
+
+#[test]
+fn yle_text_page_markup_variations() {
+    let html = std::fs::read_to_string("tests/fixtures/yle_text/100.htm").unwrap();
+    // Case, attribute order, quotes and whitespace don't change the page
+    let html = html
+        .replace("<big>", "<BIG class=\"title\" >")
+        .replace("<SPAN CLASS=\"nav\">", "<span\n  id=nav class='nav'>")
+        .replace(
+            "<a href=\"102_0001.htm\">",
+            "<A target=\"_self\" HREF='102_0001.htm'>",
+        )
+        .replace("<pre>", "<!-- rows --><PRE class=\"ttv\">")
+        .replace("</pre>", "</PRE >");
+    let page = TeleText::new()
+        .parse(HtmlLoader {
+            page_data: html.into(),
+        })
+        .unwrap();
+
+    assert_eq!(page.title, "YLE TEKSTI-TV");
+    assert_eq!(page.page_navigation.len(), 4);
+    assert_eq!(page.middle_rows.len(), 6);
+    assert_eq!(row_links(&page.middle_rows[1]), vec!["102_0001.htm"]);
+    assert_eq!(page.bottom_navigation[0].url, "102_0001.htm");
+}
+
+#[test]
+fn yle_text_multiple_sub_pages() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/190_0002.htm");
+    let page = TeleText::new().parse(loader).unwrap();
+
+    assert_eq!(page.title, "JÄÄKIEKKO");
+    // Both sub pages and pages can be navigated to
+    assert_eq!(
+        row_links(&page.page_navigation),
+        vec![
+            "189_0001.htm",
+            "190_0001.htm",
+            "190_0003.htm",
+            "191_0001.htm"
+        ]
+    );
+
+    assert_eq!(page.middle_rows.len(), 8);
+    assert_eq!(
+        row_text(&page.middle_rows[4]),
+        " HIFK - Kärpät                1-3"
+    );
+    assert!(row_links(&page.middle_rows[4]).is_empty());
+    assert_eq!(
+        row_links(&page.middle_rows[7]),
+        vec!["192_0001.htm", "193_0001.htm"]
+    );
+
+    // Current sub page is a text
+    assert_eq!(row_text(&page.sub_pages), "1 2 3 4");
+    assert_eq!(
+        row_links(&page.sub_pages),
+        vec!["190_0001.htm", "190_0003.htm", "190_0004.htm"]
+    );
+    assert_eq!(page.sub_page_count(), Some(4));
+}
+
+#[test]
+fn yle_text_missing_links() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/899.htm");
+    let page = TeleText::new().parse(loader).unwrap();
+
+    assert_eq!(page.title, "TEKSTI-TV:N SIVUT");
+    // Last page has no next page or sub pages
+    assert_eq!(row_links(&page.page_navigation), vec!["898_0001.htm"]);
+    assert!(matches!(page.page_navigation[3], HtmlItem::Text(_)));
+    assert_eq!(row_text(&page.page_navigation[3..]), "Seuraava sivu");
+    assert!(page.sub_pages.is_empty());
+    assert_eq!(page.sub_page_count(), None);
+
+    assert_eq!(page.middle_rows.len(), 4);
+    assert!(page.middle_rows.iter().all(|row| row_links(row).is_empty()));
+    assert_eq!(
+        page.fastext.get(FastextKey::Green).unwrap().url,
+        "130_0001.htm"
+    );
+}
+
+#[test]
+fn yle_text_not_found_page() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/404.htm");
+    let err = TeleText::new().parse(loader).unwrap_err();
+    assert_eq!(err, ParseErr::NotFound);
+}
+
+#[test]
+fn yle_text_error_page() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/error.htm");
+    let err = TeleText::new().parse(loader).unwrap_err();
+    assert_eq!(err, ParseErr::Maintenance);
+}
+
+#[test]
+fn yle_text_borrowed_page() {
+    for file in ["100.htm", "190_0002.htm", "899.htm"] {
+        let html = std::fs::read_to_string(format!("tests/fixtures/yle_text/{file}")).unwrap();
+        let borrowed = TeleText::parse_str(&html).unwrap();
+        let loader = HtmlLoader {
+            page_data: html.as_str().into(),
+        };
+        let owned = TeleText::new().parse(loader).unwrap();
+        // Page of the GUI borrows the html it keeps
+        let loader = HtmlLoader {
+            page_data: html.as_str().into(),
+        };
+        let shared = <YleText as HtmlParser>::new().parse(loader).unwrap();
+
+        assert_eq!(borrowed.to_plain_text(), owned.to_plain_text(), "{}", file);
+        assert_eq!(
+            borrowed.to_plain_text(),
+            shared.teletext().to_plain_text(),
+            "{}",
+            file
+        );
+    }
+}
+
+#[test]
+fn yle_text_changed_rows() {
+    let html = std::fs::read_to_string("tests/fixtures/yle_text/190_0002.htm").unwrap();
+    let previous = TeleText::parse_str(&html).unwrap();
+    assert!(previous.changed_rows(&previous).is_empty());
+
+    // Score of one game changes in a refresh
+    let updated = html.replace(
+        "HIFK - Kärpät                1-3",
+        "HIFK - Kärpät                2-3",
+    );
+    let page = TeleText::parse_str(&updated).unwrap();
+    assert_eq!(page.changed_rows(&previous), vec![4]);
+
+    // Rows that only exist in the new version are changed unless they're empty
+    let mut shorter = TeleText::parse_str(&html).unwrap();
+    shorter.middle_rows.truncate(5);
+    assert_eq!(page.changed_rows(&shorter), vec![4, 5, 7]);
+}
+
+#[test]
+fn yle_text_middle_texts() {
+    let loader = HtmlLoader::new("tests/fixtures/yle_text/100.htm");
+    let page = TeleText::new().parse(loader).unwrap();
+
+    let texts = page.middle_texts();
+    assert_eq!(texts.len(), page.middle_rows.len());
+    assert_eq!(texts[0], "");
+    assert_eq!(texts[2], " 103 Myrskyvaroitus & tulvat");
+    assert_eq!(texts[5], " Katso yle.fi verkosta");
+}